@@ -0,0 +1,47 @@
+use rusty_edges::graph::Graph;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// Plain timing harness, run with `cargo bench --features rayon`
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    f(); // Warm up
+    let mut runs = 0;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(1) {
+        f();
+        runs += 1;
+    }
+    println!("{:<32} {:>12.3?} per run", name, start.elapsed() / runs);
+}
+
+// Sparse graph where every node has out-degree 1 to 3
+fn sparse(n: usize) -> Graph<usize> {
+    let mut g = Graph::init(0..n);
+    for i in 0..n {
+        for step in 1..=(i % 3 + 1) {
+            g.connect(&i, &((i * 7 + step * 13) % n));
+        }
+    }
+    g
+}
+
+fn main() {
+    let g = sparse(500_000);
+    bench("components 500k", || {
+        black_box(g.components().len());
+    });
+    for threads in [1, 2, 4, 8] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            bench(&format!("par_components 500k x{}", threads), || {
+                black_box(g.par_components().len());
+            });
+            bench(&format!("par_distances 500k x{}", threads), || {
+                black_box(g.par_distances(&0).unwrap().len());
+            });
+        });
+    }
+}
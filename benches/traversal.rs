@@ -0,0 +1,66 @@
+use rusty_edges::graph::Graph;
+use rusty_edges::iter::TraversalScratch;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// Plain timing harness, run with `cargo bench`
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    f(); // Warm up
+    let mut runs = 0;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(1) {
+        f();
+        runs += 1;
+    }
+    println!("{:<32} {:>12.3?} per run", name, start.elapsed() / runs);
+}
+
+// Sparse graph where every node has out-degree 1 to 3
+fn sparse(n: usize) -> Graph<usize> {
+    let mut g = Graph::init(0..n);
+    for i in 0..n {
+        for step in 1..=(i % 3 + 1) {
+            g.connect(&i, &((i * 7 + step * 13) % n));
+        }
+    }
+    g
+}
+
+fn main() {
+    bench("build sparse 100k", || {
+        black_box(sparse(100_000));
+    });
+
+    bench("build + remove 1k sparse 100k", || {
+        let mut g = sparse(100_000);
+        for i in 0..1_000 {
+            g.remove(&(i * 97));
+        }
+        black_box(g);
+    });
+
+    let g = sparse(100_000);
+    bench("bfs sparse 100k", || {
+        black_box(g.bfs(&0).count());
+    });
+    bench("dfs sparse 100k", || {
+        black_box(g.dfs(&0).count());
+    });
+    bench("bfs_indexed sparse 100k", || {
+        black_box(g.bfs_indexed(0).count());
+    });
+    bench("10k small dfs", || {
+        for start in 0..10_000 {
+            black_box(g.dfs(&start).take(16).count());
+        }
+    });
+    let mut scratch = TraversalScratch::new();
+    bench("10k small dfs_with", || {
+        for start in 0..10_000 {
+            black_box(g.dfs_with(&mut scratch, &start).take(16).count());
+        }
+    });
+    bench("edges sparse 100k", || {
+        black_box(g.edges().count());
+    });
+}
@@ -0,0 +1,117 @@
+use crate::iter::{Mode, OrderingIter, PriorityOrderingIter, WalkIter};
+use crate::{graph::*, hash};
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+// Read-only operations shared by Graph and the views over it, so a
+// traversal written against this works on any of them
+pub trait GraphBase<T> {
+    fn contains(&self, label: &T) -> bool;
+
+    fn labels(&self) -> Vec<&T>;
+
+    // Empty if the node isn't present
+    fn neighbors(&self, label: &T) -> Vec<&T>;
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64>;
+
+    fn edges(&self) -> Vec<(&T, &T)> {
+        self.labels()
+            .into_iter()
+            .flat_map(|from| self.neighbors(from).into_iter().map(move |to| (from, to)))
+            .collect()
+    }
+
+    fn walk<'a>(&'a self, start: &'a T, mode: Mode) -> WalkIter<'a, T, Self>
+    where
+        Self: Sized,
+        T: Hash,
+    {
+        WalkIter::new(self, start, mode)
+    }
+
+    fn bfs<'a>(&'a self, start: &'a T) -> WalkIter<'a, T, Self>
+    where
+        Self: Sized,
+        T: Hash,
+    {
+        self.walk(start, Mode::Bredth)
+    }
+
+    fn dfs<'a>(&'a self, start: &'a T) -> WalkIter<'a, T, Self>
+    where
+        Self: Sized,
+        T: Hash,
+    {
+        self.walk(start, Mode::Depth)
+    }
+
+    fn ordering(&self) -> OrderingIter<'_, T, Self>
+    where
+        Self: Sized,
+        T: Hash,
+    {
+        OrderingIter::new(self)
+    }
+
+    fn ordering_by_priority<K, F>(&self, priority: F) -> PriorityOrderingIter<'_, T, K, F, Self>
+    where
+        Self: Sized,
+        T: Hash,
+        K: Ord,
+        F: Fn(&T) -> K,
+    {
+        PriorityOrderingIter::new(self, priority)
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for Graph<T> {
+    fn contains(&self, label: &T) -> bool {
+        Graph::contains(self, label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.nodes.values().map(|n| &n.label).collect()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        match self.get(label) {
+            Some(node) => node.edges.keys().map(|k| &self.nodes[k].label).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.get(from)?.edges.get(&hash(to)).copied()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::undo::UndoableGraph;
+
+    // Written once against the trait
+    fn reachable<T: Hash + Eq, G: GraphBase<T>>(graph: &G, start: &T) -> usize {
+        let start = graph.labels().into_iter().find(|l| *l == start).unwrap();
+        graph.bfs(start).count()
+    }
+
+    #[test]
+    fn generic_algorithms() {
+        let g = Graph::path_graph(5);
+        assert_eq!(reachable(&g, &1), 4);
+        assert_eq!(reachable(&g.reversed(), &1), 2);
+        assert_eq!(reachable(&g.subgraph_view(&[0, 1, 3, 4]), &0), 2);
+
+        let u = UndoableGraph::from(Graph::cycle_graph(3));
+        assert_eq!(reachable(&u, &2), 3);
+
+        let r = g.reversed();
+        let order = r.ordering().collect::<Vec<_>>();
+        assert_eq!(order, vec![&4, &3, &2, &1, &0]);
+        assert_eq!(GraphBase::edges(&g).len(), 4);
+        assert_eq!(g.reversed().weight(&1, &0), Some(1));
+        assert_eq!(g.reversed().weight(&0, &1), None);
+    }
+}
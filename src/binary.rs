@@ -0,0 +1,159 @@
+use crate::{graph::*, hash};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Layout is MAGIC, VERSION, postcard payload, then an FNV-1a checksum of
+// the payload. Node keys aren't stored since DefaultHasher output isn't
+// stable across Rust releases, labels are rehashed on load instead
+const MAGIC: &[u8; 4] = b"GRST";
+const VERSION: u8 = 1;
+const HEADER: usize = 5;
+const CHECKSUM: usize = 8;
+
+#[derive(Debug, PartialEq)]
+pub enum BinaryError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    ChecksumMismatch,
+    Decode(String),
+}
+
+impl<T: Serialize> Graph<T> {
+    // Fails if a label can't be serialized
+    pub fn to_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
+        let keys = self.nodes.keys().collect::<Vec<_>>();
+        let index = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (**k, i as u32))
+            .collect::<HashMap<_, _>>();
+        let labels = keys
+            .iter()
+            .map(|k| &self.nodes[k].label)
+            .collect::<Vec<_>>();
+        let edges = keys
+            .iter()
+            .flat_map(|k| {
+                let from = index[k];
+                self.nodes[k].edges.iter().map(move |(t, w)| (from, t, *w))
+            })
+            .map(|(from, t, w)| (from, index[t], w))
+            .collect::<Vec<_>>();
+
+        let payload = postcard::to_allocvec(&(labels, edges))?;
+        let mut bytes = Vec::with_capacity(HEADER + payload.len() + CHECKSUM);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&payload);
+        bytes.extend_from_slice(&fnv1a(&payload).to_le_bytes());
+        Ok(bytes)
+    }
+}
+
+impl<T: DeserializeOwned + Hash + Eq> Graph<T> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BinaryError> {
+        if bytes.len() < HEADER + CHECKSUM || &bytes[..4] != MAGIC {
+            return Err(BinaryError::BadMagic);
+        }
+        if bytes[4] != VERSION {
+            return Err(BinaryError::UnsupportedVersion(bytes[4]));
+        }
+        let (payload, checksum) = bytes[HEADER..].split_at(bytes.len() - HEADER - CHECKSUM);
+        if fnv1a(payload).to_le_bytes() != checksum {
+            return Err(BinaryError::ChecksumMismatch);
+        }
+
+        let (labels, edges): (Vec<T>, Vec<(u32, u32, i64)>) =
+            postcard::from_bytes(payload).map_err(|e| BinaryError::Decode(e.to_string()))?;
+        let keys = labels.iter().map(hash).collect::<Vec<_>>();
+        let mut graph = Graph::with_capacity(labels.len());
+        for label in labels {
+            graph.add(label);
+        }
+        for (from, to, weight) in edges {
+            let (from, to) = match (keys.get(from as usize), keys.get(to as usize)) {
+                (Some(from), Some(to)) => (from, to),
+                _ => return Err(BinaryError::Decode("edge out of range".to_owned())),
+            };
+            graph.set_edge(*from, *to, Some(weight));
+        }
+        Ok(graph)
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut g = Graph::init(["a".to_owned(), "b".to_owned(), "c".to_owned()]);
+        assert!(g.connect(&"a".to_owned(), &"b".to_owned()));
+        assert!(g.biconnect(&"b".to_owned(), &"c".to_owned()));
+        let key = *g.nodes.keys().next().unwrap();
+        let targets = g.nodes[&key].edges.keys().copied().collect::<Vec<_>>();
+        for target in targets {
+            g.set_edge(key, target, Some(7));
+        }
+
+        let bytes = g.to_bytes().unwrap();
+        let h = Graph::<String>::from_bytes(&bytes).unwrap();
+        assert_eq!(h.size(), 3);
+        assert_eq!(h.edges().count(), 3);
+        for edge in g.edges() {
+            assert!(h
+                .edges()
+                .any(|e| (e.from, e.to, e.weight) == (edge.from, edge.to, edge.weight)));
+        }
+    }
+
+    #[test]
+    fn corruption() {
+        let bytes = Graph::path_graph(4).to_bytes().unwrap();
+
+        let mut flipped = bytes.clone();
+        flipped[HEADER + 1] ^= 1;
+        assert_eq!(
+            Graph::<usize>::from_bytes(&flipped).unwrap_err(),
+            BinaryError::ChecksumMismatch
+        );
+
+        let mut versioned = bytes.clone();
+        versioned[4] = 9;
+        assert_eq!(
+            Graph::<usize>::from_bytes(&versioned).unwrap_err(),
+            BinaryError::UnsupportedVersion(9)
+        );
+
+        assert_eq!(
+            Graph::<usize>::from_bytes(b"nope").unwrap_err(),
+            BinaryError::BadMagic
+        );
+        assert!(Graph::<usize>::from_bytes(&bytes).is_ok());
+    }
+
+    // A label that always refuses to serialize
+    #[derive(PartialEq, Eq, Hash)]
+    struct Opaque;
+
+    impl Serialize for Opaque {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("opaque"))
+        }
+    }
+
+    #[test]
+    fn unserializable() {
+        let mut g = Graph::new();
+        g.add(Opaque);
+        assert!(g.to_bytes().is_err());
+        assert!(Graph::<Opaque>::new().to_bytes().is_ok());
+    }
+}
@@ -0,0 +1,138 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// The clock is only read every so many checks, cancellation every time
+const CLOCK_EVERY: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    Timeout,
+    Cancelled,
+}
+
+// What was found before the search was interrupted
+#[derive(Debug, PartialEq)]
+pub struct Partial<R> {
+    pub reason: Interrupt,
+    pub found: R,
+}
+
+// Shared flag to stop a search from another thread
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Limits on a long search, checked by the search as it goes. Once the
+// deadline has been seen to pass every later check times out too
+#[derive(Debug, Default)]
+pub struct Budget {
+    deadline: Option<Instant>,
+    token: Option<CancelToken>,
+    checks: Cell<u32>,
+    expired: Cell<bool>,
+}
+
+impl Budget {
+    pub fn unlimited() -> Self {
+        Default::default()
+    }
+
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Budget {
+            deadline: Instant::now().checked_add(timeout),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Budget {
+            deadline: Some(deadline),
+            ..Default::default()
+        }
+    }
+
+    pub fn and_token(self, token: CancelToken) -> Self {
+        Budget {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    pub fn check(&self) -> Result<(), Interrupt> {
+        let checks = self.checks.get();
+        self.checks.set(checks.wrapping_add(1));
+        self.check_clock(checks.is_multiple_of(CLOCK_EVERY))
+    }
+
+    // Reads the clock every time, for searches whose steps are too big to
+    // only look every CLOCK_EVERY checks
+    pub fn check_now(&self) -> Result<(), Interrupt> {
+        self.check_clock(true)
+    }
+
+    fn check_clock(&self, read: bool) -> Result<(), Interrupt> {
+        if self.token.as_ref().is_some_and(CancelToken::is_cancelled) {
+            return Err(Interrupt::Cancelled);
+        }
+        if self.expired.get() {
+            return Err(Interrupt::Timeout);
+        }
+        match self.deadline {
+            Some(deadline) if read && Instant::now() >= deadline => {
+                self.expired.set(true);
+                Err(Interrupt::Timeout)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget() {
+        let unlimited = Budget::unlimited();
+        assert!((0..1000).all(|_| unlimited.check().is_ok()));
+
+        let expired = Budget::with_timeout(Duration::ZERO);
+        assert_eq!(expired.check(), Err(Interrupt::Timeout));
+
+        let token = CancelToken::new();
+        let budget = Budget::with_timeout(Duration::from_secs(3600)).and_token(token.clone());
+        assert_eq!(budget.check(), Ok(()));
+        token.cancel();
+        assert_eq!(budget.check(), Err(Interrupt::Cancelled));
+
+        // check skips the clock between every CLOCK_EVERY calls, but a
+        // timeout once seen stays reported
+        let passed = Budget::with_deadline(Instant::now());
+        assert_eq!(passed.check(), Err(Interrupt::Timeout));
+        assert!((0..CLOCK_EVERY * 2).all(|_| passed.check() == Err(Interrupt::Timeout)));
+        assert_eq!(passed.check_now(), Err(Interrupt::Timeout));
+
+        // Only the skipped reads can miss it the first time round
+        let late = Budget::with_deadline(Instant::now() + Duration::from_millis(20));
+        assert_eq!(late.check(), Ok(()));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(late.check(), Ok(()));
+        assert_eq!(late.check_now(), Err(Interrupt::Timeout));
+        assert_eq!(late.check(), Err(Interrupt::Timeout));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::graph::*;
+use std::fmt::{Display, Write};
+use std::hash::Hash;
+
+impl<T: Hash + Eq + Ord + Display> Graph<T> {
+    // One line per node in label order, listing its targets in label order
+    // as `from: to, to=weight`, with weights of 1 left off. Backslashes,
+    // colons, commas, equals signs and newlines in labels are escaped with
+    // a backslash, so different graphs never give the same string. The
+    // same graph always gives the same string however it was built, so
+    // it's safe to compare against a stored snapshot
+    pub fn canonical_string(&self) -> String {
+        self.render(|key| &self.nodes[&key].label)
+    }
+}
+
+impl<T: Hash + Eq + Display> Graph<T> {
+    // Same format as canonical_string for labels that aren't Ord, with
+    // nodes and targets ordered by structural hash instead. None if the
+    // graph has a cycle
+    pub fn canonical_string_by_hash(&self) -> Option<String> {
+        let hashes = self.structural_keys()?;
+        Some(self.render(|key| (hashes[&key], key)))
+    }
+
+    fn render<K: Ord, F: Fn(u64) -> K>(&self, order: F) -> String {
+        let mut nodes = self.nodes.iter().collect::<Vec<_>>();
+        nodes.sort_by_cached_key(|(k, _)| order(**k));
+        let mut out = String::new();
+        for (_, node) in nodes {
+            let mut edges = node
+                .edges
+                .iter()
+                .map(|(t, w)| (order(*t), *t, *w))
+                .collect::<Vec<_>>();
+            edges.sort_unstable_by(|a, b| (&a.0, a.2).cmp(&(&b.0, b.2)));
+            write!(out, "{}:", escape(&node.label)).unwrap();
+            for (i, (_, to, weight)) in edges.into_iter().enumerate() {
+                let separator = if i == 0 { " " } else { ", " };
+                let to = escape(&self.nodes[&to].label);
+                match weight {
+                    1 => write!(out, "{}{}", separator, to),
+                    _ => write!(out, "{}{}={}", separator, to, weight),
+                }
+                .unwrap();
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn escape<T: Display>(label: &T) -> String {
+    let mut escaped = String::new();
+    for c in label.to_string().chars() {
+        match c {
+            '\\' | ':' | ',' | '=' => escaped.extend(['\\', c]),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::{self, Formatter};
+
+    #[test]
+    fn canonical_string() {
+        let mut a = Graph::init(vec!["web", "api", "db", "cache"]);
+        let mut b = Graph::init(vec!["cache", "db", "api", "web"]);
+        for g in [&mut a, &mut b] {
+            for (from, to, weight) in [
+                ("web", "api", 1),
+                ("api", "db", 3),
+                ("api", "cache", 1),
+                ("db", "db", -1),
+            ] {
+                assert!(g.connect_weighted(&from, &to, weight));
+            }
+        }
+        let expected = "api: cache, db=3\ncache:\ndb: db=-1\nweb: api\n";
+        assert_eq!(a.canonical_string(), expected);
+        assert_eq!(b.canonical_string(), expected);
+        assert_eq!(Graph::<u8>::new().canonical_string(), "");
+    }
+
+    #[test]
+    fn escaping() {
+        // Unescaped, both of these would read "a: b, c=2"
+        let mut one = Graph::init(vec!["a", "b, c=2"]);
+        assert!(one.connect(&"a", &"b, c=2"));
+        let mut two = Graph::init(vec!["a", "b", "c"]);
+        assert!(two.connect(&"a", &"b"));
+        assert!(two.connect_weighted(&"a", &"c", 2));
+        assert_eq!(one.canonical_string(), "a: b\\, c\\=2\nb\\, c\\=2:\n");
+        assert_eq!(two.canonical_string(), "a: b, c=2\nb:\nc:\n");
+
+        let g = Graph::init(vec!["x:\ny", "x\\"]);
+        assert_eq!(g.canonical_string(), "x\\:\\ny:\nx\\\\:\n");
+    }
+
+    #[derive(PartialEq, Eq, Hash)]
+    struct Name(&'static str);
+
+    impl Display for Name {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    #[test]
+    fn by_hash() {
+        let names = ["web", "api", "db", "cache"];
+        let build = |order: &[usize]| {
+            let mut g = Graph::new();
+            for i in order {
+                g.add(Name(names[*i]));
+            }
+            for (from, to, weight) in [(0, 1, 1), (1, 2, 3), (1, 3, 1)] {
+                assert!(g.connect_weighted(&Name(names[from]), &Name(names[to]), weight));
+            }
+            g
+        };
+        let a = build(&[0, 1, 2, 3]).canonical_string_by_hash().unwrap();
+        let b = build(&[3, 2, 1, 0]).canonical_string_by_hash().unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.lines().count(), 4);
+        assert!(a.contains("web: api\n") && a.contains("db:\n"));
+
+        let mut cyclic = build(&[0, 1, 2, 3]);
+        assert!(cyclic.connect(&Name("db"), &Name("web")));
+        assert!(cyclic.canonical_string_by_hash().is_none());
+    }
+}
@@ -0,0 +1,305 @@
+use crate::graph::*;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+type Scores = (HashMap<u64, f64>, HashMap<(u64, u64), f64>);
+
+impl<T: Hash + Eq> Graph<T> {
+    // Brandes' algorithm over unweighted directed edges
+    pub fn betweenness_centrality(&self) -> HashMap<&T, f64> {
+        self.betweenness_centrality_with_progress(|_, _| ())
+    }
+
+    // Reports the number of sources done out of the total after each one
+    pub fn betweenness_centrality_with_progress<P>(&self, mut progress: P) -> HashMap<&T, f64>
+    where
+        P: FnMut(usize, usize),
+    {
+        self.betweenness_from(self.nodes.keys().copied(), 1.0, &mut progress)
+    }
+
+    // Approximation using only `samples` source nodes drawn uniformly at
+    // random, scaled up to the full graph. The same seed always draws the
+    // same sources for the same labels
+    pub fn betweenness_centrality_sampled(&self, samples: usize, seed: u64) -> HashMap<&T, f64> {
+        let samples = samples.min(self.size());
+        if samples == 0 {
+            return self.betweenness_from(None, 1.0, &mut |_, _| ());
+        }
+        // Partial Fisher-Yates over the keys in a fixed order
+        let mut keys = self.nodes.keys().copied().collect::<Vec<_>>();
+        keys.sort_unstable();
+        let mut state = seed;
+        for i in 0..samples {
+            let j = i + (split_mix(&mut state) % (keys.len() - i) as u64) as usize;
+            keys.swap(i, j);
+        }
+        keys.truncate(samples);
+        let scale = self.size() as f64 / samples as f64;
+        self.betweenness_from(keys, scale, &mut |_, _| ())
+    }
+
+    fn betweenness_from<I>(
+        &self,
+        pivots: I,
+        scale: f64,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> HashMap<&T, f64>
+    where
+        I: IntoIterator<Item = u64>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.brandes(pivots, scale, progress)
+            .0
+            .into_iter()
+            .map(|(k, v)| (&self.nodes[&k].label, v))
+            .collect()
+    }
+
+    // Brandes' algorithm over unweighted directed edges, scoring edges by
+    // the shortest paths running along them
+    pub fn edge_betweenness_centrality(&self) -> HashMap<(&T, &T), f64> {
+        self.brandes(self.nodes.keys().copied(), 1.0, &mut |_, _| ())
+            .1
+            .into_iter()
+            .map(|((a, b), v)| ((&self.nodes[&a].label, &self.nodes[&b].label), v))
+            .collect()
+    }
+
+    // Node and edge scores from the given source nodes
+    fn brandes<I>(&self, pivots: I, scale: f64, progress: &mut dyn FnMut(usize, usize)) -> Scores
+    where
+        I: IntoIterator<Item = u64>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let pivots = pivots.into_iter();
+        let total = pivots.len();
+        let mut scores = self
+            .nodes
+            .keys()
+            .map(|k| (*k, 0.0))
+            .collect::<HashMap<_, _>>();
+        let mut edge_scores = self
+            .nodes
+            .iter()
+            .flat_map(|(k, n)| n.edges.keys().map(move |t| ((*k, *t), 0.0)))
+            .collect::<HashMap<_, _>>();
+
+        for (done, source) in pivots.enumerate() {
+            let mut stack = Vec::new();
+            let mut predecessors: HashMap<u64, Vec<u64>> = HashMap::new();
+            let mut paths: HashMap<u64, f64> = HashMap::new();
+            let mut distances: HashMap<u64, usize> = HashMap::new();
+            paths.insert(source, 1.0);
+            distances.insert(source, 0);
+
+            let mut buffer = VecDeque::new();
+            buffer.push_back(source);
+            while let Some(key) = buffer.pop_front() {
+                stack.push(key);
+                let distance = distances[&key];
+                for target in self.nodes[&key].edges.keys() {
+                    if !distances.contains_key(target) {
+                        distances.insert(*target, distance + 1);
+                        buffer.push_back(*target);
+                    }
+                    if distances[target] == distance + 1 {
+                        *paths.entry(*target).or_insert(0.0) += paths[&key];
+                        predecessors.entry(*target).or_default().push(key);
+                    }
+                }
+            }
+
+            let mut dependencies: HashMap<u64, f64> = HashMap::new();
+            while let Some(key) = stack.pop() {
+                let dependency = dependencies.get(&key).copied().unwrap_or(0.0);
+                for pred in predecessors.get(&key).into_iter().flatten() {
+                    let share = paths[pred] / paths[&key] * (1.0 + dependency);
+                    *dependencies.entry(*pred).or_insert(0.0) += share;
+                    *edge_scores.get_mut(&(*pred, key)).unwrap() += share * scale;
+                }
+                if key != source {
+                    *scores.get_mut(&key).unwrap() += dependency * scale;
+                }
+            }
+            progress(done + 1, total);
+        }
+
+        (scores, edge_scores)
+    }
+
+    // Based on outgoing distances, scaled by the fraction of the graph reachable
+    pub fn closeness_centrality(&self) -> HashMap<&T, f64> {
+        let n = self.size();
+        self.nodes
+            .iter()
+            .map(|(key, node)| {
+                let distances = self.hop_distances(*key);
+                let reached = distances.len() - 1;
+                let total: usize = distances.values().sum();
+                let score = if total == 0 || n < 2 {
+                    0.0
+                } else {
+                    (reached as f64 / total as f64) * (reached as f64 / (n - 1) as f64)
+                };
+                (&node.label, score)
+            })
+            .collect()
+    }
+}
+
+impl<T: Hash + Eq + Clone> Graph<T> {
+    // Sparsified copy keeping roughly keep_fraction of the edges, highest
+    // edge betweenness first. A spanning forest of the best edges goes in
+    // before anything else so weak connectivity is never lost, which can
+    // mean keeping more edges than asked for
+    pub fn backbone(&self, keep_fraction: f64) -> Graph<T> {
+        let mut edges = self
+            .brandes(self.nodes.keys().copied(), 1.0, &mut |_, _| ())
+            .1
+            .into_iter()
+            .collect::<Vec<_>>();
+        edges.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(a.cmp(b)));
+        let target = (edges.len() as f64 * keep_fraction.clamp(0.0, 1.0)).ceil() as usize;
+
+        // Kruskal over the edges with direction ignored
+        let mut roots = self
+            .nodes
+            .keys()
+            .map(|k| (*k, *k))
+            .collect::<HashMap<_, _>>();
+        // Iterative so long chains can't overflow the stack, pointing
+        // everything on the way straight at the root afterwards
+        fn find(roots: &mut HashMap<u64, u64>, key: u64) -> u64 {
+            let mut root = key;
+            while roots[&root] != root {
+                root = roots[&root];
+            }
+            let mut key = key;
+            while key != root {
+                key = roots.insert(key, root).unwrap();
+            }
+            root
+        }
+        let mut kept = Vec::new();
+        let mut rest = Vec::new();
+        for ((a, b), _) in edges {
+            let (x, y) = (find(&mut roots, a), find(&mut roots, b));
+            if x != y {
+                roots.insert(x, y);
+                kept.push((a, b));
+            } else {
+                rest.push((a, b));
+            }
+        }
+        let extra = target.saturating_sub(kept.len());
+        kept.extend(rest.into_iter().take(extra));
+
+        let mut backbone = Graph::with_capacity(self.size());
+        for node in self.nodes.values() {
+            backbone.add(node.label.clone());
+        }
+        for (a, b) in kept {
+            backbone.set_edge(a, b, self.nodes[&a].edges.get(&b).copied());
+        }
+        backbone
+    }
+}
+
+// SplitMix64, plenty for picking sources and needs no dependency
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn betweenness() {
+        // 0 -> 1 -> 2 -> 3
+        let g = Graph::path_graph(4);
+        let scores = g.betweenness_centrality();
+        assert_eq!(scores[&0], 0.0);
+        assert_eq!(scores[&1], 2.0);
+        assert_eq!(scores[&2], 2.0);
+        assert_eq!(scores[&3], 0.0);
+
+        let sampled = g.betweenness_centrality_sampled(4, 7);
+        assert_eq!(sampled, scores);
+        assert_eq!(g.betweenness_centrality_sampled(0, 7)[&1], 0.0);
+
+        // One source at a time, only paths from 0 pass through 1, so it
+        // scores 4 times 2 when 0 is drawn. A seed always draws the same one
+        let draws = (0..64)
+            .map(|seed| {
+                let one = g.betweenness_centrality_sampled(1, seed);
+                assert_eq!(one, g.betweenness_centrality_sampled(1, seed));
+                one[&1] as u64
+            })
+            .collect::<Vec<_>>();
+        assert!(draws.contains(&8) && draws.contains(&0));
+        let hits = draws.iter().filter(|d| **d == 8).count();
+        assert!((4..=32).contains(&hits));
+
+        let mut last = (0, 0);
+        let reported = g.betweenness_centrality_with_progress(|done, total| last = (done, total));
+        assert_eq!(reported, scores);
+        assert_eq!(last, (4, 4));
+    }
+
+    #[test]
+    fn edge_betweenness_and_backbone() {
+        // Two triangles joined by a single bridge 2 -> 3
+        let mut g = Graph::init(0..6);
+        for (a, b) in [(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 5), (5, 3)] {
+            assert!(g.connect(&a, &b));
+        }
+        let scores = g.edge_betweenness_centrality();
+        assert_eq!(scores.len(), 7);
+        let bridge = scores[&(&2, &3)];
+        assert!(scores.values().all(|s| *s <= bridge));
+        assert_eq!(bridge, 9.0);
+
+        let backbone = g.backbone(0.0);
+        assert_eq!(backbone.size(), 6);
+        assert_eq!(backbone.edges().count(), 5);
+        assert!(backbone.is_connected(&2, &3));
+        assert!(backbone.is_weakly_connected());
+
+        assert_eq!(g.backbone(0.9).edges().count(), 7);
+        assert_eq!(g.backbone(1.0).edges().count(), 7);
+    }
+
+    #[test]
+    fn betweenness_splits_shortest_paths() {
+        // a -> b -> d and a -> c -> d
+        let mut g = Graph::init('a'..='d');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'a', &'c'));
+        assert!(g.connect(&'b', &'d'));
+        assert!(g.connect(&'c', &'d'));
+
+        let scores = g.betweenness_centrality();
+        assert_eq!(scores[&'b'], 0.5);
+        assert_eq!(scores[&'c'], 0.5);
+        assert_eq!(scores[&'a'], 0.0);
+    }
+
+    #[test]
+    fn closeness() {
+        let g = Graph::star_graph(4);
+        let scores = g.closeness_centrality();
+        assert_eq!(scores[&0], 1.0);
+        assert_eq!(scores[&1], 0.0);
+
+        let g = Graph::path_graph(3);
+        let scores = g.closeness_centrality();
+        assert!((scores[&0] - 2.0 / 3.0).abs() < 1e-9);
+        assert!((scores[&1] - 0.5).abs() < 1e-9);
+    }
+}
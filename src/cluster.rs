@@ -0,0 +1,147 @@
+use crate::{graph::*, hash};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+
+impl<T: Hash + Eq> Graph<T> {
+    // Core index of every node with edge direction ignored
+    pub fn core_numbers(&self) -> HashMap<&T, usize> {
+        let undirected = self.undirected();
+        let mut degrees = undirected
+            .iter()
+            .map(|(k, n)| (*k, n.len()))
+            .collect::<HashMap<_, _>>();
+        let mut heap = degrees
+            .iter()
+            .map(|(k, d)| Reverse((*d, *k)))
+            .collect::<BinaryHeap<_>>();
+
+        let mut cores = HashMap::new();
+        let mut k = 0;
+        while let Some(Reverse((degree, key))) = heap.pop() {
+            if cores.contains_key(&key) || degrees[&key] != degree {
+                continue; // Stale entry
+            }
+            k = k.max(degree);
+            cores.insert(key, k);
+            for other in &undirected[&key] {
+                if !cores.contains_key(other) {
+                    let d = degrees.get_mut(other).unwrap();
+                    *d -= 1;
+                    heap.push(Reverse((*d, *other)));
+                }
+            }
+        }
+
+        cores
+            .into_iter()
+            .map(|(k, c)| (&self.nodes[&k].label, c))
+            .collect()
+    }
+
+    // Label propagation with edge direction ignored, ties go to the
+    // current label and then the smallest so the result is deterministic
+    pub fn communities(&self) -> Vec<HashSet<&T>> {
+        let undirected = self.undirected();
+        let mut order = undirected.keys().copied().collect::<Vec<_>>();
+        order.sort_unstable();
+        let mut labels = order.iter().map(|k| (*k, *k)).collect::<HashMap<_, _>>();
+
+        for _ in 0..100 {
+            let mut changed = false;
+            for key in &order {
+                let mut counts: HashMap<u64, usize> = HashMap::new();
+                for other in &undirected[key] {
+                    *counts.entry(labels[other]).or_insert(0) += 1;
+                }
+                let best = match counts.values().max() {
+                    Some(best) => *best,
+                    None => continue,
+                };
+                let current = labels[key];
+                if counts.get(&current) == Some(&best) {
+                    continue;
+                }
+                let label = counts
+                    .into_iter()
+                    .filter(|(_, c)| *c == best)
+                    .map(|(l, _)| l)
+                    .min()
+                    .unwrap();
+                labels.insert(*key, label);
+                changed = true;
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut communities: HashMap<u64, HashSet<&T>> = HashMap::new();
+        for (key, label) in labels {
+            communities
+                .entry(label)
+                .or_default()
+                .insert(&self.nodes[&key].label);
+        }
+        communities.into_values().collect()
+    }
+}
+
+impl<T: Hash + Eq + Clone> Graph<T> {
+    pub fn k_core(&self, k: usize) -> Graph<T> {
+        let keep = self
+            .core_numbers()
+            .into_iter()
+            .filter(|(_, c)| *c >= k)
+            .map(|(label, _)| hash(label))
+            .collect::<HashSet<_>>();
+        self.subgraph_keys(&keep)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cores() {
+        // Complete graph on 0..4 with a tail 0 - 4 - 5
+        let mut g = Graph::complete_graph(4);
+        g.add(4);
+        g.add(5);
+        assert!(g.connect(&0, &4));
+        assert!(g.connect(&4, &5));
+
+        let cores = g.core_numbers();
+        assert_eq!(cores[&0], 3);
+        assert_eq!(cores[&3], 3);
+        assert_eq!(cores[&4], 1);
+        assert_eq!(cores[&5], 1);
+
+        let core = g.k_core(2);
+        assert_eq!(core.size(), 4);
+        assert!(core.connections(&4).is_none());
+        assert_eq!(core.edges().count(), 12);
+
+        assert_eq!(g.k_core(0).size(), 6);
+        assert_eq!(g.k_core(4).size(), 0);
+    }
+
+    #[test]
+    fn communities() {
+        // Two triangles joined by a single edge, plus an isolated node
+        let mut g = Graph::init(0..7);
+        for (a, b) in [(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (2, 3)] {
+            assert!(g.biconnect(&a, &b));
+        }
+
+        let communities = g.communities();
+        assert_eq!(communities.len(), 3);
+        let find = |n| communities.iter().find(|c| c.contains(&n)).unwrap();
+        assert_eq!(find(0), find(1));
+        assert_eq!(find(0), find(2));
+        assert_eq!(find(3), find(5));
+        assert_ne!(find(0), find(3));
+        assert_eq!(find(6).len(), 1);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::graph::*;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+impl<T: Hash + Eq + Clone> Graph<T> {
+    // Contracts every maximal run of nodes with in- and outdegree 1 into
+    // the first node of the run, which takes over the edge out of the last
+    // one. Returns the labels absorbed into each remaining node, in order
+    pub fn collapse_chains(&mut self) -> HashMap<T, Vec<T>> {
+        let linked = |node: &Node<T>| node.incoming.len() == 1 && node.edges.len() == 1;
+        let next = |graph: &Self, key: u64| *graph.nodes[&key].edges.keys().next().unwrap();
+        let links = self
+            .nodes
+            .iter()
+            .filter(|(_, n)| linked(n))
+            .map(|(k, _)| *k)
+            .collect::<HashSet<_>>();
+
+        // Runs start wherever the previous node isn't part of one
+        let previous = |key: &u64| *self.nodes[key].incoming.keys().next().unwrap();
+        let mut starts = links
+            .iter()
+            .filter(|k| !links.contains(&previous(k)))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+        let mut runs = Vec::new();
+        loop {
+            let start = match starts.pop() {
+                Some(start) => start,
+                // Whatever's left is a cycle made up entirely of links
+                None => match links.iter().find(|k| !seen.contains(*k)) {
+                    Some(start) => *start,
+                    None => break,
+                },
+            };
+            let mut run = vec![start];
+            seen.insert(start);
+            let mut key = next(self, start);
+            while links.contains(&key) && seen.insert(key) {
+                run.push(key);
+                key = next(self, key);
+            }
+            runs.push(run);
+        }
+
+        let mut absorbed = HashMap::new();
+        for run in runs.into_iter().filter(|r| r.len() > 1) {
+            let last = *run.last().unwrap();
+            let exit = next(self, last);
+            let weight = self.nodes[&last].edges.get(&exit).copied();
+            let mut labels = Vec::new();
+            for key in &run[1..] {
+                labels.push(self.remove_node(*key).unwrap().0.label);
+            }
+            let (first, exit) = (run[0], if run.contains(&exit) { run[0] } else { exit });
+            self.set_edge(first, exit, weight);
+            absorbed.insert(self.nodes[&first].label.clone(), labels);
+        }
+        absorbed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chains() {
+        // a -> b -> c -> d -> e with a branch a -> x -> e
+        let mut g = Graph::init(['a', 'b', 'c', 'd', 'e', 'x'].iter().copied());
+        for (from, to) in [
+            ('a', 'b'),
+            ('b', 'c'),
+            ('c', 'd'),
+            ('d', 'e'),
+            ('a', 'x'),
+            ('x', 'e'),
+        ] {
+            assert!(g.connect(&from, &to));
+        }
+        let absorbed = g.collapse_chains();
+        assert_eq!(absorbed.len(), 1);
+        assert_eq!(absorbed[&'b'], vec!['c', 'd']);
+        assert_eq!(g.size(), 4);
+        assert!(g.is_connected(&'b', &'e'));
+        assert_eq!(g.indegree(&'e'), Some(2));
+        assert!(g.collapse_chains().is_empty());
+
+        // A bare cycle shrinks to one node looping back on itself
+        let mut g = Graph::cycle_graph(5);
+        let absorbed = g.collapse_chains();
+        assert_eq!(g.size(), 1);
+        assert_eq!(absorbed.values().next().unwrap().len(), 4);
+        let label = *absorbed.keys().next().unwrap();
+        assert!(g.is_connected(&label, &label));
+    }
+}
@@ -0,0 +1,259 @@
+use crate::graph::*;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+impl<T: Hash + Eq> Graph<T> {
+    // Fewest vertex-disjoint paths covering a DAG, None if there is a cycle
+    pub fn minimum_path_cover(&self) -> Option<Vec<Vec<&T>>> {
+        if !self.is_dag() {
+            return None;
+        }
+
+        let keys = self.nodes.keys().copied().collect::<Vec<_>>();
+        let index = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (*k, i))
+            .collect::<HashMap<_, _>>();
+        let adjacency = keys
+            .iter()
+            .map(|k| self.nodes[k].edges.keys().map(|t| index[t]).collect())
+            .collect::<Vec<_>>();
+
+        // Each matched edge joins two nodes into the same path
+        let next = max_matching(&adjacency, keys.len());
+        let mut has_prev = vec![false; keys.len()];
+        for n in next.iter().flatten() {
+            has_prev[*n] = true;
+        }
+
+        let paths = (0..keys.len())
+            .filter(|i| !has_prev[*i])
+            .map(|start| {
+                let mut path = vec![&self.nodes[&keys[start]].label];
+                let mut current = start;
+                while let Some(n) = next[current] {
+                    path.push(&self.nodes[&keys[n]].label);
+                    current = n;
+                }
+                path
+            })
+            .collect();
+        Some(paths)
+    }
+
+    // Largest set of mutually unreachable nodes in a DAG, found from a
+    // minimum cover of the transitive closure (Dilworth) via König's theorem
+    pub fn maximum_antichain(&self) -> Option<HashSet<&T>> {
+        if !self.is_dag() {
+            return None;
+        }
+
+        let keys = self.nodes.keys().copied().collect::<Vec<_>>();
+        let index = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (*k, i))
+            .collect::<HashMap<_, _>>();
+        let closure = keys
+            .iter()
+            .map(|k| {
+                self.hop_distances(*k)
+                    .into_keys()
+                    .filter(|t| t != k)
+                    .map(|t| index[&t])
+                    .collect()
+            })
+            .collect::<Vec<Vec<_>>>();
+
+        let pairs = max_matching(&closure, keys.len());
+        let mut matched_right = vec![None; keys.len()];
+        for (l, r) in pairs.iter().enumerate() {
+            if let Some(r) = r {
+                matched_right[*r] = Some(l);
+            }
+        }
+
+        // Alternating paths out of unmatched left nodes
+        let mut left_seen = vec![false; keys.len()];
+        let mut right_seen = vec![false; keys.len()];
+        let mut stack = (0..keys.len())
+            .filter(|l| pairs[*l].is_none())
+            .collect::<Vec<_>>();
+        while let Some(l) = stack.pop() {
+            if left_seen[l] {
+                continue;
+            }
+            left_seen[l] = true;
+            for &r in &closure[l] {
+                if pairs[l] != Some(r) && !right_seen[r] {
+                    right_seen[r] = true;
+                    if let Some(next) = matched_right[r] {
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+
+        let antichain = (0..keys.len())
+            .filter(|i| left_seen[*i] && !right_seen[*i])
+            .map(|i| &self.nodes[&keys[i]].label)
+            .collect();
+        Some(antichain)
+    }
+
+    pub fn width(&self) -> Option<usize> {
+        Some(self.maximum_antichain()?.len())
+    }
+
+    // Both ends of a maximal matching, at most twice the optimal size
+    pub fn vertex_cover_approx(&self) -> HashSet<&T> {
+        let mut cover = HashSet::new();
+        for (key, node) in &self.nodes {
+            for target in node.edges.keys() {
+                if !cover.contains(key) && !cover.contains(target) {
+                    cover.insert(*key);
+                    cover.insert(*target);
+                }
+            }
+        }
+        cover.into_iter().map(|k| &self.nodes[&k].label).collect()
+    }
+
+    // Repeatedly takes the node with fewest remaining neighbours, edge
+    // direction is ignored
+    pub fn independent_set_greedy(&self) -> HashSet<&T> {
+        let mut remaining = self.undirected();
+        let mut set = HashSet::new();
+        while let Some(key) = remaining
+            .iter()
+            .min_by_key(|(k, n)| (n.len(), **k))
+            .map(|(k, _)| *k)
+        {
+            set.insert(&self.nodes[&key].label);
+            let neighbours = remaining.remove(&key).unwrap();
+            for neighbour in &neighbours {
+                for other in remaining.remove(neighbour).unwrap() {
+                    if let Some(n) = remaining.get_mut(&other) {
+                        n.remove(neighbour);
+                    }
+                }
+            }
+        }
+        set
+    }
+}
+
+// Kuhn's augmenting path matching of left nodes onto right nodes
+pub(crate) fn max_matching(adjacency: &[Vec<usize>], right: usize) -> Vec<Option<usize>> {
+    fn augment(
+        left: usize,
+        adjacency: &[Vec<usize>],
+        seen: &mut [bool],
+        matched: &mut [Option<usize>],
+    ) -> bool {
+        for &r in &adjacency[left] {
+            if seen[r] {
+                continue;
+            }
+            seen[r] = true;
+            if matched[r].is_none_or(|l| augment(l, adjacency, seen, matched)) {
+                matched[r] = Some(left);
+                return true;
+            }
+        }
+        false
+    }
+
+    let mut matched = vec![None; right];
+    for left in 0..adjacency.len() {
+        let mut seen = vec![false; right];
+        augment(left, adjacency, &mut seen, &mut matched);
+    }
+
+    let mut pairs = vec![None; adjacency.len()];
+    for (r, l) in matched.into_iter().enumerate() {
+        if let Some(l) = l {
+            pairs[l] = Some(r);
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_cover() {
+        assert_eq!(Graph::path_graph(5).minimum_path_cover().unwrap().len(), 1);
+        assert_eq!(Graph::star_graph(5).minimum_path_cover().unwrap().len(), 4);
+        assert!(Graph::cycle_graph(3).minimum_path_cover().is_none());
+
+        // a -> b -> c, a -> d -> c, e -> d
+        let mut g = Graph::init('a'..='e');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'a', &'d'));
+        assert!(g.connect(&'d', &'c'));
+        assert!(g.connect(&'e', &'d'));
+
+        let cover = g.minimum_path_cover().unwrap();
+        assert_eq!(cover.len(), 2);
+        assert_eq!(cover.iter().map(|p| p.len()).sum::<usize>(), 5);
+        for path in cover {
+            assert!(path.windows(2).all(|w| g.is_connected(w[0], w[1])));
+        }
+    }
+
+    #[test]
+    fn antichain() {
+        assert_eq!(Graph::path_graph(4).width(), Some(1));
+        assert_eq!(Graph::star_graph(5).width(), Some(4));
+        assert_eq!(Graph::grid_graph(3, 3).width(), Some(3));
+        assert_eq!(Graph::cycle_graph(3).width(), None);
+
+        // a -> b -> c, a -> d, e -> c, f isolated
+        let mut g = Graph::init('a'..='f');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'a', &'d'));
+        assert!(g.connect(&'e', &'c'));
+
+        let antichain = g.maximum_antichain().unwrap();
+        assert_eq!(antichain.len(), 4);
+        for a in &antichain {
+            for b in &antichain {
+                assert!(a == b || !g.bfs(a).any(|n| n == *b));
+            }
+        }
+    }
+
+    #[test]
+    fn vertex_cover() {
+        let g = Graph::grid_graph(3, 3);
+        let cover = g.vertex_cover_approx();
+        for edge in g.edges() {
+            assert!(cover.contains(edge.from) || cover.contains(edge.to));
+        }
+        assert!(cover.len() <= 8); // Optimal is 4
+
+        assert!(Graph::path_graph(1).vertex_cover_approx().is_empty());
+    }
+
+    #[test]
+    fn independent_set() {
+        // Leaves of a star are the best choice
+        let g = Graph::star_graph(5);
+        let set = g.independent_set_greedy();
+        assert_eq!(set.len(), 4);
+        assert!(!set.contains(&0));
+
+        let g = Graph::grid_graph(3, 3);
+        let set = g.independent_set_greedy();
+        for edge in g.edges() {
+            assert!(!(set.contains(edge.from) && set.contains(edge.to)));
+        }
+        assert_eq!(set.len(), 5);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::graph::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+impl<T: Hash + Eq> Graph<T> {
+    // Greedy Eades-Lin-Smyth heuristic, removing the returned edges leaves
+    // the graph acyclic but the set isn't guaranteed to be minimal
+    pub fn feedback_arc_set(&self) -> Vec<(&T, &T)> {
+        self.feedback_arc_keys()
+            .into_iter()
+            .map(|(a, b)| (&self.nodes[&a].label, &self.nodes[&b].label))
+            .collect()
+    }
+
+    // Runs in O(V + E). Sinks and sources are kept on stacks, everything
+    // else in buckets by outdegree minus indegree, entries going stale
+    // rather than being moved when a degree changes
+    fn feedback_arc_keys(&self) -> Vec<(u64, u64)> {
+        let mut keys = self.nodes.keys().copied().collect::<Vec<_>>();
+        keys.sort_unstable();
+        let index = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (*k, i))
+            .collect::<HashMap<_, _>>();
+        let size = keys.len();
+        let mut outgoing = vec![Vec::new(); size];
+        let mut incoming = vec![Vec::new(); size];
+        for (i, key) in keys.iter().enumerate() {
+            for target in self.nodes[key].edges.keys().filter(|t| *t != key) {
+                outgoing[i].push(index[target]);
+                incoming[index[target]].push(i);
+            }
+        }
+
+        let mut out_degree = outgoing.iter().map(Vec::len).collect::<Vec<_>>();
+        let mut in_degree = incoming.iter().map(Vec::len).collect::<Vec<_>>();
+        // Shifted by size so the lowest possible delta, 1 - size, is bucket 1
+        let bucket = |out: usize, inn: usize| size + out - inn;
+        let mut buckets = vec![Vec::new(); 2 * size];
+        for i in 0..size {
+            buckets[bucket(out_degree[i], in_degree[i])].push(i);
+        }
+        let mut sinks = (0..size)
+            .filter(|i| out_degree[*i] == 0)
+            .collect::<Vec<_>>();
+        let mut sources = (0..size).filter(|i| in_degree[*i] == 0).collect::<Vec<_>>();
+        let mut top = buckets.len().saturating_sub(1);
+        let mut removed = vec![false; size];
+
+        let mut head = Vec::new();
+        let mut tail = Vec::new();
+        for _ in 0..size {
+            while sinks.last().is_some_and(|i| removed[*i]) {
+                sinks.pop();
+            }
+            while sources.last().is_some_and(|i| removed[*i]) {
+                sources.pop();
+            }
+            let node = if let Some(sink) = sinks.pop() {
+                tail.push(sink);
+                sink
+            } else if let Some(source) = sources.pop() {
+                head.push(source);
+                source
+            } else {
+                let node = loop {
+                    match buckets[top].pop() {
+                        Some(i) if !removed[i] && bucket(out_degree[i], in_degree[i]) == top => {
+                            break i;
+                        }
+                        Some(_) => {}
+                        None => top -= 1,
+                    }
+                };
+                head.push(node);
+                node
+            };
+
+            removed[node] = true;
+            for target in &outgoing[node] {
+                if !removed[*target] {
+                    in_degree[*target] -= 1;
+                    if in_degree[*target] == 0 {
+                        sources.push(*target);
+                    }
+                    let b = bucket(out_degree[*target], in_degree[*target]);
+                    buckets[b].push(*target);
+                    top = top.max(b);
+                }
+            }
+            for source in &incoming[node] {
+                if !removed[*source] {
+                    out_degree[*source] -= 1;
+                    if out_degree[*source] == 0 {
+                        sinks.push(*source);
+                    }
+                    buckets[bucket(out_degree[*source], in_degree[*source])].push(*source);
+                }
+            }
+        }
+
+        let mut position = vec![0; size];
+        for (p, i) in head.into_iter().chain(tail.into_iter().rev()).enumerate() {
+            position[i] = p;
+        }
+        let mut arcs = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            for target in self.nodes[key].edges.keys() {
+                if position[index[target]] <= position[i] {
+                    arcs.push((*key, *target));
+                }
+            }
+        }
+        arcs
+    }
+}
+
+impl<T: Hash + Eq + Clone> Graph<T> {
+    pub fn into_dag(mut self) -> (Self, Vec<(T, T)>) {
+        let mut removed = Vec::new();
+        for (a, b) in self.feedback_arc_keys() {
+            self.set_edge(a, b, None);
+            removed.push((self.nodes[&a].label.clone(), self.nodes[&b].label.clone()));
+        }
+        (self, removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feedback_arcs() {
+        assert!(Graph::path_graph(5).feedback_arc_set().is_empty());
+        assert_eq!(Graph::cycle_graph(5).feedback_arc_set().len(), 1);
+
+        let (dag, removed) = Graph::complete_graph(4).into_dag();
+        assert_eq!(removed.len(), 6);
+        assert_eq!(dag.edges().count(), 6);
+        assert_eq!(dag.ordering().count(), 4);
+
+        // Big enough that rescanning every node for each pick would crawl
+        let n = 50_000;
+        let mut g = Graph::cycle_graph(n);
+        for i in (0..n).step_by(7) {
+            assert!(g.connect(&i, &((i + 2) % n)));
+            assert!(g.connect(&((i + 5) % n), &i));
+        }
+        let (dag, removed) = g.into_dag();
+        assert!(!removed.is_empty());
+        assert_eq!(dag.ordering().count(), n);
+    }
+
+    #[test]
+    fn into_dag() {
+        let mut g = Graph::init('a'..='e');
+
+        // a -> b -> c -> a and c -> d -> e -> c
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'c', &'a'));
+        assert!(g.connect(&'c', &'d'));
+        assert!(g.connect(&'d', &'e'));
+        assert!(g.connect(&'e', &'c'));
+
+        let (dag, removed) = g.into_dag();
+        assert!(removed.len() <= 2);
+        assert_eq!(dag.ordering().count(), 5);
+        assert_eq!(dag.edges().count() + removed.len(), 6);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::collections::{hash_map, HashMap};
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::slice;
+
+// Most nodes only have a handful of outgoing edges, so they're kept in a
+// flat list that is scanned linearly and only spill into a map once the
+// degree grows past SPILL. Mirrors the HashMap API the rest of the crate
+// was written against
+const SPILL: usize = 16;
+
+#[derive(Debug, Clone)]
+pub(crate) enum Edges {
+    Inline(Vec<(u64, i64)>),
+    Map(HashMap<u64, i64>),
+}
+
+impl Default for Edges {
+    fn default() -> Self {
+        Edges::Inline(Vec::new())
+    }
+}
+
+impl Edges {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        if capacity > SPILL {
+            Edges::Map(HashMap::with_capacity(capacity))
+        } else {
+            Edges::Inline(Vec::with_capacity(capacity))
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Edges::Inline(v) => v.len(),
+            Edges::Map(m) => m.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[cfg(test)]
+    pub(crate) fn capacity(&self) -> usize {
+        match self {
+            Edges::Inline(v) => v.capacity(),
+            Edges::Map(m) => m.capacity(),
+        }
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        if self.len() + additional > SPILL {
+            self.spill();
+        }
+        match self {
+            Edges::Inline(v) => v.reserve(additional),
+            Edges::Map(m) => m.reserve(additional),
+        }
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        if let Edges::Map(m) = self {
+            if m.len() <= SPILL {
+                *self = Edges::Inline(m.drain().collect());
+            }
+        }
+        match self {
+            Edges::Inline(v) => v.shrink_to_fit(),
+            Edges::Map(m) => m.shrink_to_fit(),
+        }
+    }
+
+    fn spill(&mut self) {
+        if let Edges::Inline(v) = self {
+            *self = Edges::Map(v.drain(..).collect());
+        }
+    }
+
+    pub(crate) fn get(&self, target: &u64) -> Option<&i64> {
+        match self {
+            Edges::Inline(v) => v.iter().find(|(t, _)| t == target).map(|(_, w)| w),
+            Edges::Map(m) => m.get(target),
+        }
+    }
+
+    pub(crate) fn contains_key(&self, target: &u64) -> bool {
+        self.get(target).is_some()
+    }
+
+    pub(crate) fn insert(&mut self, target: u64, weight: i64) -> Option<i64> {
+        if let Edges::Inline(v) = self {
+            if let Some((_, w)) = v.iter_mut().find(|(t, _)| *t == target) {
+                return Some(core::mem::replace(w, weight));
+            }
+            if v.len() < SPILL {
+                v.push((target, weight));
+                return None;
+            }
+            self.spill();
+        }
+        match self {
+            Edges::Map(m) => m.insert(target, weight),
+            Edges::Inline(_) => unreachable!(),
+        }
+    }
+
+    pub(crate) fn remove(&mut self, target: &u64) -> Option<i64> {
+        match self {
+            Edges::Inline(v) => {
+                let i = v.iter().position(|(t, _)| t == target)?;
+                Some(v.swap_remove(i).1)
+            }
+            Edges::Map(m) => m.remove(target),
+        }
+    }
+
+    pub(crate) fn retain<F: FnMut(&u64, &mut i64) -> bool>(&mut self, mut f: F) {
+        match self {
+            Edges::Inline(v) => v.retain_mut(|(t, w)| f(t, w)),
+            Edges::Map(m) => m.retain(f),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> Iter<'_> {
+        match self {
+            Edges::Inline(v) => Iter::Inline(v.iter()),
+            Edges::Map(m) => Iter::Map(m.iter()),
+        }
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &u64> + '_ {
+        self.iter().map(|(t, _)| t)
+    }
+}
+
+pub(crate) enum Iter<'a> {
+    Inline(slice::Iter<'a, (u64, i64)>),
+    Map(hash_map::Iter<'a, u64, i64>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a u64, &'a i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::Inline(i) => i.next().map(|(t, w)| (t, w)),
+            Iter::Map(i) => i.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Iter::Inline(i) => i.size_hint(),
+            Iter::Map(i) => i.size_hint(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Edges {
+    type Item = (&'a u64, &'a i64);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<(u64, i64)> for Edges {
+    fn from_iter<I: IntoIterator<Item = (u64, i64)>>(iter: I) -> Self {
+        let mut edges = Edges::new();
+        for (target, weight) in iter {
+            edges.insert(target, weight);
+        }
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spills_and_shrinks() {
+        let mut edges = Edges::new();
+        for t in 0..SPILL as u64 {
+            assert_eq!(edges.insert(t, 1), None);
+        }
+        assert!(matches!(edges, Edges::Inline(_)));
+        assert_eq!(edges.insert(3, 5), Some(1));
+
+        assert_eq!(edges.insert(100, 2), None);
+        assert!(matches!(edges, Edges::Map(_)));
+        assert_eq!(edges.len(), SPILL + 1);
+        assert_eq!(edges.get(&3), Some(&5));
+
+        assert_eq!(edges.remove(&100), Some(2));
+        assert_eq!(edges.remove(&100), None);
+        edges.shrink_to_fit();
+        assert!(matches!(edges, Edges::Inline(_)));
+        assert_eq!(edges.get(&3), Some(&5));
+
+        edges.retain(|t, _| t % 2 == 0);
+        assert_eq!(edges.len(), SPILL / 2);
+        assert!(!edges.contains_key(&1) && edges.contains_key(&2));
+
+        edges.reserve(SPILL);
+        assert!(matches!(edges, Edges::Map(_)));
+        assert_eq!(edges.keys().count(), SPILL / 2);
+    }
+}
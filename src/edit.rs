@@ -0,0 +1,336 @@
+use crate::{graph::*, hash};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Graphs this small on both sides get an exhaustive search, anything
+// bigger gets the greedy alignment on its own
+const EXACT_LIMIT: usize = 7;
+
+// One step turning one graph into the other. Deletions and reweights name
+// nodes by their label in the first graph, insertions by their label in
+// the second
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit<'a, T> {
+    DeleteNode(&'a T),
+    InsertNode(&'a T),
+    RelabelNode {
+        from: &'a T,
+        to: &'a T,
+    },
+    DeleteEdge {
+        from: &'a T,
+        to: &'a T,
+        weight: i64,
+    },
+    InsertEdge {
+        from: &'a T,
+        to: &'a T,
+        weight: i64,
+    },
+    Reweight {
+        from: &'a T,
+        to: &'a T,
+        left: i64,
+        right: i64,
+    },
+}
+
+// Edits in the order they can be applied, nodes first. Exact when the
+// search was exhaustive, otherwise an upper bound on the distance
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditScript<'a, T> {
+    pub edits: Vec<Edit<'a, T>>,
+    pub exact: bool,
+}
+
+impl<T> EditScript<'_, T> {
+    pub fn distance(&self) -> usize {
+        self.edits.len()
+    }
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    // Fewest unit cost node and edge insertions, deletions and relabellings
+    // turning this graph into other, changing an edge's weight counting as
+    // one. Exact for graphs of up to EXACT_LIMIT nodes, otherwise nodes are
+    // paired up as by align, same labels first
+    pub fn edit_distance<'a>(&'a self, other: &'a Graph<T>) -> EditScript<'a, T> {
+        let aligned = self.align(other, |a, b| if a == b { 2.0 } else { 1.0 });
+        let mut mapping = aligned
+            .into_iter()
+            .map(|(a, b)| (hash(a), hash(b)))
+            .collect::<HashMap<_, _>>();
+        let exact = self.size() <= EXACT_LIMIT && other.size() <= EXACT_LIMIT;
+        if exact {
+            let bound = self.script(other, &mapping).len();
+            if let Some(better) = Search::new(self, other, bound).run() {
+                mapping = better;
+            }
+        }
+        EditScript {
+            edits: self.script(other, &mapping),
+            exact,
+        }
+    }
+
+    // The edits a node mapping implies, from our keys to theirs
+    fn script<'a>(&'a self, other: &'a Graph<T>, mapping: &HashMap<u64, u64>) -> Vec<Edit<'a, T>> {
+        let ours = sorted_keys(self);
+        let theirs = sorted_keys(other);
+        let inverse = mapping
+            .iter()
+            .map(|(a, b)| (*b, *a))
+            .collect::<HashMap<_, _>>();
+        let mut edits = Vec::new();
+
+        for a in &ours {
+            let label = &self.nodes[a].label;
+            match mapping.get(a) {
+                None => edits.push(Edit::DeleteNode(label)),
+                Some(b) if a != b => edits.push(Edit::RelabelNode {
+                    from: label,
+                    to: &other.nodes[b].label,
+                }),
+                Some(_) => (),
+            }
+        }
+        for b in &theirs {
+            if !inverse.contains_key(b) {
+                edits.push(Edit::InsertNode(&other.nodes[b].label));
+            }
+        }
+
+        for a in &ours {
+            let node = &self.nodes[a];
+            let mut targets = node.edges.iter().collect::<Vec<_>>();
+            targets.sort_unstable();
+            for (t, weight) in targets {
+                let (from, to) = (&node.label, &self.nodes[t].label);
+                let theirs = match (mapping.get(a), mapping.get(t)) {
+                    (Some(x), Some(y)) => other.nodes[x].edges.get(y).copied(),
+                    _ => None,
+                };
+                match theirs {
+                    None => edits.push(Edit::DeleteEdge {
+                        from,
+                        to,
+                        weight: *weight,
+                    }),
+                    Some(right) if right != *weight => edits.push(Edit::Reweight {
+                        from,
+                        to,
+                        left: *weight,
+                        right,
+                    }),
+                    Some(_) => (),
+                }
+            }
+        }
+        for b in &theirs {
+            let node = &other.nodes[b];
+            let mut targets = node.edges.iter().collect::<Vec<_>>();
+            targets.sort_unstable();
+            for (t, weight) in targets {
+                let ours = match (inverse.get(b), inverse.get(t)) {
+                    (Some(x), Some(y)) => self.nodes[x].edges.contains_key(y),
+                    _ => false,
+                };
+                if !ours {
+                    edits.push(Edit::InsertEdge {
+                        from: &node.label,
+                        to: &other.nodes[t].label,
+                        weight: *weight,
+                    });
+                }
+            }
+        }
+        edits
+    }
+}
+
+fn sorted_keys<T>(graph: &Graph<T>) -> Vec<u64> {
+    let mut keys = graph.nodes.keys().copied().collect::<Vec<_>>();
+    keys.sort_unstable();
+    keys
+}
+
+// Branch and bound over every way of mapping our nodes onto distinct
+// nodes of theirs or deleting them, costing edges between nodes as soon as
+// both ends are placed
+struct Search<'a, T> {
+    ours: &'a Graph<T>,
+    theirs: &'a Graph<T>,
+    a: Vec<u64>,
+    b: Vec<u64>,
+    assigned: Vec<Option<usize>>,
+    used: Vec<bool>,
+    best: usize,
+    found: Option<Vec<Option<usize>>>,
+}
+
+impl<'a, T> Search<'a, T> {
+    fn new(ours: &'a Graph<T>, theirs: &'a Graph<T>, bound: usize) -> Self {
+        let (a, b) = (sorted_keys(ours), sorted_keys(theirs));
+        Search {
+            ours,
+            theirs,
+            assigned: Vec::with_capacity(a.len()),
+            used: vec![false; b.len()],
+            a,
+            b,
+            best: bound,
+            found: None,
+        }
+    }
+
+    // A mapping strictly cheaper than the bound, if there is one
+    fn run(mut self) -> Option<HashMap<u64, u64>> {
+        self.extend(0);
+        let found = self.found.take()?;
+        Some(
+            found
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, t)| Some((self.a[i], self.b[t?])))
+                .collect(),
+        )
+    }
+
+    fn extend(&mut self, cost: usize) {
+        if cost >= self.best {
+            return;
+        }
+        let i = self.assigned.len();
+        if i == self.a.len() {
+            let total = cost + self.leftover();
+            if total < self.best {
+                self.best = total;
+                self.found = Some(self.assigned.clone());
+            }
+            return;
+        }
+        for t in (0..self.b.len()).map(Some).chain([None]) {
+            if t.is_some_and(|t| self.used[t]) {
+                continue;
+            }
+            let step = self.place(i, t);
+            self.assigned.push(t);
+            if let Some(t) = t {
+                self.used[t] = true;
+            }
+            self.extend(cost + step);
+            if let Some(t) = t {
+                self.used[t] = false;
+            }
+            self.assigned.pop();
+        }
+    }
+
+    // Cost of mapping our node i to t, including edges to the nodes before
+    // it and its own self-loop
+    fn place(&self, i: usize, t: Option<usize>) -> usize {
+        let mut cost = match t {
+            None => 1,
+            Some(t) => (self.a[i] != self.b[t]) as usize,
+        };
+        for j in 0..=i {
+            let u = if j == i { t } else { self.assigned[j] };
+            cost += self.edge_cost(i, j, t, u);
+            if j != i {
+                cost += self.edge_cost(j, i, u, t);
+            }
+        }
+        cost
+    }
+
+    fn edge_cost(&self, i: usize, j: usize, t: Option<usize>, u: Option<usize>) -> usize {
+        let ours = self.ours.nodes[&self.a[i]].edges.get(&self.a[j]);
+        let theirs = match (t, u) {
+            (Some(t), Some(u)) => self.theirs.nodes[&self.b[t]].edges.get(&self.b[u]),
+            _ => None,
+        };
+        match (ours, theirs) {
+            (Some(x), Some(y)) => (x != y) as usize,
+            (None, None) => 0,
+            _ => 1,
+        }
+    }
+
+    // Their nodes nothing was mapped to, along with their edges
+    fn leftover(&self) -> usize {
+        let unused = |k: &u64| !self.used[self.b.binary_search(k).unwrap()];
+        self.b
+            .iter()
+            .map(|k| {
+                let node = &self.theirs.nodes[k];
+                let edges = node.edges.keys().filter(|t| unused(k) || unused(t)).count();
+                unused(k) as usize + edges
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(char, char, i64)], loose: &[char]) -> Graph<char> {
+        let mut g = Graph::init(loose.iter().copied());
+        for (a, b, w) in edges {
+            for n in [a, b] {
+                if !g.contains(n) {
+                    g.add(*n);
+                }
+            }
+            assert!(g.connect_weighted(a, b, *w));
+        }
+        g
+    }
+
+    #[test]
+    fn edit_distance() {
+        let g = graph(&[('a', 'b', 1), ('b', 'c', 1), ('c', 'a', 2)], &[]);
+        let same = g.edit_distance(&g);
+        assert!(same.exact && same.edits.is_empty());
+
+        // Renaming c is one edit, not a delete, an insert and their edges
+        let h = graph(&[('a', 'b', 1), ('b', 'd', 1), ('d', 'a', 5)], &['e']);
+        let script = g.edit_distance(&h);
+        assert!(script.exact);
+        assert_eq!(script.distance(), 3);
+        assert!(script.edits.contains(&Edit::RelabelNode {
+            from: &'c',
+            to: &'d'
+        }));
+        assert!(script.edits.contains(&Edit::InsertNode(&'e')));
+        assert!(script.edits.contains(&Edit::Reweight {
+            from: &'c',
+            to: &'a',
+            left: 2,
+            right: 5
+        }));
+
+        let empty = Graph::new();
+        assert_eq!(g.edit_distance(&empty).distance(), 6);
+        assert_eq!(empty.edit_distance(&g).distance(), 6);
+    }
+
+    #[test]
+    fn size_guard() {
+        // Only the centre of the star was renamed
+        let star = |centre, leaves: &[char]| {
+            let edges = leaves.iter().map(|l| (centre, *l, 1)).collect::<Vec<_>>();
+            graph(&edges, &[])
+        };
+        let g = star('x', &['a', 'b', 'c']);
+        let h = star('y', &['a', 'b', 'c']);
+        assert_eq!(g.edit_distance(&h).distance(), 1);
+
+        // Past the limit the answer is still a valid script, just not
+        // guaranteed to be the shortest
+        let (big, shifted) = (Graph::init('a'..='j'), Graph::init('f'..='o'));
+        let script = big.edit_distance(&shifted);
+        assert!(!script.exact);
+        assert_eq!(script.distance(), 5);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{edges::Edges, graph::*, hash};
+use core::hash::Hash;
+
+// A label that may or may not be in the graph yet, hashed once up front
+pub struct Entry<'a, T> {
+    graph: &'a mut Graph<T>,
+    key: u64,
+    label: T,
+}
+
+// A node known to be in the graph
+pub struct NodeEntry<'a, T> {
+    graph: &'a mut Graph<T>,
+    key: u64,
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    pub fn entry(&mut self, label: T) -> Entry<'_, T> {
+        Entry {
+            key: hash(&label),
+            graph: self,
+            label,
+        }
+    }
+}
+
+impl<'a, T> Entry<'a, T> {
+    pub fn label(&self) -> &T {
+        &self.label
+    }
+
+    // Leaves an existing node and its edges alone
+    pub fn or_insert(self) -> NodeEntry<'a, T> {
+        let Entry { graph, key, label } = self;
+        if !graph.nodes.contains_key(&key) {
+            let node = Node {
+                label,
+                edges: Edges::new(),
+                incoming: Edges::new(),
+            };
+            graph.insert_node(key, node);
+        }
+        NodeEntry { graph, key }
+    }
+}
+
+impl<'a, T: Hash> NodeEntry<'a, T> {
+    pub fn label(&self) -> &T {
+        &self.graph.nodes[&self.key].label
+    }
+
+    pub fn into_label(self) -> &'a T {
+        &self.graph.nodes[&self.key].label
+    }
+
+    // Connects to other if it's in the graph, a missing target is skipped
+    // so insert it first when that matters
+    pub fn and_connect_to(self, other: &T) -> Self {
+        let target = hash(other);
+        if self.graph.nodes.contains_key(&target) {
+            self.graph.add_edge(self.key, target);
+        }
+        self
+    }
+
+    pub fn and_disconnect_from(self, other: &T) -> Self {
+        let target = hash(other);
+        if self.graph.nodes.contains_key(&target) {
+            self.graph.set_edge(self.key, target, None);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries() {
+        let mut g = Graph::new();
+        for (from, to) in [("a", "b"), ("b", "c"), ("a", "c"), ("c", "c")] {
+            g.entry(to).or_insert();
+            g.entry(from).or_insert().and_connect_to(&to);
+        }
+        assert_eq!(g.size(), 3);
+        assert_eq!(g.edges().count(), 4);
+        assert_eq!(g.indegree(&"c"), Some(3));
+        assert_eq!((g.num_sources(), g.num_sinks()), (1, 0));
+
+        // Existing nodes keep their edges
+        let node = g.entry("a").or_insert();
+        assert_eq!(node.label(), &"a");
+        assert_eq!(node.and_disconnect_from(&"b").into_label(), &"a");
+        assert!(g.is_connected(&"a", &"c"));
+        assert!(!g.is_connected(&"a", &"b"));
+
+        assert_eq!(g.entry("z").label(), &"z");
+        g.entry("d").or_insert().and_connect_to(&"y");
+        assert_eq!(g.size(), 4);
+        assert_eq!(g.outdegree(&"d"), Some(0));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{graph::*, hash};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+impl<T: Hash + Eq> Graph<T> {
+    // Computes a value for every node from the values of its dependencies,
+    // where an edge a -> b means a depends on b. Dependencies come in a
+    // fixed order, by key. None if the graph has a cycle
+    pub fn evaluate<V, F>(&self, mut f: F) -> Option<HashMap<&T, V>>
+    where
+        F: FnMut(&T, &[&V]) -> V,
+    {
+        let values = self.evaluate_keys(|_, label, deps| f(label, deps))?;
+        Some(self.by_label(values))
+    }
+
+    // As evaluate, but values are looked up in and saved to the cache by
+    // structural hash, so a node is only computed if it or something below
+    // it changed since the cache was filled. The cache can be shared
+    // between graphs
+    pub fn evaluate_cached<V, F>(
+        &self,
+        cache: &mut HashMap<u64, V>,
+        mut f: F,
+    ) -> Option<HashMap<&T, V>>
+    where
+        V: Clone,
+        F: FnMut(&T, &[&V]) -> V,
+    {
+        let hashes = self.structural_keys()?;
+        let values = self.evaluate_keys(|key, label, deps| {
+            let hash = hashes[&key];
+            if let Some(value) = cache.get(&hash) {
+                return value.clone();
+            }
+            let value = f(label, deps);
+            cache.insert(hash, value.clone());
+            value
+        })?;
+        Some(self.by_label(values))
+    }
+
+    fn evaluate_keys<V, F>(&self, mut compute: F) -> Option<HashMap<u64, V>>
+    where
+        F: FnMut(u64, &T, &[&V]) -> V,
+    {
+        let order = self.ordering().map(hash).collect::<Vec<_>>();
+        if order.len() != self.size() {
+            return None;
+        }
+        let mut values = HashMap::with_capacity(self.size());
+        for key in order.into_iter().rev() {
+            let node = &self.nodes[&key];
+            let mut deps = node.edges.keys().copied().collect::<Vec<_>>();
+            deps.sort_unstable();
+            let deps = deps.iter().map(|k| &values[k]).collect::<Vec<_>>();
+            let value = compute(key, &node.label, &deps);
+            values.insert(key, value);
+        }
+        Some(values)
+    }
+
+    // Folds values forward along the topological order. Nodes with no
+    // incoming edges start from init, the rest are computed from the value
+    // of each predecessor along with the weight of the edge from it, by
+    // key. None if the graph has a cycle
+    pub fn dag_dp<V, I, F>(&self, init: I, f: F) -> Option<HashMap<&T, V>>
+    where
+        I: FnMut(&T) -> V,
+        F: FnMut(&T, &[(&V, i64)]) -> V,
+    {
+        self.fold(false, init, f)
+    }
+
+    // As dag_dp but against the edges, so nodes with no outgoing edges
+    // start from init and the rest are computed from their successors
+    pub fn dag_dp_backward<V, I, F>(&self, init: I, f: F) -> Option<HashMap<&T, V>>
+    where
+        I: FnMut(&T) -> V,
+        F: FnMut(&T, &[(&V, i64)]) -> V,
+    {
+        self.fold(true, init, f)
+    }
+
+    fn fold<V, I, F>(&self, backward: bool, mut init: I, mut f: F) -> Option<HashMap<&T, V>>
+    where
+        I: FnMut(&T) -> V,
+        F: FnMut(&T, &[(&V, i64)]) -> V,
+    {
+        let mut order = self.ordering().map(hash).collect::<Vec<_>>();
+        if order.len() != self.size() {
+            return None;
+        }
+        if backward {
+            order.reverse();
+        }
+        let mut values = HashMap::with_capacity(self.size());
+        for key in order {
+            let node = &self.nodes[&key];
+            let edges = if backward {
+                &node.edges
+            } else {
+                &node.incoming
+            };
+            let value = if edges.is_empty() {
+                init(&node.label)
+            } else {
+                let mut inputs = edges.iter().map(|(k, w)| (*k, *w)).collect::<Vec<_>>();
+                inputs.sort_unstable();
+                let inputs = inputs
+                    .into_iter()
+                    .map(|(k, w)| (&values[&k], w))
+                    .collect::<Vec<_>>();
+                f(&node.label, &inputs)
+            };
+            values.insert(key, value);
+        }
+        Some(self.by_label(values))
+    }
+
+    fn by_label<V>(&self, values: HashMap<u64, V>) -> HashMap<&T, V> {
+        values
+            .into_iter()
+            .map(|(k, v)| (&self.nodes[&k].label, v))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each cell is its own number plus everything it refers to
+    fn cells() -> Graph<u32> {
+        let mut g = Graph::init([1, 2, 3, 10, 20]);
+        for (a, b) in [(10, 1), (10, 2), (20, 10), (20, 3)] {
+            assert!(g.connect(&a, &b));
+        }
+        g
+    }
+
+    #[test]
+    fn evaluate() {
+        let g = cells();
+        let values = g
+            .evaluate(|n, deps: &[&u32]| *n + deps.iter().copied().sum::<u32>())
+            .unwrap();
+        assert_eq!(values[&10], 13);
+        assert_eq!(values[&20], 36);
+        assert_eq!(values[&3], 3);
+
+        let mut cyclic = cells();
+        assert!(cyclic.connect(&1, &20));
+        assert!(cyclic.evaluate(|_, _: &[&u32]| 0).is_none());
+    }
+
+    #[test]
+    fn dag_dp() {
+        // Tasks with durations on the edges out of them
+        let mut g = Graph::init(vec!["design", "build", "docs", "test", "ship"]);
+        for (a, b, w) in [
+            ("design", "build", 3),
+            ("design", "docs", 3),
+            ("build", "test", 5),
+            ("docs", "ship", 2),
+            ("test", "ship", 1),
+        ] {
+            assert!(g.connect_weighted(&a, &b, w));
+        }
+        let latest = |inputs: &[(&i64, i64)]| inputs.iter().map(|(v, w)| *v + w).max().unwrap();
+        let earliest = g.dag_dp(|_| 0, |_, inputs| latest(inputs)).unwrap();
+        assert_eq!(earliest[&"test"], 8);
+        assert_eq!(earliest[&"ship"], 9);
+
+        // Slack is how much later a task could start without delaying ship
+        let remaining = g
+            .dag_dp_backward(|_| 0, |_, inputs| latest(inputs))
+            .unwrap();
+        assert_eq!(remaining[&"design"], 9);
+        assert_eq!(9 - remaining[&"docs"] - earliest[&"docs"], 4);
+        assert_eq!(9 - remaining[&"build"] - earliest[&"build"], 0);
+
+        let paths = g
+            .dag_dp(|_| 1u64, |_, inputs| inputs.iter().map(|(v, _)| **v).sum())
+            .unwrap();
+        assert_eq!(paths[&"ship"], 2);
+
+        assert!(g.connect(&"ship", &"design"));
+        assert!(g.dag_dp(|_| 0, |_, _| 0).is_none());
+        assert!(g.dag_dp_backward(|_| 0, |_, _| 0).is_none());
+    }
+
+    #[test]
+    fn cached() {
+        let mut g = cells();
+        let mut cache = HashMap::new();
+        let mut calls = 0;
+        let mut sum = |n: &u32, deps: &[&u32]| {
+            calls += 1;
+            *n + deps.iter().copied().sum::<u32>()
+        };
+        assert_eq!(g.evaluate_cached(&mut cache, &mut sum).unwrap()[&20], 36);
+        assert_eq!(g.evaluate_cached(&mut cache, &mut sum).unwrap()[&20], 36);
+
+        // Only 20 lost an edge, everything below it comes from the cache
+        assert!(g.disconnect(&20, &3));
+        assert_eq!(g.evaluate_cached(&mut cache, &mut sum).unwrap()[&20], 33);
+        assert_eq!(calls, 6);
+    }
+}
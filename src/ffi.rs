@@ -0,0 +1,184 @@
+// C ABI over a string-labelled graph. Every string crossing the boundary
+// is a NUL-terminated UTF-8 C string, and anything handed out must be
+// given back to the matching grust_*_free function.
+use crate::graph::*;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+pub struct GrustGraph {
+    graph: Graph<String>,
+}
+
+unsafe fn label(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok().map(str::to_owned)
+}
+
+#[no_mangle]
+pub extern "C" fn grust_graph_new() -> *mut GrustGraph {
+    let graph = GrustGraph {
+        graph: Graph::new(),
+    };
+    Box::into_raw(Box::new(graph))
+}
+
+/// # Safety
+/// `graph` must come from `grust_graph_new` and not already be freed.
+#[no_mangle]
+pub unsafe extern "C" fn grust_graph_free(graph: *mut GrustGraph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
+
+/// # Safety
+/// `graph` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn grust_size(graph: *const GrustGraph) -> usize {
+    graph.as_ref().map_or(0, |g| g.graph.size())
+}
+
+/// # Safety
+/// `graph` must be a live handle and `label` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn grust_add(graph: *mut GrustGraph, label: *const c_char) -> bool {
+    match (graph.as_mut(), self::label(label)) {
+        (Some(g), Some(label)) => {
+            g.graph.add(label);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// # Safety
+/// `graph` must be a live handle and `label` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn grust_remove(graph: *mut GrustGraph, label: *const c_char) -> bool {
+    match (graph.as_mut(), self::label(label)) {
+        (Some(g), Some(label)) => g.graph.remove(&label).is_some(),
+        _ => false,
+    }
+}
+
+/// # Safety
+/// `graph` must be a live handle and `from`/`to` valid C strings.
+#[no_mangle]
+pub unsafe extern "C" fn grust_connect(
+    graph: *mut GrustGraph,
+    from: *const c_char,
+    to: *const c_char,
+) -> bool {
+    match (graph.as_mut(), label(from), label(to)) {
+        (Some(g), Some(from), Some(to)) => g.graph.connect(&from, &to),
+        _ => false,
+    }
+}
+
+/// # Safety
+/// `graph` must be a live handle and `from`/`to` valid C strings.
+#[no_mangle]
+pub unsafe extern "C" fn grust_disconnect(
+    graph: *mut GrustGraph,
+    from: *const c_char,
+    to: *const c_char,
+) -> bool {
+    match (graph.as_mut(), label(from), label(to)) {
+        (Some(g), Some(from), Some(to)) => g.graph.disconnect(&from, &to),
+        _ => false,
+    }
+}
+
+/// # Safety
+/// `graph` must be a live handle and `from`/`to` valid C strings.
+#[no_mangle]
+pub unsafe extern "C" fn grust_is_connected(
+    graph: *const GrustGraph,
+    from: *const c_char,
+    to: *const c_char,
+) -> bool {
+    match (graph.as_ref(), label(from), label(to)) {
+        (Some(g), Some(from), Some(to)) => g.graph.is_connected(&from, &to),
+        _ => false,
+    }
+}
+
+/// Topological order as an array of `*len` strings, free it with
+/// `grust_strings_free`. Nodes on or behind a cycle are left out.
+///
+/// # Safety
+/// `graph` must be a live handle and `len` writable.
+#[no_mangle]
+pub unsafe extern "C" fn grust_topo_order(
+    graph: *const GrustGraph,
+    len: *mut usize,
+) -> *mut *mut c_char {
+    let g = match graph.as_ref() {
+        Some(g) if !len.is_null() => g,
+        _ => return ptr::null_mut(),
+    };
+    let labels = g
+        .graph
+        .ordering()
+        .map(|l| CString::new(l.as_str()).unwrap_or_default().into_raw())
+        .collect::<Vec<_>>();
+    *len = labels.len();
+    Box::into_raw(labels.into_boxed_slice()) as *mut *mut c_char
+}
+
+/// # Safety
+/// `strings` and `len` must be exactly as returned by `grust_topo_order`.
+#[no_mangle]
+pub unsafe extern "C" fn grust_strings_free(strings: *mut *mut c_char, len: usize) {
+    if strings.is_null() {
+        return;
+    }
+    let strings = Box::from_raw(ptr::slice_from_raw_parts_mut(strings, len));
+    for s in strings.iter() {
+        drop(CString::from_raw(*s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let (a, b, c) = (
+            CString::new("a").unwrap(),
+            CString::new("b").unwrap(),
+            CString::new("c").unwrap(),
+        );
+        unsafe {
+            let g = grust_graph_new();
+            assert!(grust_add(g, a.as_ptr()));
+            assert!(grust_add(g, b.as_ptr()));
+            assert!(grust_add(g, c.as_ptr()));
+            assert!(!grust_add(g, ptr::null()));
+            assert_eq!(grust_size(g), 3);
+
+            assert!(grust_connect(g, b.as_ptr(), c.as_ptr()));
+            assert!(grust_connect(g, a.as_ptr(), b.as_ptr()));
+            assert!(grust_is_connected(g, a.as_ptr(), b.as_ptr()));
+
+            let mut len = 0;
+            let order = grust_topo_order(g, &mut len);
+            assert_eq!(len, 3);
+            let labels = (0..len)
+                .map(|i| CStr::from_ptr(*order.add(i)).to_str().unwrap().to_owned())
+                .collect::<Vec<_>>();
+            assert_eq!(labels, vec!["a", "b", "c"]);
+            grust_strings_free(order, len);
+
+            assert!(grust_disconnect(g, a.as_ptr(), b.as_ptr()));
+            assert!(grust_remove(g, c.as_ptr()));
+            assert!(!grust_remove(g, c.as_ptr()));
+            assert_eq!(grust_size(g), 2);
+            grust_graph_free(g);
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{base::GraphBase, graph::*, hash};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Deref;
+
+// How much an edge can carry and what each unit sent along it costs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowEdge {
+    pub capacity: i64,
+    pub cost: i64,
+}
+
+// Result of a min-cost flow, value can fall short of the amount asked for
+// if the network can't carry it. Only edges with flow on them are listed
+#[derive(Debug)]
+pub struct Flow<'a, T> {
+    pub value: i64,
+    pub cost: i64,
+    pub edges: HashMap<(&'a T, &'a T), i64>,
+}
+
+// Graph wrapper where every edge has a capacity and a per-unit cost. The
+// capacity is stored as the wrapped graph's edge weight
+#[derive(Debug)]
+pub struct FlowGraph<T> {
+    graph: Graph<T>,
+    costs: HashMap<(u64, u64), i64>,
+}
+
+// Residual arc, arcs come in pairs with the reverse of arc i at i ^ 1
+struct Residual {
+    to: usize,
+    capacity: i64,
+    cost: i64,
+}
+
+impl<T> Default for FlowGraph<T> {
+    fn default() -> Self {
+        Graph::new().into()
+    }
+}
+
+// Existing edge weights become capacities with no cost
+impl<T> From<Graph<T>> for FlowGraph<T> {
+    fn from(graph: Graph<T>) -> Self {
+        let costs = graph
+            .nodes
+            .iter()
+            .flat_map(|(k, n)| n.edges.keys().map(move |t| ((*k, *t), 0)))
+            .collect();
+        FlowGraph { graph, costs }
+    }
+}
+
+impl<T> Deref for FlowGraph<T> {
+    type Target = Graph<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T> FlowGraph<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn into_inner(self) -> Graph<T> {
+        self.graph
+    }
+}
+
+impl<T: Hash + Eq> FlowGraph<T> {
+    // Replacing a node drops its outgoing edges, so their costs go too
+    pub fn add(&mut self, label: T) {
+        let key = hash(&label);
+        if let Some(node) = self.graph.nodes.get(&key) {
+            for to in node.edges.keys() {
+                self.costs.remove(&(key, *to));
+            }
+        }
+        self.graph.add(label);
+    }
+
+    pub fn remove(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        let Some((node, incoming)) = self.graph.remove_node(key) else {
+            return false;
+        };
+        for to in node.edges.keys() {
+            self.costs.remove(&(key, *to));
+        }
+        for (from, _) in incoming {
+            self.costs.remove(&(from, key));
+        }
+        true
+    }
+
+    // Replaces the capacity and cost if the edge already exists
+    pub fn connect_flow(&mut self, from: &T, to: &T, capacity: i64, cost: i64) -> bool {
+        let (from, to) = (hash(from), hash(to));
+        if !self.graph.nodes.contains_key(&from) || !self.graph.nodes.contains_key(&to) {
+            return false;
+        }
+        self.graph.set_edge(from, to, Some(capacity));
+        self.costs.insert((from, to), cost);
+        true
+    }
+
+    pub fn disconnect(&mut self, from: &T, to: &T) -> bool {
+        self.costs.remove(&(hash(from), hash(to)));
+        self.graph.disconnect(from, to)
+    }
+
+    pub fn flow_edge(&self, from: &T, to: &T) -> Option<FlowEdge> {
+        let key = (hash(from), hash(to));
+        Some(FlowEdge {
+            capacity: self.graph.weight(from, to)?,
+            cost: self.costs[&key],
+        })
+    }
+
+    // Cheapest way to send amount units from source to sink
+    pub fn min_cost_flow(&self, source: &T, sink: &T, amount: i64) -> Option<Flow<'_, T>> {
+        self.graph
+            .min_cost_flow_by(source, sink, amount, |from, to, capacity| FlowEdge {
+                capacity,
+                cost: self.costs[&(hash(from), hash(to))],
+            })
+    }
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    // Cheapest way to send amount units from source to sink, with each
+    // edge's capacity and cost given by the function from its endpoints and
+    // weight. Costs can be negative as long as no cycle has a negative
+    // total. None if either end is missing
+    pub fn min_cost_flow_by<F>(
+        &self,
+        source: &T,
+        sink: &T,
+        amount: i64,
+        edge: F,
+    ) -> Option<Flow<'_, T>>
+    where
+        F: Fn(&T, &T, i64) -> FlowEdge,
+    {
+        self.get(source)?;
+        self.get(sink)?;
+        let keys = self.nodes.keys().copied().collect::<Vec<_>>();
+        let index = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (*k, i))
+            .collect::<HashMap<_, _>>();
+
+        // Self-loops can never carry useful flow so they're left out
+        let mut arcs = Vec::new();
+        let mut adjacent = vec![Vec::new(); keys.len()];
+        let mut originals = Vec::new();
+        for (from, node) in &self.nodes {
+            for (to, weight) in &node.edges {
+                if from == to {
+                    continue;
+                }
+                let FlowEdge { capacity, cost } = edge(&node.label, &self.nodes[to].label, *weight);
+                let (a, b) = (index[from], index[to]);
+                adjacent[a].push(arcs.len());
+                arcs.push(Residual {
+                    to: b,
+                    capacity: capacity.max(0),
+                    cost,
+                });
+                adjacent[b].push(arcs.len());
+                arcs.push(Residual {
+                    to: a,
+                    capacity: 0,
+                    cost: -cost,
+                });
+                originals.push((*from, *to, capacity.max(0)));
+            }
+        }
+
+        // Successive shortest paths, Bellman-Ford copes with the negative
+        // costs on reverse arcs
+        let (s, t) = (index[&hash(source)], index[&hash(sink)]);
+        let (mut value, mut cost) = (0, 0);
+        while value < amount && s != t {
+            let mut distance = vec![None; keys.len()];
+            let mut via = vec![usize::MAX; keys.len()];
+            distance[s] = Some(0);
+            for _ in 0..keys.len() {
+                let mut changed = false;
+                for (a, arcs_from) in adjacent.iter().enumerate() {
+                    let Some(d) = distance[a] else { continue };
+                    for &i in arcs_from {
+                        let arc = &arcs[i];
+                        let next = d + arc.cost;
+                        if arc.capacity > 0 && distance[arc.to].is_none_or(|e| next < e) {
+                            distance[arc.to] = Some(next);
+                            via[arc.to] = i;
+                            changed = true;
+                        }
+                    }
+                }
+                if !changed {
+                    break;
+                }
+            }
+            let Some(unit) = distance[t] else { break };
+
+            let mut push = amount - value;
+            let mut current = t;
+            while current != s {
+                push = push.min(arcs[via[current]].capacity);
+                current = arcs[via[current] ^ 1].to;
+            }
+            let mut current = t;
+            while current != s {
+                arcs[via[current]].capacity -= push;
+                arcs[via[current] ^ 1].capacity += push;
+                current = arcs[via[current] ^ 1].to;
+            }
+            value += push;
+            cost += push * unit;
+        }
+
+        // Whatever's left of a forward arc's capacity wasn't used
+        let edges = originals
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, (from, to, capacity))| {
+                let used = capacity - arcs[2 * i].capacity;
+                let labels = (&self.nodes[&from].label, &self.nodes[&to].label);
+                (used > 0).then_some((labels, used))
+            })
+            .collect();
+        Some(Flow { value, cost, edges })
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for FlowGraph<T> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edges() {
+        let mut g = FlowGraph::from(Graph::init('a'..='c'));
+        assert!(g.connect_flow(&'a', &'b', 4, 2));
+        assert!(!g.connect_flow(&'a', &'z', 1, 1));
+        assert_eq!(
+            g.flow_edge(&'a', &'b'),
+            Some(FlowEdge {
+                capacity: 4,
+                cost: 2
+            })
+        );
+        assert_eq!(g.weight(&'a', &'b'), Some(4));
+
+        assert!(g.disconnect(&'a', &'b'));
+        assert!(g.flow_edge(&'a', &'b').is_none());
+        assert!(g.connect_flow(&'b', &'c', 1, 1));
+        assert!(g.remove(&'c'));
+        assert!(g.flow_edge(&'b', &'c').is_none());
+
+        // Re-adding b drops b -> a but keeps a -> b
+        assert!(g.connect_flow(&'a', &'b', 2, 1));
+        assert!(g.connect_flow(&'b', &'a', 2, 1));
+        g.add('b');
+        assert!(g.flow_edge(&'b', &'a').is_none());
+        assert!(g.flow_edge(&'a', &'b').is_some());
+    }
+
+    #[test]
+    fn min_cost_flow() {
+        // Two depots shipping to two shops, the cheap route is too narrow
+        // to carry everything
+        let mut g = FlowGraph::from(Graph::init(vec!["s", "d1", "d2", "x", "y", "t"]));
+        for (from, to, capacity, cost) in [
+            ("s", "d1", 3, 0),
+            ("s", "d2", 3, 0),
+            ("d1", "x", 2, 1),
+            ("d1", "y", 3, 4),
+            ("d2", "x", 3, 3),
+            ("d2", "y", 1, 2),
+            ("x", "t", 3, 0),
+            ("y", "t", 3, 0),
+        ] {
+            assert!(g.connect_flow(&from, &to, capacity, cost));
+        }
+
+        let flow = g.min_cost_flow(&"s", &"t", 5).unwrap();
+        assert_eq!(flow.value, 5);
+        assert_eq!(flow.cost, 2 + 4 + 3 + 2);
+        assert_eq!(flow.edges[&(&"d1", &"x")], 2);
+        assert_eq!(flow.edges[&(&"d1", &"y")], 1);
+        assert_eq!(flow.edges[&(&"d2", &"x")], 1);
+        assert!(!flow.edges.contains_key(&(&"x", &"y")));
+
+        // More than the network can carry
+        let flow = g.min_cost_flow(&"s", &"t", 10).unwrap();
+        assert_eq!(flow.value, 6);
+        assert_eq!(flow.cost, 17);
+
+        assert_eq!(g.min_cost_flow(&"s", &"s", 3).unwrap().value, 0);
+        assert!(g.min_cost_flow(&"s", &"z", 3).is_none());
+    }
+
+    #[test]
+    fn min_cost_flow_by() {
+        // Plain weights as capacities, every edge costing one
+        let mut g = Graph::init(0..4usize);
+        for (a, b) in [(0, 1), (1, 3), (0, 2), (2, 1), (2, 3)] {
+            assert!(g.connect(&a, &b));
+        }
+        let flow = g
+            .min_cost_flow_by(&0, &3, 2, |_, _, capacity| FlowEdge { capacity, cost: 1 })
+            .unwrap();
+        assert_eq!((flow.value, flow.cost), (2, 4));
+        assert_eq!(flow.edges.len(), 4);
+    }
+}
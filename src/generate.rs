@@ -0,0 +1,103 @@
+use crate::graph::*;
+
+impl Graph<usize> {
+    // 0 -> 1 -> ... -> n-1
+    pub fn path_graph(n: usize) -> Self {
+        let mut graph = Graph::init(0..n);
+        for i in 1..n {
+            graph.connect(&(i - 1), &i);
+        }
+        graph
+    }
+
+    // 0 -> 1 -> ... -> n-1 -> 0
+    pub fn cycle_graph(n: usize) -> Self {
+        let mut graph = Self::path_graph(n);
+        if n > 1 {
+            graph.connect(&(n - 1), &0);
+        }
+        graph
+    }
+
+    // Every node connected to every other node in both directions
+    pub fn complete_graph(n: usize) -> Self {
+        let mut graph = Graph::init(0..n);
+        for i in 0..n {
+            for j in (0..n).filter(|&j| j != i) {
+                graph.connect(&i, &j);
+            }
+        }
+        graph
+    }
+
+    // 0 -> i for every other node
+    pub fn star_graph(n: usize) -> Self {
+        let mut graph = Graph::init(0..n);
+        for i in 1..n {
+            graph.connect(&0, &i);
+        }
+        graph
+    }
+
+    // Node at (x, y) is labelled y * w + x and connects right and down
+    pub fn grid_graph(w: usize, h: usize) -> Self {
+        let mut graph = Graph::init(0..w * h);
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                if x + 1 < w {
+                    graph.connect(&i, &(i + 1));
+                }
+                if y + 1 < h {
+                    graph.connect(&i, &(i + w));
+                }
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_and_cycle() {
+        let g = Graph::path_graph(4);
+        assert_eq!(g.edges().count(), 3);
+        assert!(g.is_connected(&2, &3));
+        assert!(!g.is_connected(&3, &0));
+
+        let g = Graph::cycle_graph(4);
+        assert_eq!(g.edges().count(), 4);
+        assert!(g.is_connected(&3, &0));
+
+        assert_eq!(Graph::cycle_graph(1).edges().count(), 0);
+        assert_eq!(Graph::path_graph(0).edges().count(), 0);
+    }
+
+    #[test]
+    fn complete_and_star() {
+        let g = Graph::complete_graph(4);
+        assert_eq!(g.edges().count(), 12);
+        assert!(g.is_biconnected(&1, &3));
+
+        let g = Graph::star_graph(5);
+        assert_eq!(g.edges().count(), 4);
+        assert_eq!(g.connections(&0).unwrap().len(), 4);
+        assert!(g.connections(&4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn grid() {
+        // 0 -> 1 -> 2
+        // |    |    |
+        // 3 -> 4 -> 5
+        let g = Graph::grid_graph(3, 2);
+        assert_eq!(g.edges().count(), 7);
+        assert!(g.is_connected(&0, &3));
+        assert!(g.is_connected(&4, &5));
+        assert!(!g.is_connected(&2, &3));
+        assert_eq!(g.bfs(&0).count(), 6);
+    }
+}
@@ -1,17 +1,22 @@
+use crate::collections::{HashMap, HashSet};
+use crate::edges::Edges;
 use crate::hash;
-use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
+use alloc::vec::Vec;
+use core::hash::Hash;
 
+// Edge, source and sink counts are kept in step by the edge and node
+// helpers
 #[derive(Debug)]
 pub struct Graph<T> {
     pub(crate) nodes: HashMap<u64, Node<T>>,
+    edge_count: usize,
+    sources: usize,
+    sinks: usize,
 }
 
 impl<T> Default for Graph<T> {
     fn default() -> Self {
-        Graph {
-            nodes: HashMap::new(),
-        }
+        Graph::with_capacity(0)
     }
 }
 
@@ -19,6 +24,293 @@ impl<T> Graph<T> {
     pub fn new() -> Self {
         Default::default()
     }
+
+    pub fn with_capacity(nodes: usize) -> Self {
+        Graph {
+            nodes: HashMap::with_capacity(nodes),
+            edge_count: 0,
+            sources: 0,
+            sinks: 0,
+        }
+    }
+
+    // For nodes built up elsewhere, the reverse index is rebuilt
+    pub(crate) fn from_nodes(nodes: HashMap<u64, Node<T>>) -> Self {
+        let mut graph = Graph {
+            nodes,
+            edge_count: 0,
+            sources: 0,
+            sinks: 0,
+        };
+        graph.reindex_incoming();
+        graph
+    }
+
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    // Fraction of the possible edges between distinct nodes that exist.
+    // Self-loops count towards it so it can go over 1
+    pub fn density(&self) -> f64 {
+        let n = self.size();
+        if n > 1 {
+            self.edge_count as f64 / (n * (n - 1)) as f64
+        } else {
+            0.0
+        }
+    }
+
+    pub fn iter_sources(&self) -> impl Iterator<Item = &T> + '_ {
+        self.nodes
+            .values()
+            .filter(|n| n.is_source())
+            .map(|n| &n.label)
+    }
+
+    pub fn iter_sinks(&self) -> impl Iterator<Item = &T> + '_ {
+        self.nodes
+            .values()
+            .filter(|n| n.is_sink())
+            .map(|n| &n.label)
+    }
+
+    // Nodes whose label matches, found as the iterator is consumed
+    pub fn find<'a, F>(&'a self, mut pred: F) -> impl Iterator<Item = NodeRef<'a, T>> + 'a
+    where
+        F: FnMut(&T) -> bool + 'a,
+    {
+        self.nodes
+            .values()
+            .filter(move |n| pred(&n.label))
+            .map(move |node| NodeRef { graph: self, node })
+    }
+
+    pub fn num_sources(&self) -> usize {
+        self.sources
+    }
+
+    pub fn num_sinks(&self) -> usize {
+        self.sinks
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        for node in self.nodes.values_mut() {
+            node.edges.shrink_to_fit();
+            node.incoming.shrink_to_fit();
+        }
+    }
+
+    pub(crate) fn indegrees(&self) -> HashMap<u64, usize> {
+        self.nodes
+            .iter()
+            .map(|(k, n)| (*k, n.incoming.len()))
+            .collect()
+    }
+
+    // Rebuilds the reverse index, for bulk edits that bypass set_edge
+    pub(crate) fn reindex_incoming(&mut self) {
+        let incoming = self.collect_incoming();
+        for (key, node) in self.nodes.iter_mut() {
+            node.incoming = incoming[key].iter().copied().collect();
+        }
+        self.recount_ends();
+    }
+
+    fn recount_ends(&mut self) {
+        self.edge_count = self.nodes.values().map(|n| n.edges.len()).sum();
+        self.sources = self.nodes.values().filter(|n| n.is_source()).count();
+        self.sinks = self.nodes.values().filter(|n| n.is_sink()).count();
+    }
+
+    // Takes the given nodes out of, or puts them back into, the source and
+    // sink counts. Done either side of an edit touching them
+    fn tally(&mut self, keys: &[u64], add: bool) {
+        for key in keys {
+            if let Some(node) = self.nodes.get(key) {
+                let (source, sink) = (node.is_source() as usize, node.is_sink() as usize);
+                if add {
+                    self.sources += source;
+                    self.sinks += sink;
+                } else {
+                    self.sources -= source;
+                    self.sinks -= sink;
+                }
+            }
+        }
+    }
+
+    fn collect_incoming(&self) -> HashMap<u64, Incoming> {
+        let mut incoming = self
+            .nodes
+            .keys()
+            .map(|k| (*k, Vec::new()))
+            .collect::<HashMap<_, _>>();
+        for (key, node) in &self.nodes {
+            for (target, weight) in &node.edges {
+                incoming.get_mut(target).unwrap().push((*key, *weight));
+            }
+        }
+        incoming
+    }
+
+    // Adds, reweights or (with None) removes the edge between two existing
+    // nodes, keeping the target's reverse index in step. Returns the old
+    // weight
+    pub(crate) fn set_edge(&mut self, from: u64, to: u64, weight: Option<i64>) -> Option<i64> {
+        let edges = &mut self.nodes.get_mut(&from).unwrap().edges;
+        let was_sink = edges.is_empty();
+        let previous = match weight {
+            Some(weight) => edges.insert(to, weight),
+            None => edges.remove(&to),
+        };
+        let is_sink = edges.is_empty();
+        self.sinks = self.sinks + is_sink as usize - was_sink as usize;
+        match (previous, weight) {
+            (None, Some(_)) => self.edge_count += 1,
+            (Some(_), None) => self.edge_count -= 1,
+            _ => (),
+        }
+
+        let incoming = &mut self.nodes.get_mut(&to).unwrap().incoming;
+        let was_source = incoming.is_empty();
+        match weight {
+            Some(weight) => incoming.insert(from, weight),
+            None => incoming.remove(&from),
+        };
+        let is_source = incoming.is_empty();
+        self.sources = self.sources + is_source as usize - was_source as usize;
+        previous
+    }
+
+    // New edges get a weight of 1, an existing edge keeps its weight
+    pub(crate) fn add_edge(&mut self, from: u64, to: u64) {
+        if !self.nodes[&from].edges.contains_key(&to) {
+            self.set_edge(from, to, Some(1));
+        }
+    }
+
+    // Inserts a node along with its outgoing edges. Edges already pointing
+    // at the key carry over, the outgoing edges of a replaced node don't
+    pub(crate) fn insert_node(&mut self, key: u64, mut node: Node<T>) -> Option<Node<T>> {
+        let mut touched = node.edges.keys().copied().collect::<Vec<_>>();
+        touched.push(key);
+        if let Some(previous) = self.nodes.get(&key) {
+            touched.extend(previous.edges.keys());
+        }
+        touched.sort_unstable();
+        touched.dedup();
+        self.tally(&touched, false);
+
+        node.incoming = match self.nodes.get_mut(&key) {
+            Some(previous) => core::mem::take(&mut previous.incoming),
+            None => Edges::new(),
+        };
+        let targets = node.edges.iter().map(|(t, w)| (*t, *w)).collect::<Vec<_>>();
+        self.edge_count += targets.len();
+        let previous = self.nodes.insert(key, node);
+        if let Some(previous) = &previous {
+            self.edge_count -= previous.edges.len();
+            for target in previous.edges.keys() {
+                self.nodes.get_mut(target).unwrap().incoming.remove(&key);
+            }
+        }
+        for (target, weight) in targets {
+            self.nodes
+                .get_mut(&target)
+                .unwrap()
+                .incoming
+                .insert(key, weight);
+        }
+        self.tally(&touched, true);
+        previous
+    }
+
+    // Removes a node and every edge touching it, returning the node along
+    // with the sources and weights of its incoming edges. Only the node's
+    // own neighbours are touched
+    pub(crate) fn remove_node(&mut self, key: u64) -> Option<(Node<T>, Incoming)> {
+        let node = self.nodes.get(&key)?;
+        let mut touched = node.edges.keys().copied().collect::<Vec<_>>();
+        touched.extend(node.incoming.keys());
+        touched.push(key);
+        touched.sort_unstable();
+        touched.dedup();
+        self.tally(&touched, false);
+
+        let mut node = self.nodes.remove(&key).unwrap();
+        for target in node.edges.keys().filter(|t| **t != key) {
+            self.nodes.get_mut(target).unwrap().incoming.remove(&key);
+        }
+
+        let incoming = core::mem::take(&mut node.incoming)
+            .iter()
+            .filter(|(from, _)| **from != key)
+            .map(|(from, weight)| (*from, *weight))
+            .collect::<Vec<_>>();
+        for (from, _) in &incoming {
+            self.nodes.get_mut(from).unwrap().edges.remove(&key);
+        }
+        self.edge_count -= node.edges.len() + incoming.len();
+        self.tally(&touched, true);
+        Some((node, incoming))
+    }
+
+    pub(crate) fn retain_keys(&mut self, keep: &HashSet<u64>) {
+        self.nodes.retain(|k, _| keep.contains(k));
+        for node in self.nodes.values_mut() {
+            node.edges.retain(|t, _| keep.contains(t));
+            node.incoming.retain(|f, _| keep.contains(f));
+        }
+        self.recount_ends();
+    }
+
+    // Neighbours of every node ignoring edge direction and self-loops
+    #[cfg(feature = "std")]
+    pub(crate) fn undirected(&self) -> HashMap<u64, HashSet<u64>> {
+        let mut undirected = self
+            .nodes
+            .keys()
+            .map(|k| (*k, HashSet::new()))
+            .collect::<HashMap<_, _>>();
+        for (key, node) in &self.nodes {
+            for target in node.edges.keys().filter(|t| *t != key) {
+                undirected.get_mut(key).unwrap().insert(*target);
+                undirected.get_mut(target).unwrap().insert(*key);
+            }
+        }
+        undirected
+    }
+}
+
+impl<T: AsRef<str>> Graph<T> {
+    pub fn find_by_substring<'a>(
+        &'a self,
+        pattern: &'a str,
+    ) -> impl Iterator<Item = NodeRef<'a, T>> + 'a {
+        self.find(move |label| label.as_ref().contains(pattern))
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn find_by_regex<'a>(
+        &'a self,
+        regex: &'a regex::Regex,
+    ) -> impl Iterator<Item = NodeRef<'a, T>> + 'a {
+        self.find(move |label| regex.is_match(label.as_ref()))
+    }
 }
 
 impl<T: Hash + Eq + Default> Graph<T> {
@@ -38,22 +330,183 @@ impl<T: Hash + Eq> Graph<T> {
     }
 
     pub fn add(&mut self, label: T) {
+        self.add_with_capacity(label, 0);
+    }
+
+    // Room for `edges` outgoing edges without rehashing
+    pub fn add_with_capacity(&mut self, label: T, edges: usize) {
         let key = hash(&label);
         let node = Node {
             label,
-            edges: HashMap::new(),
+            edges: Edges::with_capacity(edges),
+            incoming: Edges::new(),
         };
-        self.nodes.insert(key, node);
+        self.insert_node(key, node);
+    }
+
+    pub fn reserve_edges(&mut self, label: &T, additional: usize) -> bool {
+        match self.nodes.get_mut(&hash(label)) {
+            Some(node) => {
+                node.edges.reserve(additional);
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn remove(&mut self, label: &T) -> Option<Node<T>> {
-        let key = hash(label);
-        let node = self.nodes.remove(&key)?;
+        let (node, _) = self.remove_node(hash(label))?;
+        Some(node)
+    }
+
+    // Says which labels were present, a repeated label is only removed the
+    // first time
+    pub fn remove_all<'a, I>(&mut self, labels: I) -> Vec<bool>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        labels
+            .into_iter()
+            .map(|label| self.remove_node(hash(label)).is_some())
+            .collect()
+    }
 
-        for other in self.nodes.values_mut() {
-            other.disconnect_from(label);
+    // Says which edges were there to be removed
+    pub fn disconnect_all<'a, I>(&mut self, edges: I) -> Vec<bool>
+    where
+        I: IntoIterator<Item = (&'a T, &'a T)>,
+        T: 'a,
+    {
+        edges
+            .into_iter()
+            .map(|(from, to)| {
+                let (from, to) = (hash(from), hash(to));
+                self.nodes.contains_key(&from)
+                    && self.nodes.contains_key(&to)
+                    && self.set_edge(from, to, None).is_some()
+            })
+            .collect()
+    }
+
+    // Removes everything not reachable from the roots, returning how many
+    pub fn prune_unreachable<'a, I>(&mut self, roots: I) -> usize
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        let mut keep = HashSet::new();
+        for root in roots.into_iter().map(hash) {
+            if self.nodes.contains_key(&root) && !keep.contains(&root) {
+                keep.extend(self.hop_distances(root).into_keys());
+            }
         }
-        Some(node)
+        self.prune_to(keep)
+    }
+
+    // Removes everything that can't reach one of the sinks, returning how many
+    pub fn prune_unreachable_to<'a, I>(&mut self, sinks: I) -> usize
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        let mut stack = sinks
+            .into_iter()
+            .map(hash)
+            .filter(|k| self.nodes.contains_key(k))
+            .collect::<Vec<_>>();
+        let mut keep = HashSet::new();
+        while let Some(key) = stack.pop() {
+            if keep.insert(key) {
+                stack.extend(self.nodes[&key].incoming.keys());
+            }
+        }
+        self.prune_to(keep)
+    }
+
+    // Repeatedly removes nodes with fewer than min_in incoming or min_out
+    // outgoing edges until none are left, returning how many went. Only the
+    // neighbours of a removed node are rechecked
+    pub fn prune_by_degree(&mut self, min_in: usize, min_out: usize) -> usize {
+        let violates = |node: &Node<T>| node.incoming.len() < min_in || node.edges.len() < min_out;
+        let mut pending = self
+            .nodes
+            .iter()
+            .filter(|(_, n)| violates(n))
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+        let before = self.size();
+        while let Some(key) = pending.pop() {
+            if !self.nodes.get(&key).is_some_and(violates) {
+                continue;
+            }
+            let (node, incoming) = self.remove_node(key).unwrap();
+            let neighbors = node
+                .edges
+                .keys()
+                .copied()
+                .chain(incoming.iter().map(|(f, _)| *f));
+            for neighbor in neighbors {
+                if self.nodes.get(&neighbor).is_some_and(violates) {
+                    pending.push(neighbor);
+                }
+            }
+        }
+        before - self.size()
+    }
+
+    // Drops edges lighter than min_weight, returning how many went. Nodes
+    // are kept even if it leaves them isolated
+    pub fn threshold(&mut self, min_weight: i64) -> usize {
+        let light = self
+            .nodes
+            .iter()
+            .flat_map(|(k, n)| {
+                n.edges
+                    .iter()
+                    .filter(|(_, w)| **w < min_weight)
+                    .map(move |(t, _)| (*k, *t))
+            })
+            .collect::<Vec<_>>();
+        for (from, to) in &light {
+            self.set_edge(*from, *to, None);
+        }
+        light.len()
+    }
+
+    // Scales every weight by factor, rounding to the nearest whole number
+    // with halves away from zero, and drops the edges that reach 0. Returns
+    // how many were dropped. A weight of 1 survives any factor of at least
+    // 0.5, so observations counted in larger steps fade more smoothly
+    pub fn decay(&mut self, factor: f64) -> usize {
+        let edges = self
+            .nodes
+            .iter()
+            .flat_map(|(k, n)| n.edges.iter().map(move |(t, w)| (*k, *t, *w)))
+            .collect::<Vec<_>>();
+        let mut dropped = 0;
+        for (from, to, weight) in edges {
+            // By hand, as f64::round needs std
+            let scaled = weight as f64 * factor;
+            let weight = if scaled < 0.0 {
+                scaled - 0.5
+            } else {
+                scaled + 0.5
+            } as i64;
+            if weight == 0 {
+                dropped += 1;
+                self.set_edge(from, to, None);
+            } else {
+                self.set_edge(from, to, Some(weight));
+            }
+        }
+        dropped
+    }
+
+    fn prune_to(&mut self, keep: HashSet<u64>) -> usize {
+        let before = self.size();
+        self.retain_keys(&keep);
+        before - self.size()
     }
 
     pub fn connections(&self, label: &T) -> Option<HashSet<&T>> {
@@ -68,54 +521,268 @@ impl<T: Hash + Eq> Graph<T> {
         Some(res)
     }
 
+    pub fn outdegree(&self, label: &T) -> Option<usize> {
+        Some(self.get(label)?.edges.len())
+    }
+
+    pub fn indegree(&self, label: &T) -> Option<usize> {
+        Some(self.get(label)?.incoming.len())
+    }
+
+    pub fn sources(&self) -> HashSet<&T> {
+        self.iter_sources().collect()
+    }
+
+    pub fn sinks(&self) -> HashSet<&T> {
+        self.iter_sinks().collect()
+    }
+
+    pub fn node(&self, label: &T) -> Option<NodeRef<'_, T>> {
+        Some(NodeRef {
+            graph: self,
+            node: self.get(label)?,
+        })
+    }
+
+    // Nodes with an edge to themselves, e.g. states that can stay put
+    pub fn self_loops(&self) -> HashSet<&T> {
+        self.nodes
+            .iter()
+            .filter(|(k, n)| n.edges.contains_key(k))
+            .map(|(_, n)| &n.label)
+            .collect()
+    }
+
+    pub fn contains(&self, label: &T) -> bool {
+        self.nodes.contains_key(&hash(label))
+    }
+
+    // False rather than None when either end is missing, checked without
+    // building anything
+    pub fn contains_edge(&self, from: &T, to: &T) -> bool {
+        self.get(from)
+            .is_some_and(|n| n.edges.contains_key(&hash(to)))
+    }
+
     pub fn is_connected(&self, from: &T, to: &T) -> bool {
-        let node = self.get(from);
-        node.is_some() && node.unwrap().is_adjacent_to(to)
+        self.contains_edge(from, to)
     }
 
     pub fn connect(&mut self, from: &T, to: &T) -> bool {
         let a = hash(&from);
         let b = hash(&to);
         let bb = self.nodes.contains_key(&b);
-        let na = self.nodes.get_mut(&a);
-        if bb && na.is_some() {
-            na.unwrap().connect_to(to);
+        let ba = self.nodes.contains_key(&a);
+        if bb && ba {
+            self.add_edge(a, b);
             true
         } else {
             false
         }
     }
 
+    // Sets the weight whether or not the edge was already there
+    pub fn connect_weighted(&mut self, from: &T, to: &T, weight: i64) -> bool {
+        let (from, to) = (hash(from), hash(to));
+        if !self.nodes.contains_key(&from) || !self.nodes.contains_key(&to) {
+            return false;
+        }
+        self.set_edge(from, to, Some(weight));
+        true
+    }
+
+    // Adds delta to the weight, with a missing edge starting from 0, and
+    // gives back the new weight
+    pub fn increment_weight(&mut self, from: &T, to: &T, delta: i64) -> Option<i64> {
+        let (from, to) = (hash(from), hash(to));
+        if !self.nodes.contains_key(&to) {
+            return None;
+        }
+        let weight = self.nodes.get(&from)?.edges.get(&to).copied().unwrap_or(0) + delta;
+        self.set_edge(from, to, Some(weight));
+        Some(weight)
+    }
+
     pub fn disconnect(&mut self, from: &T, to: &T) -> bool {
         let a = hash(&from);
         let b = hash(&to);
         let bb = self.nodes.contains_key(&b);
-        let na = self.nodes.get_mut(&a);
-        if bb && na.is_some() {
-            na.unwrap().disconnect_from(to);
+        let ba = self.nodes.contains_key(&a);
+        if bb && ba {
+            self.set_edge(a, b, None);
             true
         } else {
             false
         }
     }
 
+    // Unlike disconnect, None tells a missing edge apart from a removed one
+    pub fn remove_edge(&mut self, from: &T, to: &T) -> Option<i64> {
+        let (from, to) = (hash(from), hash(to));
+        if !self.nodes.get(&from)?.edges.contains_key(&to) {
+            return None;
+        }
+        self.set_edge(from, to, None)
+    }
+
     pub fn is_biconnected(&self, a: &T, b: &T) -> bool {
         self.is_connected(a, b) && self.is_connected(b, a)
     }
 
+    // Treats a and b as an undirected pair, adding both directions or
+    // neither if either node is missing. a to itself is a single self-loop
     pub fn biconnect(&mut self, a: &T, b: &T) -> bool {
-        self.connect(a, b) && self.connect(b, a)
+        self.set_pair(a, b, true)
     }
 
+    // Removes whichever directions exist, false only if a node is missing
     pub fn bidisconnect(&mut self, a: &T, b: &T) -> bool {
-        self.disconnect(a, b) && self.disconnect(b, a)
+        self.set_pair(a, b, false)
+    }
+
+    fn set_pair(&mut self, a: &T, b: &T, connect: bool) -> bool {
+        let (a, b) = (hash(a), hash(b));
+        if !self.nodes.contains_key(&a) || !self.nodes.contains_key(&b) {
+            return false;
+        }
+        for (from, to) in [(a, b), (b, a)] {
+            if connect {
+                self.add_edge(from, to);
+            } else {
+                self.set_edge(from, to, None);
+            }
+        }
+        true
     }
 }
 
+impl<T: Hash + Eq + Clone> Graph<T> {
+    // Counts one sighting of a followed by b, adding either node if it's
+    // new. Returns the edge's weight, i.e. how often the pair was seen
+    pub fn observe(&mut self, a: &T, b: &T) -> i64 {
+        for label in [a, b] {
+            if !self.contains(label) {
+                self.add(label.clone());
+            }
+        }
+        self.increment_weight(a, b, 1).unwrap()
+    }
+}
+
+// Cloned results that don't hold a borrow, so the graph can be changed
+// based on them
+impl<T: Hash + Eq + Clone> Graph<T> {
+    pub fn sources_owned(&self) -> HashSet<T> {
+        self.sources().into_iter().cloned().collect()
+    }
+
+    pub fn sinks_owned(&self) -> HashSet<T> {
+        self.sinks().into_iter().cloned().collect()
+    }
+
+    pub fn connections_owned(&self, label: &T) -> Option<HashSet<T>> {
+        Some(self.connections(label)?.into_iter().cloned().collect())
+    }
+
+    pub fn subgraph<'a, I>(&self, labels: I) -> Graph<T>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        let keys = labels.into_iter().map(hash).collect();
+        self.subgraph_keys(&keys)
+    }
+
+    pub(crate) fn subgraph_keys(&self, keys: &HashSet<u64>) -> Graph<T> {
+        let nodes = self
+            .nodes
+            .iter()
+            .filter(|(k, _)| keys.contains(*k))
+            .map(|(k, n)| {
+                let edges = n
+                    .edges
+                    .iter()
+                    .filter(|(t, _)| keys.contains(*t))
+                    .map(|(t, w)| (*t, *w))
+                    .collect();
+                let node = Node {
+                    label: n.label.clone(),
+                    edges,
+                    incoming: Edges::new(),
+                };
+                (*k, node)
+            })
+            .collect();
+        Graph::from_nodes(nodes)
+    }
+}
+
+// Source and weight of each edge into a node
+pub(crate) type Incoming = Vec<(u64, i64)>;
+
 #[derive(Debug)]
 pub struct Node<T> {
     pub label: T,
-    pub(crate) edges: HashMap<u64, i64>, // key is target, value is weight
+    pub(crate) edges: Edges,    // key is target, value is weight
+    pub(crate) incoming: Edges, // key is source, value is weight
+}
+
+// Read-only view of a node, edges are given by label rather than key
+pub struct NodeRef<'a, T> {
+    graph: &'a Graph<T>,
+    node: &'a Node<T>,
+}
+
+impl<'a, T> Clone for NodeRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for NodeRef<'a, T> {}
+
+impl<'a, T> NodeRef<'a, T> {
+    pub fn label(&self) -> &'a T {
+        &self.node.label
+    }
+
+    pub fn outdegree(&self) -> usize {
+        self.node.edges.len()
+    }
+
+    pub fn indegree(&self) -> usize {
+        self.node.incoming.len()
+    }
+
+    pub fn neighbors(&self) -> impl Iterator<Item = &'a T> + 'a {
+        let graph = self.graph;
+        self.node.edges.keys().map(move |k| &graph.nodes[k].label)
+    }
+
+    // Targets along with the weight of the edge to each
+    pub fn edges(&self) -> impl Iterator<Item = (&'a T, i64)> + 'a {
+        let graph = self.graph;
+        self.node
+            .edges
+            .iter()
+            .map(move |(k, w)| (&graph.nodes[k].label, *w))
+    }
+}
+
+impl<'a, T: Hash> NodeRef<'a, T> {
+    pub fn weight(&self, to: &T) -> Option<i64> {
+        self.node.edges.get(&hash(to)).copied()
+    }
+}
+
+impl<T> Node<T> {
+    fn is_source(&self) -> bool {
+        self.incoming.is_empty()
+    }
+
+    fn is_sink(&self) -> bool {
+        self.edges.is_empty()
+    }
 }
 
 impl<T: Hash> Node<T> {
@@ -126,7 +793,9 @@ impl<T: Hash> Node<T> {
 
     pub fn connect_to(&mut self, to: &T) {
         let target = hash(to);
-        self.edges.insert(target, 1);
+        if !self.edges.contains_key(&target) {
+            self.edges.insert(target, 1);
+        }
     }
 
     pub fn disconnect_from(&mut self, from: &T) {
@@ -139,6 +808,60 @@ impl<T: Hash> Node<T> {
 mod tests {
     use super::*;
 
+    fn assert_indexed<T>(g: &Graph<T>) {
+        for (key, incoming) in g.collect_incoming() {
+            let mut indexed = g.nodes[&key]
+                .incoming
+                .iter()
+                .map(|(f, w)| (*f, *w))
+                .collect::<Vec<_>>();
+            let mut incoming = incoming;
+            indexed.sort();
+            incoming.sort();
+            assert_eq!(indexed, incoming);
+        }
+        let edges = g.nodes.values().map(|n| n.edges.len()).sum::<usize>();
+        assert_eq!(g.edge_count(), edges);
+        assert_eq!(g.num_sources(), g.iter_sources().count());
+        assert_eq!(g.num_sinks(), g.iter_sinks().count());
+    }
+
+    #[test]
+    fn reverse_index() {
+        let mut g = Graph::init('a'..='e');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'b'));
+        assert!(g.connect(&'c', &'b'));
+        assert!(g.connect(&'b', &'d'));
+        assert!(g.disconnect(&'e', &'d'));
+        assert_indexed(&g);
+        assert_eq!(g.indegree(&'b'), Some(3));
+        g.set_edge(hash(&'c'), hash(&'b'), Some(7));
+        assert_indexed(&g);
+
+        // Replacing b drops its own edges but keeps those into it
+        g.add('b');
+        assert_indexed(&g);
+        assert_eq!(g.indegree(&'b'), Some(2));
+        assert_eq!(g.indegree(&'d'), Some(0));
+
+        assert!(g.connect(&'b', &'b'));
+        assert!(g.remove(&'b').is_some());
+        assert_indexed(&g);
+        assert_eq!(g.sources().len(), 4);
+
+        let mut g = Graph::init('a'..='e');
+        for (a, b) in &[('a', 'b'), ('b', 'c'), ('c', 'd'), ('d', 'e'), ('e', 'a')] {
+            assert!(g.connect(a, b));
+        }
+        assert_indexed(&g.subgraph(&['a', 'b', 'c']));
+        assert_eq!(g.prune_unreachable_to(&['c']), 0);
+        g.retain_keys(&vec![hash(&'a'), hash(&'b')].into_iter().collect());
+        assert_indexed(&g);
+        assert_eq!(g.indegree(&'a'), Some(0));
+    }
+
     #[test]
     fn basic() {
         let mut g = Graph::init('a'..='c');
@@ -154,16 +877,386 @@ mod tests {
         assert!(g.connections(&'c').unwrap().contains(&&'a'));
 
         assert!(g.connections(&'d').is_none());
+        assert_eq!(g.indegree(&'a'), Some(2));
+        assert_eq!(g.outdegree(&'b'), Some(1));
+        assert!(g.sources().is_empty());
 
         // b <-> a <- c
         assert!(g.disconnect(&'a', &'c'));
         assert!(!g.connections(&'a').unwrap().contains(&&'c'));
         assert!(g.connections(&'c').unwrap().contains(&&'a'));
+        assert!(g.sources().contains(&'c'));
+        assert!(!g.sinks().contains(&'c'));
 
         // b <-x-> c
         assert!(g.remove(&'a').is_some());
         assert!(g.connections(&'a').is_none());
         assert!(g.connections(&'b').unwrap().is_empty());
         assert!(g.connections(&'c').unwrap().is_empty());
+        assert_eq!(g.size(), 2);
+        assert_eq!(g.sinks().len(), 2);
+    }
+
+    #[test]
+    fn capacity() {
+        let mut g = Graph::with_capacity(100);
+        assert!(g.capacity() >= 100);
+
+        g.add_with_capacity('a', 10);
+        g.add('b');
+        assert!(g.reserve_edges(&'b', 10));
+        assert!(!g.reserve_edges(&'c', 10));
+        assert!(g.get(&'a').unwrap().edges.capacity() >= 10);
+        assert!(g.connect(&'a', &'b'));
+
+        g.shrink_to_fit();
+        assert!(g.capacity() < 100);
+        assert!(g.get(&'b').unwrap().edges.capacity() < 10);
+        assert!(g.is_connected(&'a', &'b'));
+
+        g.reserve(50);
+        assert!(g.capacity() >= 52);
+    }
+
+    #[test]
+    fn prune() {
+        // a -> b -> c, d -> b, e -> f
+        let mut g = Graph::init('a'..='f');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'d', &'b'));
+        assert!(g.connect(&'e', &'f'));
+
+        assert_eq!(g.prune_unreachable(&['a', 'z']), 3);
+        assert_eq!(g.size(), 3);
+        assert!(g.connections(&'d').is_none());
+        assert!(g.is_connected(&'b', &'c'));
+
+        let mut g = Graph::init('a'..='f');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'d', &'b'));
+        assert!(g.connect(&'b', &'e'));
+
+        assert_eq!(g.prune_unreachable_to(&['c']), 2);
+        assert!(g.connections(&'e').is_none());
+        assert_eq!(g.connections(&'b').unwrap().len(), 1);
+        assert!(g.connections(&'d').is_some());
+    }
+
+    #[test]
+    fn subgraph() {
+        let mut g = Graph::init('a'..='c');
+
+        // a -> b -> c -> a
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'c', &'a'));
+
+        let sub = g.subgraph(&['a', 'b']);
+        assert_eq!(sub.size(), 2);
+        assert!(sub.is_connected(&'a', &'b'));
+        assert!(sub.connections(&'b').unwrap().is_empty());
+        assert!(sub.connections(&'c').is_none());
+    }
+
+    #[test]
+    fn batch_removal() {
+        let mut g = Graph::init(0..6usize);
+        for (a, b) in [(0, 1), (1, 2), (2, 0), (3, 1), (4, 5), (5, 5), (5, 3)] {
+            assert!(g.connect(&a, &b));
+        }
+
+        assert_eq!(g.remove_all(&[1, 9, 5, 1]), vec![true, false, true, false]);
+        assert_eq!(g.size(), 4);
+        assert_eq!(g.edges().count(), 1);
+        assert!(g.is_connected(&2, &0));
+        assert_eq!(g.connections(&3).unwrap().len(), 0);
+        assert_indexed(&g);
+
+        assert_eq!(g.remove_all(&[2, 0]), vec![true, true]);
+        assert_eq!(g.size(), 2);
+        assert_indexed(&g);
+
+        let mut g = Graph::init(0..4usize);
+        for (a, b) in [(0, 1), (1, 2), (2, 3)] {
+            assert!(g.connect(&a, &b));
+        }
+        let results = g.disconnect_all(vec![(&0, &1), (&0, &1), (&3, &0), (&2, &3), (&2, &9)]);
+        assert_eq!(results, vec![true, false, false, true, false]);
+        assert_eq!(g.edges().count(), 1);
+        assert_indexed(&g);
+    }
+
+    #[test]
+    fn contains() {
+        let mut g = Graph::init('a'..='b');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.contains(&'a'));
+        assert!(!g.contains(&'z'));
+        assert!(g.contains_edge(&'a', &'b'));
+        assert!(!g.contains_edge(&'b', &'a'));
+        assert!(!g.contains_edge(&'a', &'z'));
+        assert!(!g.contains_edge(&'z', &'a'));
+    }
+
+    #[test]
+    fn node_handles() {
+        let mut g = Graph::init('a'..='c');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'a', &'c'));
+        assert!(g.connect(&'c', &'a'));
+        g.set_edge(hash(&'a'), hash(&'c'), Some(4));
+
+        let a = g.node(&'a').unwrap();
+        assert_eq!(a.label(), &'a');
+        assert_eq!((a.outdegree(), a.indegree()), (2, 1));
+        let mut neighbors = a.neighbors().collect::<Vec<_>>();
+        neighbors.sort();
+        assert_eq!(neighbors, vec![&'b', &'c']);
+        let mut edges = a.edges().collect::<Vec<_>>();
+        edges.sort();
+        assert_eq!(edges, vec![(&'b', 1), (&'c', 4)]);
+        assert_eq!(a.weight(&'c'), Some(4));
+        assert_eq!(a.weight(&'a'), None);
+        assert!(g.node(&'z').is_none());
+    }
+
+    #[test]
+    fn source_and_sink_counts() {
+        let mut g = Graph::init('a'..='d');
+        assert_eq!((g.num_sources(), g.num_sinks()), (4, 4));
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'c', &'c'));
+        assert_eq!((g.num_sources(), g.num_sinks()), (2, 1));
+        assert_eq!(g.iter_sources().count(), 2);
+        assert_eq!(g.iter_sinks().collect::<Vec<_>>(), vec![&'d']);
+        assert_indexed(&g);
+
+        g.add('c');
+        assert_indexed(&g);
+        assert_eq!(g.num_sinks(), 2);
+        assert!(g.connect(&'d', &'a'));
+        assert!(g.remove(&'b').is_some());
+        assert_indexed(&g);
+        assert_eq!((g.num_sources(), g.num_sinks()), (2, 2));
+        assert_eq!(g.remove_all(&['a', 'c']), vec![true, true]);
+        assert_eq!((g.num_sources(), g.num_sinks()), (1, 1));
+    }
+
+    #[test]
+    fn edge_count() {
+        let mut g = Graph::init('a'..='d');
+        assert_eq!((g.edge_count(), g.density()), (0, 0.0));
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'b'));
+        assert!(g.connect(&'c', &'b'));
+        assert!(g.connect(&'b', &'d'));
+        assert_eq!(g.edge_count(), 4);
+        assert_eq!(g.density(), 4.0 / 12.0);
+
+        assert!(g.disconnect(&'a', &'b'));
+        assert!(g.disconnect(&'a', &'b'));
+        assert_eq!(g.edge_count(), 3);
+        assert!(g.remove(&'b').is_some());
+        assert_eq!(g.edge_count(), 0);
+        assert_indexed(&g);
+        assert_eq!(Graph::init(0..1usize).density(), 0.0);
+    }
+
+    #[test]
+    fn prune_by_degree() {
+        // a, b and c form a core, d hangs off it and e points at d
+        let mut g = Graph::init('a'..='e');
+        for (a, b) in [('a', 'b'), ('b', 'c'), ('c', 'a'), ('c', 'd'), ('e', 'd')] {
+            assert!(g.connect(&a, &b));
+        }
+        assert!(g.biconnect(&'a', &'b'));
+
+        assert_eq!(g.prune_by_degree(0, 0), 0);
+
+        // d is the only sink, losing it leaves e with nowhere to go
+        assert_eq!(g.prune_by_degree(0, 1), 2);
+        assert_eq!(g.size(), 3);
+        assert_indexed(&g);
+
+        // Nothing has two of each, so the peeling takes everything
+        assert_eq!(g.prune_by_degree(2, 2), 3);
+        assert_eq!(g.size(), 0);
+    }
+
+    #[test]
+    fn self_loops() {
+        // idle -> idle, idle -> busy, busy -> busy, busy -> idle
+        let mut g = Graph::init(vec!["idle", "busy"]);
+        for (a, b) in [
+            ("idle", "idle"),
+            ("idle", "busy"),
+            ("busy", "busy"),
+            ("busy", "idle"),
+        ] {
+            assert!(g.connect(&a, &b));
+        }
+        assert_eq!(g.self_loops(), HashSet::from([&"idle", &"busy"]));
+        assert_eq!(g.edge_count(), 4);
+        assert_eq!(g.indegree(&"idle"), Some(2));
+        assert_eq!(g.bfs(&"idle").count(), 2);
+        assert_eq!(g.dfs(&"busy").count(), 2);
+        assert_eq!(g.edges().filter(|e| e.from == e.to).count(), 2);
+
+        assert!(g.disconnect(&"busy", &"busy"));
+        assert_eq!(g.self_loops(), HashSet::from([&"idle"]));
+        assert!(g.remove(&"idle").is_some());
+        assert_indexed(&g);
+    }
+
+    #[test]
+    fn find() {
+        let g = Graph::init(vec!["core/graph", "core/iter", "util/path", "web"]);
+        let mut found = g
+            .find_by_substring("core/")
+            .map(|n| *n.label())
+            .collect::<Vec<_>>();
+        found.sort_unstable();
+        assert_eq!(found, vec!["core/graph", "core/iter"]);
+        assert_eq!(g.find(|l| l.len() == 3).count(), 1);
+        assert_eq!(g.find_by_substring("").count(), 4);
+        assert!(g.find_by_substring("x").next().is_none());
+
+        #[cfg(feature = "regex")]
+        {
+            let regex = regex::Regex::new(r"^\w+/(graph|path)$").unwrap();
+            assert_eq!(g.find_by_regex(&regex).count(), 2);
+        }
+    }
+
+    #[test]
+    fn biconnect() {
+        let mut g = Graph::init('a'..='c');
+        assert!(!g.biconnect(&'a', &'z'));
+        assert!(!g.biconnect(&'z', &'a'));
+        assert_eq!(g.edge_count(), 0);
+
+        assert!(g.biconnect(&'a', &'b'));
+        assert!(g.is_biconnected(&'b', &'a'));
+        assert!(g.biconnect(&'c', &'c'));
+        assert_eq!(g.edge_count(), 3);
+
+        // Half a pair is still removed
+        assert!(g.disconnect(&'b', &'a'));
+        assert!(g.bidisconnect(&'b', &'a'));
+        assert!(!g.contains_edge(&'a', &'b'));
+        assert!(g.bidisconnect(&'c', &'c'));
+        assert!(!g.bidisconnect(&'c', &'z'));
+        assert_eq!(g.edge_count(), 0);
+        assert_indexed(&g);
+    }
+
+    #[test]
+    fn weights() {
+        use crate::base::GraphBase;
+        let mut g = Graph::init(vec!["rust", "graph", "crate"]);
+        assert!(g.connect_weighted(&"rust", &"graph", 5));
+        assert!(g.connect(&"rust", &"graph"));
+        assert!(g.biconnect(&"rust", &"graph"));
+        assert_eq!(g.weight(&"rust", &"graph"), Some(5));
+        assert_eq!(g.weight(&"graph", &"rust"), Some(1));
+        assert!(g.connect_weighted(&"rust", &"graph", 2));
+        assert!(!g.connect_weighted(&"rust", &"x", 2));
+
+        // Co-occurrence counts
+        for (a, b) in [("rust", "crate"), ("rust", "crate"), ("crate", "graph")] {
+            g.increment_weight(&a, &b, 1);
+        }
+        assert_eq!(g.weight(&"rust", &"crate"), Some(2));
+        assert_eq!(g.increment_weight(&"rust", &"crate", -2), Some(0));
+        assert_eq!(g.increment_weight(&"x", &"crate", 1), None);
+        assert_eq!(g.edge_count(), 4);
+        assert_indexed(&g);
+    }
+
+    #[test]
+    fn observe() {
+        let events = ["login", "view", "view", "buy", "login", "view", "logout"];
+        let mut g = Graph::new();
+        for pair in events.windows(2) {
+            g.observe(&pair[0], &pair[1]);
+        }
+        assert_eq!(g.size(), 4);
+        assert_eq!(g.edge_count(), 5);
+        assert_eq!(g.observe(&"login", &"view"), 3);
+        assert_eq!(g.threshold(2), 4);
+        assert_eq!(g.edges().count(), 1);
+        assert_eq!(g.size(), 4);
+        assert_eq!(g.threshold(2), 0);
+        assert_indexed(&g);
+    }
+
+    #[test]
+    fn decay() {
+        use crate::base::GraphBase;
+        let mut g = Graph::init('a'..='c');
+        g.increment_weight(&'a', &'b', 100);
+        g.increment_weight(&'b', &'c', 10);
+        g.increment_weight(&'c', &'a', -40);
+        assert_eq!(g.decay(0.5), 0);
+        assert_eq!(g.weight(&'a', &'b'), Some(50));
+        assert_eq!(g.weight(&'c', &'a'), Some(-20));
+        assert_eq!(g.decay(0.09), 1);
+        assert_eq!(g.weight(&'a', &'b'), Some(5));
+        assert_eq!(g.weight(&'c', &'a'), Some(-2));
+        assert!(!g.contains_edge(&'b', &'c'));
+
+        // Weight 1 rounds up to 1 down to a factor of one half, not below
+        g.increment_weight(&'b', &'c', 1);
+        assert_eq!(g.decay(0.5), 0);
+        assert_eq!(g.weight(&'b', &'c'), Some(1));
+        assert_eq!(g.weight(&'a', &'b'), Some(3));
+        assert_eq!(g.weight(&'c', &'a'), Some(-1));
+        assert_eq!(g.decay(0.49), 2);
+        assert_eq!(g.weight(&'a', &'b'), Some(1));
+        assert_eq!(g.decay(0.0), 1);
+        assert_eq!(g.edge_count(), 0);
+        assert_indexed(&g);
+    }
+
+    #[test]
+    fn remove_edge() {
+        let mut g = Graph::init('a'..='b');
+        assert!(g.connect(&'a', &'b'));
+        g.set_edge(hash(&'a'), hash(&'b'), Some(3));
+
+        assert_eq!(g.remove_edge(&'b', &'a'), None);
+        assert_eq!(g.remove_edge(&'a', &'z'), None);
+        assert_eq!(g.remove_edge(&'z', &'a'), None);
+        assert_eq!(g.remove_edge(&'a', &'b'), Some(3));
+        assert_eq!(g.remove_edge(&'a', &'b'), None);
+        assert_eq!(g.edge_count(), 0);
+        assert_indexed(&g);
+    }
+
+    #[test]
+    fn owned_results() {
+        let mut g = Graph::init(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]);
+        let (a, b, c) = ("a".to_owned(), "b".to_owned(), "c".to_owned());
+        assert!(g.connect(&a, &b));
+        assert!(g.connect(&a, &c));
+
+        // Mutating while holding the results
+        for sink in g.sinks_owned() {
+            g.remove(&sink);
+        }
+        assert_eq!(g.sources_owned(), HashSet::from([a.clone()]));
+        assert!(g.connections_owned(&a).unwrap().is_empty());
+
+        g.add(b.clone());
+        assert!(g.connect(&b, &a));
+        for target in g.connections_owned(&b).unwrap() {
+            assert!(g.connect(&target, &b));
+        }
+        assert!(g.is_biconnected(&a, &b));
+        assert!(g.connections_owned(&c).is_none());
     }
 }
@@ -0,0 +1,394 @@
+use crate::{base::GraphBase, graph::*, hash};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Write};
+use std::hash::Hash;
+use std::ops::Deref;
+
+// Graph wrapper where nodes can be put into named, nestable groups. Each
+// node belongs directly to at most one group
+#[derive(Debug)]
+pub struct GroupedGraph<T> {
+    graph: Graph<T>,
+    groups: HashMap<String, Group>,
+}
+
+#[derive(Debug, Default)]
+struct Group {
+    parent: Option<String>,
+    members: HashSet<u64>,
+}
+
+impl<T> Default for GroupedGraph<T> {
+    fn default() -> Self {
+        Graph::new().into()
+    }
+}
+
+impl<T> From<Graph<T>> for GroupedGraph<T> {
+    fn from(graph: Graph<T>) -> Self {
+        GroupedGraph {
+            graph,
+            groups: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Deref for GroupedGraph<T> {
+    type Target = Graph<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T> GroupedGraph<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn into_inner(self) -> Graph<T> {
+        self.graph
+    }
+
+    // False if the name is taken or the parent doesn't exist
+    pub fn add_group(&mut self, name: &str, parent: Option<&str>) -> bool {
+        if self.groups.contains_key(name) || parent.is_some_and(|p| !self.groups.contains_key(p)) {
+            return false;
+        }
+        let group = Group {
+            parent: parent.map(str::to_owned),
+            members: HashSet::new(),
+        };
+        self.groups.insert(name.to_owned(), group);
+        true
+    }
+
+    pub fn parent_group(&self, name: &str) -> Option<&str> {
+        self.groups.get(name)?.parent.as_deref()
+    }
+
+    // The group and everything nested inside it, outermost first
+    fn nested(&self, name: &str) -> Vec<String> {
+        let mut nested = vec![name.to_owned()];
+        let mut i = 0;
+        while i < nested.len() {
+            for (child, group) in &self.groups {
+                if group.parent.as_ref() == Some(&nested[i]) {
+                    nested.push(child.clone());
+                }
+            }
+            i += 1;
+        }
+        nested
+    }
+
+    fn nested_members(&self, name: &str) -> HashSet<u64> {
+        self.nested(name)
+            .iter()
+            .flat_map(|g| self.groups[g].members.iter().copied())
+            .collect()
+    }
+
+    fn group_of_key(&self, key: u64) -> Option<&str> {
+        self.groups
+            .iter()
+            .find(|(_, g)| g.members.contains(&key))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+impl<T: Hash + Eq> GroupedGraph<T> {
+    pub fn add(&mut self, label: T) {
+        self.graph.add(label);
+    }
+
+    pub fn remove(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        for group in self.groups.values_mut() {
+            group.members.remove(&key);
+        }
+        self.graph.remove(label).is_some()
+    }
+
+    pub fn connect(&mut self, from: &T, to: &T) -> bool {
+        self.graph.connect(from, to)
+    }
+
+    pub fn disconnect(&mut self, from: &T, to: &T) -> bool {
+        self.graph.disconnect(from, to)
+    }
+
+    // Moves the node out of any group it's already in
+    pub fn assign(&mut self, label: &T, group: &str) -> bool {
+        let key = hash(label);
+        if !self.graph.nodes.contains_key(&key) || !self.groups.contains_key(group) {
+            return false;
+        }
+        self.unassign(label);
+        self.groups.get_mut(group).unwrap().members.insert(key);
+        true
+    }
+
+    pub fn unassign(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        self.groups
+            .values_mut()
+            .any(|group| group.members.remove(&key))
+    }
+
+    pub fn group_of(&self, label: &T) -> Option<&str> {
+        self.group_of_key(hash(label))
+    }
+
+    // Direct members along with those of every nested group
+    pub fn members(&self, group: &str) -> Option<Vec<&T>> {
+        self.groups.get(group)?;
+        let members = self
+            .nested_members(group)
+            .into_iter()
+            .map(|k| &self.graph.nodes[&k].label)
+            .collect();
+        Some(members)
+    }
+
+    // Replaces the group, its nested groups and all of their nodes with a
+    // single summary node in the parent group. Edges crossing the boundary
+    // are redirected to the summary, with the weights of edges that end up
+    // between the same pair of nodes summed, and edges inside are dropped
+    pub fn collapse_group(&mut self, name: &str, summary: T) -> bool {
+        let parent = match self.groups.get(name) {
+            Some(group) => group.parent.clone(),
+            None => return false,
+        };
+        let members = self.nested_members(name);
+        let key = hash(&summary);
+        if self.graph.nodes.contains_key(&key) && !members.contains(&key) {
+            return false;
+        }
+
+        let mut outgoing = HashMap::<u64, i64>::new();
+        let mut incoming = HashMap::<u64, i64>::new();
+        for (from, node) in &self.graph.nodes {
+            for (to, weight) in &node.edges {
+                match (members.contains(from), members.contains(to)) {
+                    (true, false) => *outgoing.entry(*to).or_default() += weight,
+                    (false, true) => *incoming.entry(*from).or_default() += weight,
+                    _ => (),
+                }
+            }
+        }
+
+        for nested in self.nested(name) {
+            self.groups.remove(&nested);
+        }
+        for member in &members {
+            self.graph.remove_node(*member);
+        }
+        self.graph.add(summary);
+        for (to, weight) in outgoing {
+            self.graph.set_edge(key, to, Some(weight));
+        }
+        for (from, weight) in incoming {
+            self.graph.set_edge(from, key, Some(weight));
+        }
+        if let Some(parent) = parent {
+            self.groups.get_mut(&parent).unwrap().members.insert(key);
+        }
+        true
+    }
+}
+
+impl<T: Hash + Eq + Display> GroupedGraph<T> {
+    // Graphviz DOT with each group drawn as a (nested) cluster
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+        let mut roots = self
+            .groups
+            .iter()
+            .filter(|(_, g)| g.parent.is_none())
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        roots.sort_unstable();
+        for root in roots {
+            self.write_cluster(&mut dot, root, 1);
+        }
+
+        let mut loose = self
+            .graph
+            .nodes
+            .iter()
+            .filter(|(k, _)| self.group_of_key(**k).is_none())
+            .map(|(_, n)| quote(&n.label))
+            .collect::<Vec<_>>();
+        loose.sort_unstable();
+        for node in loose {
+            writeln!(dot, "    {};", node).unwrap();
+        }
+
+        let mut edges = self
+            .graph
+            .nodes
+            .values()
+            .flat_map(|n| {
+                n.edges
+                    .keys()
+                    .map(move |t| (quote(&n.label), quote(&self.graph.nodes[t].label)))
+            })
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        // Self-loops are dashed so they stand out from the cluster edges
+        for (from, to) in edges {
+            let style = if from == to { " [style=dashed]" } else { "" };
+            writeln!(dot, "    {} -> {}{};", from, to, style).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn write_cluster(&self, dot: &mut String, name: &str, depth: usize) {
+        let indent = "    ".repeat(depth);
+        writeln!(
+            dot,
+            "{}subgraph {} {{",
+            indent,
+            quote(format!("cluster_{}", name))
+        )
+        .unwrap();
+        writeln!(dot, "{}    label = {};", indent, quote(name)).unwrap();
+
+        let mut children = self
+            .groups
+            .iter()
+            .filter(|(_, g)| g.parent.as_deref() == Some(name))
+            .map(|(child, _)| child.as_str())
+            .collect::<Vec<_>>();
+        children.sort_unstable();
+        for child in children {
+            self.write_cluster(dot, child, depth + 1);
+        }
+
+        let mut members = self.groups[name]
+            .members
+            .iter()
+            .map(|k| quote(&self.graph.nodes[k].label))
+            .collect::<Vec<_>>();
+        members.sort_unstable();
+        for member in members {
+            writeln!(dot, "{}    {};", indent, member).unwrap();
+        }
+        writeln!(dot, "{}}}", indent).unwrap();
+    }
+}
+
+pub(crate) fn quote<D: Display>(value: D) -> String {
+    format!(
+        "\"{}\"",
+        value.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+impl<T: Hash + Eq> GraphBase<T> for GroupedGraph<T> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grouped() -> GroupedGraph<String> {
+        let labels = ["api", "db", "cache", "web", "cli"];
+        let mut g = GroupedGraph::from(Graph::init(labels.iter().map(|l| l.to_string())));
+        for (a, b) in [
+            ("web", "api"),
+            ("cli", "api"),
+            ("api", "db"),
+            ("api", "cache"),
+        ] {
+            assert!(g.connect(&a.to_owned(), &b.to_owned()));
+        }
+        assert!(g.connect(&"cli".to_owned(), &"db".to_owned()));
+        assert!(g.add_group("backend", None));
+        assert!(g.add_group("storage", Some("backend")));
+        assert!(!g.add_group("storage", None));
+        assert!(!g.add_group("x", Some("missing")));
+        assert!(g.assign(&"api".to_owned(), "backend"));
+        assert!(g.assign(&"db".to_owned(), "storage"));
+        assert!(g.assign(&"cache".to_owned(), "storage"));
+        g
+    }
+
+    #[test]
+    fn groups() {
+        let mut g = grouped();
+        assert_eq!(g.group_of(&"db".to_owned()), Some("storage"));
+        assert_eq!(g.parent_group("storage"), Some("backend"));
+        assert_eq!(g.members("backend").unwrap().len(), 3);
+        assert_eq!(g.members("storage").unwrap().len(), 2);
+        assert!(g.members("missing").is_none());
+
+        assert!(g.assign(&"db".to_owned(), "backend"));
+        assert_eq!(g.members("storage").unwrap().len(), 1);
+        assert!(g.remove(&"db".to_owned()));
+        assert_eq!(g.members("backend").unwrap().len(), 2);
+        assert!(!g.assign(&"db".to_owned(), "backend"));
+    }
+
+    #[test]
+    fn collapse() {
+        let mut g = grouped();
+        assert!(g.collapse_group("storage", "storage".to_owned()));
+        assert_eq!(g.size(), 4);
+        assert_eq!(g.group_of(&"storage".to_owned()), Some("backend"));
+        assert!(g.members("storage").is_none());
+        assert_eq!(g.weight(&"api".to_owned(), &"storage".to_owned()), Some(2));
+        assert_eq!(g.weight(&"cli".to_owned(), &"storage".to_owned()), Some(1));
+        assert_eq!(g.indegree(&"storage".to_owned()), Some(2));
+
+        assert!(g.collapse_group("backend", "backend".to_owned()));
+        assert_eq!(g.size(), 3);
+        assert_eq!(g.weight(&"cli".to_owned(), &"backend".to_owned()), Some(2));
+        assert!(g.outdegree(&"backend".to_owned()) == Some(0));
+        assert!(!g.collapse_group("backend", "x".to_owned()));
+    }
+
+    #[test]
+    fn dot() {
+        let mut g = grouped();
+        assert!(g.connect(&"db".to_owned(), &"db".to_owned()));
+        let expected = r#"digraph {
+    subgraph "cluster_backend" {
+        label = "backend";
+        subgraph "cluster_storage" {
+            label = "storage";
+            "cache";
+            "db";
+        }
+        "api";
+    }
+    "cli";
+    "web";
+    "api" -> "cache";
+    "api" -> "db";
+    "cli" -> "api";
+    "cli" -> "db";
+    "db" -> "db" [style=dashed];
+    "web" -> "api";
+}
+"#;
+        assert_eq!(g.to_dot(), expected);
+    }
+}
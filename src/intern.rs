@@ -0,0 +1,201 @@
+use crate::{base::GraphBase, graph::*, hash};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Deref;
+use std::sync::Arc;
+
+// Stand-in for an interned string. Symbols are handed out in order and
+// never reused, so one stays valid even after its node is removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+// Graph over string labels where each string is stored once and nodes and
+// edges only deal in symbols, so long labels are hashed once per call
+// rather than on every step of a traversal
+#[derive(Debug, Default)]
+pub struct StringGraph {
+    graph: Graph<Symbol>,
+    strings: Vec<Arc<str>>,
+    symbols: HashMap<Arc<str>, Symbol>,
+}
+
+impl<S: AsRef<str>> From<&Graph<S>> for StringGraph {
+    fn from(graph: &Graph<S>) -> Self {
+        let mut interned = StringGraph::new();
+        for node in graph.nodes.values() {
+            interned.add(node.label.as_ref());
+        }
+        for node in graph.nodes.values() {
+            let from = interned.symbols[node.label.as_ref()];
+            for (to, weight) in &node.edges {
+                let to = interned.symbols[graph.nodes[to].label.as_ref()];
+                interned
+                    .graph
+                    .set_edge(hash(&from), hash(&to), Some(*weight));
+            }
+        }
+        interned
+    }
+}
+
+impl Deref for StringGraph {
+    type Target = Graph<Symbol>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl StringGraph {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn into_inner(self) -> Graph<Symbol> {
+        self.graph
+    }
+
+    // Symbol for the string, interning it if it hasn't been seen
+    pub fn intern(&mut self, label: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(label) {
+            return *symbol;
+        }
+        let symbol = Symbol(self.strings.len() as u32);
+        let label = Arc::<str>::from(label);
+        self.strings.push(label.clone());
+        self.symbols.insert(label, symbol);
+        symbol
+    }
+
+    // Doesn't intern, so looking up an unknown string leaves no trace
+    pub fn symbol(&self, label: &str) -> Option<Symbol> {
+        self.symbols.get(label).copied()
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+        self.strings.get(symbol.index()).map(|s| &**s)
+    }
+
+    pub fn add(&mut self, label: &str) -> Symbol {
+        let symbol = self.intern(label);
+        self.graph.add(symbol);
+        symbol
+    }
+
+    pub fn remove(&mut self, label: &str) -> bool {
+        self.symbol(label)
+            .is_some_and(|s| self.graph.remove(&s).is_some())
+    }
+
+    pub fn contains(&self, label: &str) -> bool {
+        self.symbol(label).is_some_and(|s| self.graph.contains(&s))
+    }
+
+    pub fn connect(&mut self, from: &str, to: &str) -> bool {
+        match self.pair(from, to) {
+            Some((from, to)) => self.graph.connect(&from, &to),
+            None => false,
+        }
+    }
+
+    pub fn disconnect(&mut self, from: &str, to: &str) -> bool {
+        match self.pair(from, to) {
+            Some((from, to)) => self.graph.disconnect(&from, &to),
+            None => false,
+        }
+    }
+
+    pub fn weight(&self, from: &str, to: &str) -> Option<i64> {
+        let (from, to) = self.pair(from, to)?;
+        self.graph.weight(&from, &to)
+    }
+
+    pub fn labels(&self) -> Vec<&str> {
+        self.graph
+            .nodes
+            .values()
+            .map(|n| &*self.strings[n.label.index()])
+            .collect()
+    }
+
+    pub fn neighbors(&self, label: &str) -> Vec<&str> {
+        self.symbol(label)
+            .map(|s| self.graph.neighbors(&s))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| &*self.strings[s.index()])
+            .collect()
+    }
+
+    fn pair(&self, from: &str, to: &str) -> Option<(Symbol, Symbol)> {
+        Some((self.symbol(from)?, self.symbol(to)?))
+    }
+}
+
+impl GraphBase<Symbol> for StringGraph {
+    fn contains(&self, label: &Symbol) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&Symbol> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &Symbol) -> Vec<&Symbol> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &Symbol, to: &Symbol) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning() {
+        let mut g = StringGraph::new();
+        let api = g.add("services/api");
+        g.add("services/db");
+        assert_eq!(g.intern("services/api"), api);
+        assert!(g.connect("services/api", "services/db"));
+        assert!(!g.connect("services/api", "services/cache"));
+        assert_eq!(g.symbol("services/cache"), None);
+        assert_eq!(g.neighbors("services/api"), vec!["services/db"]);
+        assert_eq!(g.weight("services/api", "services/db"), Some(1));
+
+        // Removing the node keeps the string, and re-adding gets the same symbol
+        assert!(g.remove("services/api"));
+        assert!(!g.contains("services/api"));
+        assert_eq!(g.resolve(api), Some("services/api"));
+        assert_eq!(g.add("services/api"), api);
+        assert_eq!(g.size(), 2);
+        assert_eq!(g.edge_count(), 0);
+    }
+
+    #[test]
+    fn from_graph() {
+        let mut g = Graph::init(vec!["a", "b", "c"]);
+        assert!(g.connect(&"a", &"b"));
+        assert!(g.connect(&"b", &"c"));
+        g.set_edge(hash(&"b"), hash(&"c"), Some(4));
+
+        let s = StringGraph::from(&g);
+        assert_eq!(s.size(), 3);
+        assert_eq!(s.weight("b", "c"), Some(4));
+        let start = s.symbol("a").unwrap();
+        let reached = s
+            .bfs(&start)
+            .map(|n| s.resolve(*n).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(reached, vec!["a", "b", "c"]);
+    }
+}
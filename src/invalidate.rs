@@ -0,0 +1,306 @@
+use crate::{base::GraphBase, graph::*, hash};
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+use std::ops::Deref;
+
+// Graph wrapper tracking which nodes need recomputing, where an edge
+// a -> b means a is computed from b. Anything depending on a dirty node is
+// dirty too, so nodes are recomputed dependencies first. Each dirty node
+// keeps a count of its dirty dependencies, and those at zero are ready
+#[derive(Debug)]
+pub struct Invalidator<T> {
+    graph: Graph<T>,
+    pending: HashMap<u64, usize>,
+    ready: BTreeSet<u64>,
+}
+
+impl<T> Default for Invalidator<T> {
+    fn default() -> Self {
+        Graph::new().into()
+    }
+}
+
+// Existing nodes are taken to be up to date
+impl<T> From<Graph<T>> for Invalidator<T> {
+    fn from(graph: Graph<T>) -> Self {
+        Invalidator {
+            graph,
+            pending: HashMap::new(),
+            ready: BTreeSet::new(),
+        }
+    }
+}
+
+impl<T> Deref for Invalidator<T> {
+    type Target = Graph<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T> Invalidator<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn into_inner(self) -> Graph<T> {
+        self.graph
+    }
+
+    pub fn dirty_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    // Dirty with no dirty dependencies, so it can be recomputed now, lowest
+    // key first. None once everything is clean, or if only dirty cycles
+    // are left
+    pub fn next_to_recompute(&self) -> Option<&T> {
+        let key = self.ready.iter().next()?;
+        Some(&self.graph.nodes[key].label)
+    }
+
+    // Dirties the node and everything depending on it. Nodes that are
+    // already dirty have dirty dependents, so the walk stops there, though
+    // the node itself is recounted in case its edges changed
+    fn invalidate(&mut self, key: u64) {
+        self.recount(key);
+        let mut stack = vec![key];
+        while let Some(key) = stack.pop() {
+            if self.pending.contains_key(&key) {
+                continue;
+            }
+            self.pending.insert(key, 0);
+            self.recount(key);
+            for dependent in self.graph.nodes[&key].incoming.keys() {
+                if *dependent == key {
+                    continue;
+                }
+                match self.pending.get_mut(dependent) {
+                    Some(count) => {
+                        *count += 1;
+                        self.ready.remove(dependent);
+                    }
+                    None => stack.push(*dependent),
+                }
+            }
+        }
+    }
+
+    // Counts a dirty node's dirty dependencies from scratch
+    fn recount(&mut self, key: u64) {
+        if !self.pending.contains_key(&key) {
+            return;
+        }
+        let count = self.graph.nodes[&key]
+            .edges
+            .keys()
+            .filter(|k| **k != key && self.pending.contains_key(k))
+            .count();
+        self.pending.insert(key, count);
+        if count == 0 {
+            self.ready.insert(key);
+        } else {
+            self.ready.remove(&key);
+        }
+    }
+
+    // The node is clean or gone, so its dependents wait on one fewer
+    fn settle(&mut self, key: u64, dependents: impl IntoIterator<Item = u64>) {
+        self.pending.remove(&key);
+        self.ready.remove(&key);
+        for dependent in dependents.into_iter().filter(|d| *d != key) {
+            let Some(count) = self.pending.get_mut(&dependent) else {
+                continue;
+            };
+            *count -= 1;
+            if *count == 0 {
+                self.ready.insert(dependent);
+            }
+        }
+    }
+}
+
+impl<T: Hash + Eq> Invalidator<T> {
+    // New nodes start dirty as they've never been computed
+    pub fn add(&mut self, label: T) {
+        let key = hash(&label);
+        self.graph.add(label);
+        self.invalidate(key);
+    }
+
+    pub fn remove(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        let Some((_, incoming)) = self.graph.remove_node(key) else {
+            return false;
+        };
+        if self.pending.contains_key(&key) {
+            self.settle(key, incoming.iter().map(|(from, _)| *from));
+        }
+        for (from, _) in incoming {
+            self.invalidate(from);
+        }
+        true
+    }
+
+    // Either way the edge changes what from is computed from
+    pub fn connect(&mut self, from: &T, to: &T) -> bool {
+        self.graph.connect(from, to) && self.mark_dirty(from)
+    }
+
+    pub fn disconnect(&mut self, from: &T, to: &T) -> bool {
+        self.graph.disconnect(from, to) && self.mark_dirty(from)
+    }
+
+    pub fn mark_dirty(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        if !self.graph.nodes.contains_key(&key) {
+            return false;
+        }
+        self.invalidate(key);
+        true
+    }
+
+    // Only a node whose dependencies are all clean can be marked clean,
+    // false otherwise or if it wasn't dirty
+    pub fn mark_clean(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        if !self.ready.contains(&key) {
+            return false;
+        }
+        let node = &self.graph.nodes[&key];
+        let dependents = node.incoming.keys().copied().collect::<Vec<_>>();
+        self.settle(key, dependents);
+        true
+    }
+
+    pub fn is_dirty(&self, label: &T) -> bool {
+        self.pending.contains_key(&hash(label))
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for Invalidator<T> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // total = price * qty + shipping, shipping = weight
+    fn sheet() -> Invalidator<&'static str> {
+        let mut g = Invalidator::from(Graph::init(vec![
+            "price", "qty", "weight", "shipping", "subtotal", "total",
+        ]));
+        for (a, b) in [
+            ("subtotal", "price"),
+            ("subtotal", "qty"),
+            ("shipping", "weight"),
+            ("total", "subtotal"),
+            ("total", "shipping"),
+        ] {
+            assert!(g.connect(&a, &b));
+        }
+        while let Some(next) = g.next_to_recompute().copied() {
+            assert!(g.mark_clean(&next));
+        }
+        g
+    }
+
+    #[test]
+    fn recompute_order() {
+        let mut g = sheet();
+        assert_eq!(g.dirty_count(), 0);
+        assert!(g.next_to_recompute().is_none());
+
+        assert!(g.mark_dirty(&"qty"));
+        assert!(g.is_dirty(&"total"));
+        assert!(!g.is_dirty(&"shipping"));
+        assert_eq!(g.dirty_count(), 3);
+        assert!(!g.mark_clean(&"total"));
+
+        let mut order = Vec::new();
+        while let Some(next) = g.next_to_recompute().copied() {
+            assert!(g.mark_clean(&next));
+            order.push(next);
+        }
+        assert_eq!(order, vec!["qty", "subtotal", "total"]);
+        assert!(!g.mark_clean(&"qty"));
+        assert!(!g.mark_dirty(&"nope"));
+    }
+
+    #[test]
+    fn edits() {
+        let mut g = sheet();
+        g.add("tax");
+        assert!(g.is_dirty(&"tax"));
+        assert!(g.connect(&"total", &"tax"));
+        assert_eq!(g.dirty_count(), 2);
+        assert!(!g.mark_clean(&"total"));
+        assert!(g.mark_clean(&"tax"));
+        assert!(g.mark_clean(&"total"));
+
+        assert!(g.remove(&"weight"));
+        assert!(g.is_dirty(&"shipping"));
+        assert!(g.is_dirty(&"total"));
+        assert_eq!(g.dirty_count(), 2);
+
+        // A dirty cycle never becomes ready
+        assert!(g.connect(&"shipping", &"total"));
+        assert!(g.next_to_recompute().is_none());
+        assert_eq!(g.dirty_count(), 2);
+
+        // Dropping the edge back out of the cycle frees shipping again
+        assert!(g.disconnect(&"shipping", &"total"));
+        assert_eq!(g.next_to_recompute(), Some(&"shipping"));
+        assert!(g.mark_clean(&"shipping"));
+        assert_eq!(g.next_to_recompute(), Some(&"total"));
+
+        // Re-adding a dirty node drops its edges, so it's ready at once
+        assert!(g.mark_dirty(&"price"));
+        assert!(!g.mark_clean(&"subtotal"));
+        g.add("subtotal");
+        assert!(g.mark_clean(&"subtotal"));
+        assert!(g.mark_clean(&"price"));
+        assert!(g.mark_clean(&"total"));
+        assert_eq!(g.dirty_count(), 0);
+    }
+
+    #[test]
+    fn wide_fan_in() {
+        // Every layer depends on all of the one below, and a change at the
+        // bottom is recomputed a whole layer at a time
+        let mut g = Invalidator::from(Graph::init(0..40));
+        for i in 10..40 {
+            for j in (i / 10 - 1) * 10..i / 10 * 10 {
+                assert!(g.connect(&i, &j));
+            }
+        }
+        while let Some(next) = g.next_to_recompute().copied() {
+            assert!(g.mark_clean(&next));
+        }
+        assert!(g.mark_dirty(&3));
+        assert_eq!(g.dirty_count(), 31);
+        let mut order = Vec::new();
+        while let Some(next) = g.next_to_recompute().copied() {
+            assert!(g.mark_clean(&next));
+            order.push(next / 10);
+        }
+        assert_eq!(order.len(), 31);
+        assert!(order.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
@@ -0,0 +1,499 @@
+use crate::budget::{Budget, Interrupt, Partial};
+use crate::graph::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+impl<T: Hash + Eq> Graph<T> {
+    pub fn is_isomorphic<U: Hash + Eq>(&self, other: &Graph<U>) -> bool {
+        self.is_isomorphic_matching(other, |_, _| true, |_, _| true)
+    }
+
+    pub fn is_isomorphic_matching<U, N, E>(
+        &self,
+        other: &Graph<U>,
+        node_match: N,
+        edge_match: E,
+    ) -> bool
+    where
+        U: Hash + Eq,
+        N: Fn(&T, &U) -> bool,
+        E: Fn(i64, i64) -> bool,
+    {
+        let budget = Budget::unlimited();
+        let matched = self.is_isomorphic_within(other, node_match, edge_match, &budget);
+        matched.unwrap()
+    }
+
+    // Gives up once the budget runs out, as the search is exponential in
+    // the worst case
+    pub fn is_isomorphic_within<U, N, E>(
+        &self,
+        other: &Graph<U>,
+        node_match: N,
+        edge_match: E,
+        budget: &Budget,
+    ) -> Result<bool, Interrupt>
+    where
+        U: Hash + Eq,
+        N: Fn(&T, &U) -> bool,
+        E: Fn(i64, i64) -> bool,
+    {
+        if self.size() != other.size() || self.edge_count() != other.edge_count() {
+            return Ok(false);
+        }
+        let mut matcher = Matcher::new(self, other, node_match, edge_match, true);
+        let mut found = Vec::new();
+        matcher.search(&mut found, true, budget);
+        match matcher.interrupted {
+            Some(reason) => Err(reason),
+            None => Ok(!found.is_empty()),
+        }
+    }
+
+    // Every induced subgraph of self isomorphic to the pattern, as a map
+    // from pattern labels to labels in self
+    pub fn find_subgraph_isomorphisms<'a, 'b, U: Hash + Eq>(
+        &'a self,
+        pattern: &'b Graph<U>,
+    ) -> Vec<Mapping<'a, 'b, T, U>> {
+        self.find_subgraph_isomorphisms_matching(pattern, |_, _| true, |_, _| true)
+    }
+
+    pub fn find_subgraph_isomorphisms_matching<'a, 'b, U, N, E>(
+        &'a self,
+        pattern: &'b Graph<U>,
+        node_match: N,
+        edge_match: E,
+    ) -> Vec<Mapping<'a, 'b, T, U>>
+    where
+        U: Hash + Eq,
+        N: Fn(&T, &U) -> bool,
+        E: Fn(i64, i64) -> bool,
+    {
+        let budget = Budget::unlimited();
+        self.find_subgraph_isomorphisms_within(pattern, node_match, edge_match, &budget)
+            .unwrap_or_else(|partial| partial.found)
+    }
+
+    // Stops once the budget runs out, keeping whatever was already found
+    pub fn find_subgraph_isomorphisms_within<'a, 'b, U, N, E>(
+        &'a self,
+        pattern: &'b Graph<U>,
+        node_match: N,
+        edge_match: E,
+        budget: &Budget,
+    ) -> Result<Mappings<'a, 'b, T, U>, Partial<Mappings<'a, 'b, T, U>>>
+    where
+        U: Hash + Eq,
+        N: Fn(&T, &U) -> bool,
+        E: Fn(i64, i64) -> bool,
+    {
+        if pattern.size() > self.size() {
+            return Ok(Vec::new());
+        }
+        let mut matcher = Matcher::new(self, pattern, node_match, edge_match, false);
+        let mut found = Vec::new();
+        matcher.search(&mut found, false, budget);
+        let found = found
+            .into_iter()
+            .map(|mapping| {
+                mapping
+                    .into_iter()
+                    .enumerate()
+                    .map(|(p, t)| (matcher.pattern.labels[p], matcher.target.labels[t]))
+                    .collect()
+            })
+            .collect();
+        match matcher.interrupted {
+            Some(reason) => Err(Partial { reason, found }),
+            None => Ok(found),
+        }
+    }
+}
+
+// Pattern labels to the labels they were matched to
+pub type Mapping<'a, 'b, T, U> = HashMap<&'b U, &'a T>;
+type Mappings<'a, 'b, T, U> = Vec<Mapping<'a, 'b, T, U>>;
+
+// Graph flattened to dense indices for the search
+struct Indexed<'a, T> {
+    labels: Vec<&'a T>,
+    outgoing: Vec<HashMap<usize, i64>>,
+    incoming: Vec<HashMap<usize, i64>>,
+}
+
+impl<'a, T> Indexed<'a, T> {
+    fn new(graph: &'a Graph<T>) -> Self {
+        let keys = graph.nodes.keys().copied().collect::<Vec<_>>();
+        let index = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (*k, i))
+            .collect::<HashMap<_, _>>();
+        let mut incoming = vec![HashMap::new(); keys.len()];
+        let outgoing = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| {
+                graph.nodes[k]
+                    .edges
+                    .iter()
+                    .map(|(t, w)| {
+                        incoming[index[t]].insert(i, *w);
+                        (index[t], *w)
+                    })
+                    .collect()
+            })
+            .collect();
+        Indexed {
+            labels: keys.iter().map(|k| &graph.nodes[k].label).collect(),
+            outgoing,
+            incoming,
+        }
+    }
+}
+
+// One graph's half of the VF2 state. ins and outs are the terminal sets,
+// nodes with an edge into or out of the mapped part, each stamped with the
+// depth it joined at so backtracking can take it out again. Mapped nodes
+// stay stamped but never count as part of the frontier
+struct Side {
+    core: Vec<Option<usize>>,
+    ins: HashMap<usize, usize>,
+    outs: HashMap<usize, usize>,
+}
+
+impl Side {
+    fn new(size: usize) -> Self {
+        Side {
+            core: vec![None; size],
+            ins: HashMap::new(),
+            outs: HashMap::new(),
+        }
+    }
+
+    fn add<T>(&mut self, graph: &Indexed<T>, node: usize, other: usize, depth: usize) {
+        self.core[node] = Some(other);
+        self.ins.entry(node).or_insert(depth);
+        self.outs.entry(node).or_insert(depth);
+        for from in graph.incoming[node].keys() {
+            if self.core[*from].is_none() {
+                self.ins.entry(*from).or_insert(depth);
+            }
+        }
+        for to in graph.outgoing[node].keys() {
+            if self.core[*to].is_none() {
+                self.outs.entry(*to).or_insert(depth);
+            }
+        }
+    }
+
+    fn remove<T>(&mut self, graph: &Indexed<T>, node: usize, depth: usize) {
+        self.core[node] = None;
+        for from in graph.incoming[node].keys().chain([&node]) {
+            if self.ins.get(from) == Some(&depth) {
+                self.ins.remove(from);
+            }
+        }
+        for to in graph.outgoing[node].keys().chain([&node]) {
+            if self.outs.get(to) == Some(&depth) {
+                self.outs.remove(to);
+            }
+        }
+    }
+
+    // Unmapped nodes in a terminal set
+    fn frontier<'s>(&'s self, set: &'s HashMap<usize, usize>) -> impl Iterator<Item = usize> + 's {
+        set.keys().copied().filter(move |n| self.core[*n].is_none())
+    }
+
+    // Unmapped predecessors then successors of node, counted by whether
+    // they're in ins, in outs, or in neither
+    fn lookahead<T>(&self, graph: &Indexed<T>, node: usize) -> [usize; 6] {
+        let mut counts = [0; 6];
+        for (i, neighbours) in [&graph.incoming[node], &graph.outgoing[node]]
+            .iter()
+            .enumerate()
+        {
+            for n in neighbours.keys() {
+                if *n == node || self.core[*n].is_some() {
+                    continue;
+                }
+                let (inward, outward) = (self.ins.contains_key(n), self.outs.contains_key(n));
+                counts[3 * i] += inward as usize;
+                counts[3 * i + 1] += outward as usize;
+                counts[3 * i + 2] += !(inward || outward) as usize;
+            }
+        }
+        counts
+    }
+}
+
+// VF2 matcher. Pattern nodes are mapped one at a time, with candidates
+// drawn from the out terminal sets, then the in terminal sets, and only
+// from every unmapped node when both are empty. Pairs are pruned by their
+// edges to the mapped part and by how many neighbours each has in the
+// terminal sets and beyond them, counts that must agree for isomorphism
+// and can't be larger in the pattern for induced subgraphs
+struct Matcher<'a, 'b, T, U, N, E> {
+    target: Indexed<'a, T>,
+    pattern: Indexed<'b, U>,
+    node_match: N,
+    edge_match: E,
+    exact: bool,
+    rank: Vec<usize>,
+    ours: Side,
+    theirs: Side,
+    depth: usize,
+    interrupted: Option<Interrupt>,
+}
+
+impl<'a, 'b, T, U, N, E> Matcher<'a, 'b, T, U, N, E>
+where
+    N: Fn(&T, &U) -> bool,
+    E: Fn(i64, i64) -> bool,
+{
+    fn new(
+        target: &'a Graph<T>,
+        pattern: &'b Graph<U>,
+        node_match: N,
+        edge_match: E,
+        exact: bool,
+    ) -> Self {
+        let target = Indexed::new(target);
+        let pattern = Indexed::new(pattern);
+
+        // Among the candidates, most constrained pattern nodes first
+        let degree = |p: usize| pattern.outgoing[p].len() + pattern.incoming[p].len();
+        let mut order = (0..pattern.labels.len()).collect::<Vec<_>>();
+        order.sort_by_key(|p| std::cmp::Reverse(degree(*p)));
+        let mut rank = vec![0; order.len()];
+        for (i, p) in order.into_iter().enumerate() {
+            rank[p] = i;
+        }
+
+        Matcher {
+            ours: Side::new(pattern.labels.len()),
+            theirs: Side::new(target.labels.len()),
+            target,
+            pattern,
+            node_match,
+            edge_match,
+            exact,
+            rank,
+            depth: 0,
+            interrupted: None,
+        }
+    }
+
+    // True once the search should stop, either done or interrupted
+    fn search(&mut self, found: &mut Vec<Vec<usize>>, first_only: bool, budget: &Budget) -> bool {
+        if let Err(reason) = budget.check() {
+            self.interrupted = Some(reason);
+            return true;
+        }
+        if self.depth == self.pattern.labels.len() {
+            found.push(self.ours.core.iter().map(|t| t.unwrap()).collect());
+            return first_only;
+        }
+
+        let (p, candidates) = self.candidates();
+        for t in candidates {
+            if !self.feasible(p, t) {
+                continue;
+            }
+            self.depth += 1;
+            self.ours.add(&self.pattern, p, t, self.depth);
+            self.theirs.add(&self.target, t, p, self.depth);
+            if self.search(found, first_only, budget) {
+                return true;
+            }
+            self.ours.remove(&self.pattern, p, self.depth);
+            self.theirs.remove(&self.target, t, self.depth);
+            self.depth -= 1;
+        }
+        false
+    }
+
+    // The next pattern node and the target nodes it could map to
+    fn candidates(&self) -> (usize, Vec<usize>) {
+        let (ours, theirs) = (&self.ours, &self.theirs);
+        let pick = |nodes: &mut dyn Iterator<Item = usize>| nodes.min_by_key(|p| self.rank[*p]);
+        for (mine, other) in [(&ours.outs, &theirs.outs), (&ours.ins, &theirs.ins)] {
+            let Some(p) = pick(&mut ours.frontier(mine)) else {
+                continue;
+            };
+            let candidates = theirs.frontier(other).collect::<Vec<_>>();
+            if !candidates.is_empty() {
+                return (p, candidates);
+            }
+        }
+        let p = pick(&mut (0..ours.core.len()).filter(|p| ours.core[*p].is_none())).unwrap();
+        let candidates = (0..theirs.core.len()).filter(|t| theirs.core[*t].is_none());
+        (p, candidates.collect())
+    }
+
+    fn feasible(&self, p: usize, t: usize) -> bool {
+        let (pattern, target) = (&self.pattern, &self.target);
+        let (p_out, t_out) = (pattern.outgoing[p].len(), target.outgoing[t].len());
+        let (p_in, t_in) = (pattern.incoming[p].len(), target.incoming[t].len());
+        let degrees = if self.exact {
+            p_out == t_out && p_in == t_in
+        } else {
+            p_out <= t_out && p_in <= t_in
+        };
+        if !degrees || !(self.node_match)(target.labels[t], pattern.labels[p]) {
+            return false;
+        }
+
+        // Edges to the mapped part, or the pair itself, must agree both
+        // ways, weights included
+        let theirs = |p2: usize| if p2 == p { Some(t) } else { self.ours.core[p2] };
+        let ours = |t2: usize| {
+            if t2 == t {
+                Some(p)
+            } else {
+                self.theirs.core[t2]
+            }
+        };
+        let sides = [
+            (&pattern.outgoing[p], &target.outgoing[t]),
+            (&pattern.incoming[p], &target.incoming[t]),
+        ];
+        for (mine, other) in sides {
+            for (p2, weight) in mine {
+                let Some(t2) = theirs(*p2) else {
+                    continue;
+                };
+                match other.get(&t2) {
+                    Some(w) if (self.edge_match)(*w, *weight) => {}
+                    _ => return false,
+                }
+            }
+            for t2 in other.keys() {
+                if ours(*t2).is_some_and(|p2| !mine.contains_key(&p2)) {
+                    return false;
+                }
+            }
+        }
+
+        let counts = self.ours.lookahead(pattern, p);
+        let limits = self.theirs.lookahead(target, t);
+        if self.exact {
+            counts == limits
+        } else {
+            counts.iter().zip(limits).all(|(c, l)| *c <= l)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isomorphic() {
+        let a = Graph::cycle_graph(4);
+        let mut b = Graph::init('a'..='d');
+
+        // a -> c -> b -> d -> a
+        assert!(b.connect(&'a', &'c'));
+        assert!(b.connect(&'c', &'b'));
+        assert!(b.connect(&'b', &'d'));
+        assert!(b.connect(&'d', &'a'));
+        assert!(a.is_isomorphic(&b));
+
+        // Same edge count but a different shape
+        assert!(b.disconnect(&'d', &'a'));
+        assert!(b.connect(&'a', &'d'));
+        assert!(!a.is_isomorphic(&b));
+
+        assert!(!a.is_isomorphic(&Graph::path_graph(4)));
+        assert!(a.is_isomorphic_matching(&a, |x, y| x == y, |x, y| x == y));
+        assert!(!a.is_isomorphic_matching(&a, |_, _| true, |x, y| x != y));
+    }
+
+    #[test]
+    fn subgraph_isomorphisms() {
+        // Look for a -> b -> c chains that aren't closed
+        let pattern = Graph::path_graph(3);
+
+        let mut g = Graph::init('a'..='e');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'c', &'d'));
+        assert!(g.connect(&'a', &'c')); // Shortcut makes a -> b -> c induced-incompatible
+        assert!(g.connect(&'d', &'e'));
+
+        let found = g.find_subgraph_isomorphisms(&pattern);
+        let mut starts = found.iter().map(|m| *m[&0]).collect::<Vec<_>>();
+        starts.sort_unstable();
+        assert_eq!(starts, vec!['a', 'b', 'c']);
+        assert!(found.iter().all(|m| m.len() == 3));
+
+        let only_b = g.find_subgraph_isomorphisms_matching(
+            &pattern,
+            |t, p| *p != 0 || *t == 'b',
+            |_, _| true,
+        );
+        assert_eq!(only_b.len(), 1);
+        assert_eq!(only_b[0][&2], &'d');
+    }
+
+    #[test]
+    fn large_target() {
+        // Candidates past the first node come from the frontier, so a long
+        // chain with one triangle isn't rescanned at every depth
+        let mut g = Graph::path_graph(20_000);
+        assert!(g.connect(&12_345, &12_343));
+        let triangle = Graph::cycle_graph(3);
+        let found = g.find_subgraph_isomorphisms(&triangle);
+        assert_eq!(found.len(), 3);
+        assert!(found
+            .iter()
+            .all(|m| m.values().all(|t| (12_343..=12_345).contains(*t))));
+    }
+
+    #[test]
+    fn budgets() {
+        use crate::budget::CancelToken;
+        use std::time::Duration;
+
+        let a = Graph::cycle_graph(6);
+        let b = Graph::cycle_graph(6);
+        let budget = Budget::with_timeout(Duration::from_secs(3600));
+        assert_eq!(
+            a.is_isomorphic_within(&b, |_, _| true, |_, _| true, &budget),
+            Ok(true)
+        );
+        let expired = Budget::with_timeout(Duration::ZERO);
+        assert_eq!(
+            a.is_isomorphic_within(&b, |_, _| true, |_, _| true, &expired),
+            Err(Interrupt::Timeout)
+        );
+
+        // Cancelled part way through, what was found so far is kept
+        let token = CancelToken::new();
+        let budget = Budget::unlimited().and_token(token.clone());
+        let pattern = Graph::path_graph(2);
+        let found = a.find_subgraph_isomorphisms_within(
+            &pattern,
+            |_, _| true,
+            |_, _| {
+                token.cancel();
+                true
+            },
+            &budget,
+        );
+        let partial = found.unwrap_err();
+        assert_eq!(partial.reason, Interrupt::Cancelled);
+        assert!(partial.found.len() < 6);
+        let all = a.find_subgraph_isomorphisms_within(
+            &pattern,
+            |_, _| true,
+            |_, _| true,
+            &Budget::unlimited(),
+        );
+        assert_eq!(all.map(|f| f.len()), Ok(6));
+    }
+}
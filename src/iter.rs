@@ -1,6 +1,9 @@
-use crate::{graph::*, hash};
-use std::collections::{HashSet, VecDeque};
-use std::hash::Hash;
+use crate::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use crate::{base::GraphBase, graph::*, hash};
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::hash::Hash;
+use core::iter::FusedIterator;
 
 pub enum Mode {
     Bredth,
@@ -17,36 +20,249 @@ impl<T: Hash> Graph<T> {
     }
 
     pub fn walk<'a>(&'a self, start: &'a T, mode: Mode) -> WalkIter<'a, T> {
-        let mut buffer = VecDeque::new();
-        buffer.push_front(start);
+        WalkIter::new(self, start, mode)
+    }
+
+    pub fn bfs_where<F: FnMut(i64) -> bool>(
+        &self,
+        start: &T,
+        follow: F,
+    ) -> FilteredWalkIter<'_, T, F> {
+        self.walk_where(start, Mode::Bredth, follow)
+    }
 
+    pub fn dfs_where<F: FnMut(i64) -> bool>(
+        &self,
+        start: &T,
+        follow: F,
+    ) -> FilteredWalkIter<'_, T, F> {
+        self.walk_where(start, Mode::Depth, follow)
+    }
+
+    // Walk that only takes edges whose weight passes follow, as if the rest
+    // weren't there. Yields nothing if start isn't in the graph
+    pub fn walk_where<F: FnMut(i64) -> bool>(
+        &self,
+        start: &T,
+        mode: Mode,
+        follow: F,
+    ) -> FilteredWalkIter<'_, T, F> {
+        let mut buffer = VecDeque::new();
         let mut visited = HashSet::new();
-        visited.insert(hash(start));
-        WalkIter {
+        let key = hash(start);
+        if self.nodes.contains_key(&key) {
+            buffer.push_front(key);
+            visited.insert(key);
+        }
+        FilteredWalkIter {
             mode,
+            graph: self,
             buffer,
             visited,
-            graph: &self,
+            follow,
+        }
+    }
+
+    pub fn bfs_with<'a, 's>(
+        &'a self,
+        scratch: &'s mut TraversalScratch,
+        start: &T,
+    ) -> ScratchWalkIter<'a, 's, T> {
+        self.walk_with(scratch, start, Mode::Bredth)
+    }
+
+    pub fn dfs_with<'a, 's>(
+        &'a self,
+        scratch: &'s mut TraversalScratch,
+        start: &T,
+    ) -> ScratchWalkIter<'a, 's, T> {
+        self.walk_with(scratch, start, Mode::Depth)
+    }
+
+    // Same order as walk but reuses the buffers in scratch rather than
+    // allocating, yields nothing if start isn't in the graph
+    pub fn walk_with<'a, 's>(
+        &'a self,
+        scratch: &'s mut TraversalScratch,
+        start: &T,
+        mode: Mode,
+    ) -> ScratchWalkIter<'a, 's, T> {
+        scratch.buffer.clear();
+        scratch.visited.clear();
+        let key = hash(start);
+        if self.nodes.contains_key(&key) {
+            scratch.buffer.push_front(key);
+            scratch.visited.insert(key);
+        }
+        ScratchWalkIter {
+            mode,
+            graph: self,
+            scratch,
         }
     }
 
+    // Unweighted hop counts to every node reachable from start
+    pub(crate) fn hop_distances(&self, start: u64) -> HashMap<u64, usize> {
+        let mut distances = HashMap::new();
+        distances.insert(start, 0);
+        let mut buffer = VecDeque::new();
+        buffer.push_back(start);
+        while let Some(key) = buffer.pop_front() {
+            let distance = distances[&key];
+            for target in self.nodes[&key].edges.keys() {
+                if !distances.contains_key(target) {
+                    distances.insert(*target, distance + 1);
+                    buffer.push_back(*target);
+                }
+            }
+        }
+        distances
+    }
+
     pub fn edges<'a>(&'a self) -> EdgeIter<'a, T> {
         EdgeIter {
-            graph: &self,
+            graph: self,
             nodes: self.nodes.values().collect(),
-            edges: Vec::new(),
+            front: VecDeque::new(),
+            back: VecDeque::new(),
+            remaining: self.edge_count(),
+        }
+    }
+
+    // Same edges as a plain (from, to, weight) tuple
+    pub fn edges_weighted(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&T, &T, i64)> + ExactSizeIterator + FusedIterator {
+        self.edges().map(|e| (e.from, e.to, e.weight))
+    }
+}
+
+// Labels double as dense indices, so visited tracking can use a bitset
+// rather than hashing every neighbor into a set. The bitset never takes
+// more than a word per node, labels past that go in a set instead
+impl Graph<usize> {
+    pub fn bfs_indexed(&self, start: usize) -> IndexedWalkIter<'_> {
+        self.walk_indexed(start, Mode::Bredth)
+    }
+
+    pub fn dfs_indexed(&self, start: usize) -> IndexedWalkIter<'_> {
+        self.walk_indexed(start, Mode::Depth)
+    }
+
+    // Same order as walk, yields nothing if start isn't in the graph
+    pub fn walk_indexed(&self, start: usize, mode: Mode) -> IndexedWalkIter<'_> {
+        let mut buffer = VecDeque::new();
+        let mut visited = Bitset::new(self.size().saturating_mul(64));
+        if let Some(node) = self.nodes.get(&hash(&start)) {
+            buffer.push_front(node);
+            visited.insert(start);
+        }
+        IndexedWalkIter {
+            mode,
+            graph: self,
+            buffer,
+            visited,
+        }
+    }
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    // Seeded from the reverse index rather than a full scan
+    pub fn ordering(&self) -> OrderingIter<'_, T> {
+        OrderingIter::with_indegrees(self, self.labels(), self.indegrees())
+    }
+
+    // The subset in an order respecting every dependency in the full graph,
+    // including those that only run through nodes outside the subset. None
+    // if a node is missing or sits on or behind a cycle
+    pub fn ordering_of<'a, I>(&self, subset: I) -> Option<Vec<&T>>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        let keys = subset.into_iter().map(hash).collect::<HashSet<_>>();
+        let ordered = self
+            .ordering()
+            .filter(|l| keys.contains(&hash(*l)))
+            .collect::<Vec<_>>();
+        if ordered.len() == keys.len() {
+            Some(ordered)
+        } else {
+            None
+        }
+    }
+
+    // Each node's position in the topological order, None if there's a
+    // cycle so not every node has one
+    pub fn topo_index(&self) -> Option<HashMap<&T, usize>> {
+        let index = self
+            .ordering()
+            .enumerate()
+            .map(|(i, l)| (l, i))
+            .collect::<HashMap<_, _>>();
+        if index.len() == self.size() {
+            Some(index)
+        } else {
+            None
         }
     }
+
+    // Each reachable node's position in the breadth first walk from start,
+    // None if start isn't in the graph
+    pub fn bfs_order_index(&self, start: &T) -> Option<HashMap<&T, usize>> {
+        self.get(start)?;
+        Some(
+            self.bfs_where(start, |_| true)
+                .enumerate()
+                .map(|(i, l)| (l, i))
+                .collect(),
+        )
+    }
+
+    // Topological order where the ready node with the highest key goes first
+    pub fn ordering_by_priority<K, F>(&self, priority: F) -> PriorityOrderingIter<'_, T, K, F>
+    where
+        K: Ord,
+        F: Fn(&T) -> K,
+    {
+        PriorityOrderingIter::with_indegrees(self, self.labels(), self.indegrees(), priority)
+    }
 }
 
-pub struct WalkIter<'a, T> {
+pub struct WalkIter<'a, T, G = Graph<T>> {
     mode: Mode,
-    graph: &'a Graph<T>,
+    graph: &'a G,
     buffer: VecDeque<&'a T>,
     visited: HashSet<u64>,
 }
 
-impl<'a, T: Hash + Eq> Iterator for WalkIter<'a, T> {
+impl<'a, T: Hash + Eq, G: GraphBase<T>> WalkIter<'a, T, G> {
+    // Clones the rest of the walk so it can outlive the graph borrow
+    pub fn into_labels(self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.cloned().collect()
+    }
+}
+
+impl<'a, T: Hash, G> WalkIter<'a, T, G> {
+    pub(crate) fn new(graph: &'a G, start: &'a T, mode: Mode) -> Self {
+        let mut buffer = VecDeque::new();
+        buffer.push_front(start);
+
+        let mut visited = HashSet::new();
+        visited.insert(hash(start));
+        WalkIter {
+            mode,
+            buffer,
+            visited,
+            graph,
+        }
+    }
+}
+
+impl<'a, T: Hash + Eq, G: GraphBase<T>> Iterator for WalkIter<'a, T, G> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -55,17 +271,329 @@ impl<'a, T: Hash + Eq> Iterator for WalkIter<'a, T> {
             Mode::Depth => self.buffer.pop_front()?,
         };
 
-        if let Some(connections) = self.graph.connections(next) {
-            for connection in connections {
-                let key = hash(connection);
-                if !self.visited.contains(&key) {
-                    self.visited.insert(key);
-                    self.buffer.push_front(connection);
-                }
+        for connection in self.graph.neighbors(next) {
+            let key = hash(connection);
+            if !self.visited.contains(&key) {
+                self.visited.insert(key);
+                self.buffer.push_front(connection);
             }
         }
         Some(next)
     }
+
+    // Everything queued is still to come, the rest depends on the graph
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.buffer.len(), None)
+    }
+}
+
+impl<T: Hash + Eq, G: GraphBase<T>> FusedIterator for WalkIter<'_, T, G> {}
+
+// Buffers for walk_with that can be kept around between traversals
+#[derive(Debug, Default)]
+pub struct TraversalScratch {
+    buffer: VecDeque<u64>,
+    visited: HashSet<u64>,
+}
+
+impl TraversalScratch {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+pub struct ScratchWalkIter<'a, 's, T> {
+    mode: Mode,
+    graph: &'a Graph<T>,
+    scratch: &'s mut TraversalScratch,
+}
+
+impl<'a, 's, T> Iterator for ScratchWalkIter<'a, 's, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = match self.mode {
+            Mode::Bredth => self.scratch.buffer.pop_back()?,
+            Mode::Depth => self.scratch.buffer.pop_front()?,
+        };
+
+        let node = &self.graph.nodes[&key];
+        for target in node.edges.keys() {
+            if self.scratch.visited.insert(*target) {
+                self.scratch.buffer.push_front(*target);
+            }
+        }
+        Some(&node.label)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let queued = self.scratch.buffer.len();
+        let yielded = self.scratch.visited.len() - queued;
+        (queued, Some(self.graph.size() - yielded))
+    }
+}
+
+impl<T> FusedIterator for ScratchWalkIter<'_, '_, T> {}
+
+pub struct FilteredWalkIter<'a, T, F> {
+    mode: Mode,
+    graph: &'a Graph<T>,
+    buffer: VecDeque<u64>,
+    visited: HashSet<u64>,
+    follow: F,
+}
+
+impl<'a, T, F: FnMut(i64) -> bool> Iterator for FilteredWalkIter<'a, T, F> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = match self.mode {
+            Mode::Bredth => self.buffer.pop_back()?,
+            Mode::Depth => self.buffer.pop_front()?,
+        };
+
+        let node = &self.graph.nodes[&key];
+        for (target, weight) in node.edges.iter() {
+            if !self.visited.contains(target) && (self.follow)(*weight) {
+                self.visited.insert(*target);
+                self.buffer.push_front(*target);
+            }
+        }
+        Some(&node.label)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let queued = self.buffer.len();
+        let yielded = self.visited.len() - queued;
+        (queued, Some(self.graph.size() - yielded))
+    }
+}
+
+impl<T, F: FnMut(i64) -> bool> FusedIterator for FilteredWalkIter<'_, T, F> {}
+
+// Grows to fit the largest index seen below limit, any index at or past
+// it is hashed into spill
+struct Bitset {
+    words: Vec<u64>,
+    spill: HashSet<usize>,
+    limit: usize,
+    len: usize,
+}
+
+impl Bitset {
+    fn new(limit: usize) -> Self {
+        Bitset {
+            words: Vec::new(),
+            spill: HashSet::new(),
+            limit,
+            len: 0,
+        }
+    }
+
+    // Returns false if already present, like HashSet::insert
+    fn insert(&mut self, index: usize) -> bool {
+        if index >= self.limit {
+            let fresh = self.spill.insert(index);
+            self.len += fresh as usize;
+            return fresh;
+        }
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let fresh = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        self.len += fresh as usize;
+        fresh
+    }
+}
+
+pub struct IndexedWalkIter<'a> {
+    mode: Mode,
+    graph: &'a Graph<usize>,
+    buffer: VecDeque<&'a Node<usize>>,
+    visited: Bitset,
+}
+
+impl<'a> Iterator for IndexedWalkIter<'a> {
+    type Item = &'a usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = match self.mode {
+            Mode::Bredth => self.buffer.pop_back()?,
+            Mode::Depth => self.buffer.pop_front()?,
+        };
+
+        for target in node.edges.keys() {
+            let target = &self.graph.nodes[target];
+            if self.visited.insert(target.label) {
+                self.buffer.push_front(target);
+            }
+        }
+        Some(&node.label)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let yielded = self.visited.len - self.buffer.len();
+        (self.buffer.len(), Some(self.graph.size() - yielded))
+    }
+}
+
+impl FusedIterator for IndexedWalkIter<'_> {}
+
+// Topological order, nodes on or behind a cycle are never yielded
+pub struct OrderingIter<'a, T, G = Graph<T>> {
+    graph: &'a G,
+    indegrees: HashMap<u64, usize>,
+    ready: Vec<&'a T>,
+    remaining: usize,
+}
+
+fn indegrees<T: Hash, G: GraphBase<T>>(graph: &G) -> (Vec<&T>, HashMap<u64, usize>) {
+    let labels = graph.labels();
+    let mut indegrees = labels
+        .iter()
+        .map(|l| (hash(*l), 0))
+        .collect::<HashMap<_, _>>();
+    for label in &labels {
+        for neighbor in graph.neighbors(label) {
+            *indegrees.get_mut(&hash(neighbor)).unwrap() += 1;
+        }
+    }
+    (labels, indegrees)
+}
+
+impl<'a, T: Hash, G: GraphBase<T>> OrderingIter<'a, T, G> {
+    pub(crate) fn new(graph: &'a G) -> Self {
+        let (labels, indegrees) = indegrees(graph);
+        Self::with_indegrees(graph, labels, indegrees)
+    }
+
+    pub(crate) fn with_indegrees(
+        graph: &'a G,
+        mut ready: Vec<&'a T>,
+        indegrees: HashMap<u64, usize>,
+    ) -> Self {
+        ready.retain(|l| indegrees[&hash(*l)] == 0);
+        OrderingIter {
+            graph,
+            remaining: indegrees.len(),
+            indegrees,
+            ready,
+        }
+    }
+
+    // Clones the rest of the ordering so it can outlive the graph borrow
+    pub fn into_labels(self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.cloned().collect()
+    }
+}
+
+impl<'a, T: Hash, G: GraphBase<T>> Iterator for OrderingIter<'a, T, G> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let label = self.ready.pop()?;
+        self.remaining -= 1;
+        for neighbor in self.graph.neighbors(label) {
+            let degree = self.indegrees.get_mut(&hash(neighbor)).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                self.ready.push(neighbor);
+            }
+        }
+        Some(label)
+    }
+
+    // Short of the upper bound if there's a cycle
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ready.len(), Some(self.remaining))
+    }
+}
+
+impl<T: Hash, G: GraphBase<T>> FusedIterator for OrderingIter<'_, T, G> {}
+
+pub struct PriorityOrderingIter<'a, T, K, F, G = Graph<T>> {
+    graph: &'a G,
+    indegrees: HashMap<u64, usize>,
+    ready: BinaryHeap<(K, Reverse<u64>)>,
+    labels: HashMap<u64, &'a T>,
+    priority: F,
+    remaining: usize,
+}
+
+impl<'a, T: Hash, K: Ord, F: Fn(&T) -> K, G: GraphBase<T>> PriorityOrderingIter<'a, T, K, F, G> {
+    pub(crate) fn new(graph: &'a G, priority: F) -> Self {
+        let (labels, indegrees) = indegrees(graph);
+        Self::with_indegrees(graph, labels, indegrees, priority)
+    }
+
+    pub(crate) fn with_indegrees(
+        graph: &'a G,
+        labels: Vec<&'a T>,
+        indegrees: HashMap<u64, usize>,
+        priority: F,
+    ) -> Self {
+        let labels = labels
+            .into_iter()
+            .map(|l| (hash(l), l))
+            .collect::<HashMap<_, _>>();
+        let ready = indegrees
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(k, _)| (priority(labels[k]), Reverse(*k)))
+            .collect();
+        PriorityOrderingIter {
+            graph,
+            remaining: indegrees.len(),
+            indegrees,
+            ready,
+            labels,
+            priority,
+        }
+    }
+}
+
+impl<'a, T, K, F, G> Iterator for PriorityOrderingIter<'a, T, K, F, G>
+where
+    T: Hash,
+    K: Ord,
+    F: Fn(&T) -> K,
+    G: GraphBase<T>,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, Reverse(key)) = self.ready.pop()?;
+        let label = self.labels[&key];
+        self.remaining -= 1;
+        for neighbor in self.graph.neighbors(label) {
+            let target = hash(neighbor);
+            let degree = self.indegrees.get_mut(&target).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                self.ready
+                    .push(((self.priority)(neighbor), Reverse(target)));
+            }
+        }
+        Some(label)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ready.len(), Some(self.remaining))
+    }
+}
+
+impl<T, K, F, G> FusedIterator for PriorityOrderingIter<'_, T, K, F, G>
+where
+    T: Hash,
+    K: Ord,
+    F: Fn(&T) -> K,
+    G: GraphBase<T>,
+{
 }
 
 pub struct Edge<'a, T> {
@@ -74,33 +602,85 @@ pub struct Edge<'a, T> {
     pub weight: i64,
 }
 
+// Nodes are expanded from either end, each end drains its own buffer from
+// the outside in and takes over the other's once the nodes run out
 pub struct EdgeIter<'a, T> {
     graph: &'a Graph<T>,
-    nodes: Vec<&'a Node<T>>,
-    edges: Vec<Edge<'a, T>>,
+    nodes: VecDeque<&'a Node<T>>,
+    front: VecDeque<Edge<'a, T>>,
+    back: VecDeque<Edge<'a, T>>,
+    remaining: usize,
+}
+
+// Refills an emptied buffer, reusing its allocation
+fn expand<'a, T>(graph: &'a Graph<T>, from: &'a Node<T>, buffer: &mut VecDeque<Edge<'a, T>>) {
+    buffer.extend(from.edges.iter().map(|(to, weight)| Edge {
+        from: &from.label,
+        to: &graph.nodes[to].label,
+        weight: *weight,
+    }));
+}
+
+impl<'a, T> EdgeIter<'a, T> {
+    // Clones the remaining edges as (from, to, weight) so they can outlive
+    // the graph borrow
+    pub fn into_owned(self) -> Vec<(T, T, i64)>
+    where
+        T: Clone,
+    {
+        self.map(|e| (e.from.clone(), e.to.clone(), e.weight))
+            .collect()
+    }
 }
 
 impl<'a, T> Iterator for EdgeIter<'a, T> {
     type Item = Edge<'a, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(edge) = self.edges.pop() {
-            return Some(edge);
+        loop {
+            if let Some(edge) = self.front.pop_back() {
+                self.remaining -= 1;
+                return Some(edge);
+            }
+            match self.nodes.pop_back() {
+                Some(from) => expand(self.graph, from, &mut self.front),
+                None => {
+                    let edge = self.back.pop_back()?;
+                    self.remaining -= 1;
+                    return Some(edge);
+                }
+            }
         }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
 
-        let from = self.nodes.pop()?;
-        for edge in &from.edges {
-            let to = self.graph.nodes.get(edge.0).unwrap();
-            self.edges.push(Edge {
-                from: &from.label,
-                to: &to.label,
-                weight: edge.1.to_owned(),
-            });
+impl<T> DoubleEndedIterator for EdgeIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(edge) = self.back.pop_front() {
+                self.remaining -= 1;
+                return Some(edge);
+            }
+            match self.nodes.pop_front() {
+                Some(from) => expand(self.graph, from, &mut self.back),
+                None => {
+                    let edge = self.front.pop_front()?;
+                    self.remaining -= 1;
+                    return Some(edge);
+                }
+            }
         }
-        self.next()
     }
 }
 
+impl<T> ExactSizeIterator for EdgeIter<'_, T> {}
+
+impl<T> FusedIterator for EdgeIter<'_, T> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,6 +748,165 @@ mod tests {
         assert_eq!(depth.len(), 3); // Only visit each once
     }
 
+    #[test]
+    fn scratch_walks() {
+        let mut g = Graph::init('a'..='f');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'a', &'d'));
+        assert!(g.connect(&'d', &'e'));
+        assert!(g.connect(&'d', &'f'));
+        assert!(g.connect(&'f', &'a'));
+
+        let mut scratch = TraversalScratch::new();
+        for start in 'a'..='f' {
+            let bfs = g.bfs_with(&mut scratch, &start).collect::<Vec<_>>();
+            assert_eq!(bfs, g.bfs(&start).collect::<Vec<_>>());
+            let dfs = g.dfs_with(&mut scratch, &start).collect::<Vec<_>>();
+            assert_eq!(dfs, g.dfs(&start).collect::<Vec<_>>());
+        }
+        assert_eq!(g.dfs_with(&mut scratch, &'z').count(), 0);
+    }
+
+    #[test]
+    fn filtered_walks() {
+        let mut g = Graph::init(1u8..=6);
+        for (a, b, w) in [
+            (1, 2, 5),
+            (2, 3, 1),
+            (2, 4, 3),
+            (4, 5, 4),
+            (1, 6, 2),
+            (6, 5, 9),
+        ] {
+            assert!(g.connect_weighted(&a, &b, w));
+        }
+
+        let mut strong = g.bfs_where(&1, |w| w >= 3).copied().collect::<Vec<_>>();
+        strong.sort_unstable();
+        assert_eq!(strong, vec![1, 2, 4, 5]);
+        assert_eq!(g.dfs_where(&1, |w| w >= 3).count(), 4);
+        assert_eq!(g.bfs_where(&1, |_| true).count(), g.bfs(&1).count());
+        assert_eq!(g.dfs_where(&1, |_| false).collect::<Vec<_>>(), vec![&1]);
+
+        // A weak edge doesn't hide a node reachable another way
+        assert!(g.bfs_where(&1, |w| w != 4).any(|n| *n == 5));
+        assert_eq!(g.bfs_where(&7, |_| true).count(), 0);
+    }
+
+    #[test]
+    fn indexed_walks() {
+        let mut g = Graph::init(0..200);
+        for i in 0..200 {
+            assert!(g.connect(&i, &((i * 7 + 3) % 200)));
+            assert!(g.connect(&i, &((i * 13 + 1) % 200)));
+        }
+
+        for start in [0, 63, 64, 199] {
+            let bfs = g.bfs_indexed(start).collect::<Vec<_>>();
+            assert_eq!(bfs, g.bfs(&start).collect::<Vec<_>>());
+            let dfs = g.dfs_indexed(start).collect::<Vec<_>>();
+            assert_eq!(dfs, g.dfs(&start).collect::<Vec<_>>());
+        }
+        assert_eq!(g.bfs_indexed(500).count(), 0);
+
+        // Huge labels don't size the bitset
+        let mut g = Graph::init([1, usize::MAX, usize::MAX / 2]);
+        assert!(g.connect(&1, &usize::MAX));
+        assert!(g.connect(&usize::MAX, &(usize::MAX / 2)));
+        assert!(g.connect(&(usize::MAX / 2), &1));
+        let walk = g.bfs_indexed(1);
+        assert_eq!(walk.size_hint(), (1, Some(3)));
+        assert_eq!(
+            walk.collect::<Vec<_>>(),
+            vec![&1, &usize::MAX, &(usize::MAX / 2)]
+        );
+        let mut walk = g.dfs_indexed(usize::MAX);
+        assert!(walk.visited.words.is_empty());
+        assert_eq!(walk.by_ref().count(), 3);
+        assert_eq!(walk.visited.words.len(), 1);
+        assert_eq!(walk.visited.spill.len(), 2);
+    }
+
+    #[test]
+    fn ordering() {
+        let mut g = Graph::init('a'..='f');
+
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'a', &'d'));
+        assert!(g.connect(&'d', &'e'));
+        assert!(g.connect(&'d', &'f'));
+
+        let order = g.ordering().collect::<Vec<_>>();
+        assert_eq!(order.len(), 6);
+        assert_order(&order);
+
+        // e -> a closes a cycle so nothing can start
+        assert!(g.connect(&'e', &'a'));
+        assert_eq!(g.ordering().count(), 0);
+    }
+
+    #[test]
+    fn ordering_of() {
+        // a -> x -> b and b -> y -> c, with only a, b and c asked for
+        let mut g = Graph::init(vec!['a', 'b', 'c', 'x', 'y']);
+        assert!(g.connect(&'c', &'y'));
+        assert!(g.connect(&'y', &'b'));
+        assert!(g.connect(&'b', &'x'));
+        assert!(g.connect(&'x', &'a'));
+
+        let order = g.ordering_of(&['a', 'b', 'c']).unwrap();
+        assert_eq!(order, vec![&'c', &'b', &'a']);
+        assert_eq!(g.ordering_of(&['a', 'a']).unwrap(), vec![&'a']);
+        assert!(g.ordering_of(&['a', 'z']).is_none());
+
+        assert!(g.connect(&'a', &'x'));
+        assert!(g.ordering_of(&['c', 'b']).is_some());
+        assert!(g.ordering_of(&['c', 'a']).is_none());
+    }
+
+    #[test]
+    fn order_indices() {
+        let mut g = Graph::init('a'..='e');
+        for (a, b) in [('a', 'b'), ('a', 'c'), ('b', 'd'), ('c', 'd')] {
+            assert!(g.connect(&a, &b));
+        }
+        {
+            let topo = g.topo_index().unwrap();
+            assert_eq!(topo.len(), 5);
+            for edge in g.edges() {
+                assert!(topo[edge.from] < topo[edge.to]);
+            }
+
+            let bfs = g.bfs_order_index(&'a').unwrap();
+            assert_eq!(bfs.len(), 4);
+            assert_eq!((bfs[&'a'], bfs[&'d']), (0, 3));
+            assert!(bfs[&'b'] < 3 && bfs[&'c'] < 3);
+            assert!(g.bfs_order_index(&'z').is_none());
+        }
+
+        assert!(g.connect(&'d', &'a'));
+        assert!(g.topo_index().is_none());
+    }
+
+    #[test]
+    fn ordering_by_priority() {
+        let mut g = Graph::init('a'..='f');
+
+        // a -> b, c, d and b, c, d -> e, f isolated
+        for mid in 'b'..='d' {
+            assert!(g.connect(&'a', &mid));
+            assert!(g.connect(&mid, &'e'));
+        }
+
+        let order = g.ordering_by_priority(|c| *c as u32).collect::<Vec<_>>();
+        assert_eq!(order, vec![&'f', &'a', &'d', &'c', &'b', &'e']);
+
+        let order = g.ordering_by_priority(|c| Reverse(*c)).collect::<Vec<_>>();
+        assert_eq!(order, vec![&'a', &'b', &'c', &'d', &'e', &'f']);
+    }
+
     #[test]
     fn edges() {
         let mut g = Graph::init('a'..='f');
@@ -180,4 +919,111 @@ mod tests {
 
         assert_eq!(g.edges().count(), 4)
     }
+
+    #[test]
+    fn edges_weighted() {
+        let mut g = Graph::init(0u8..6);
+        for (a, b, w) in [
+            (0, 1, 2),
+            (1, 0, 3),
+            (1, 2, 1),
+            (2, 2, -4),
+            (3, 4, 5),
+            (5, 0, 1),
+        ] {
+            assert!(g.connect_weighted(&a, &b, w));
+        }
+        // An edge both ways is two edges and a self-loop is one
+        let mut expected = vec![
+            (0, 1, 2),
+            (1, 0, 3),
+            (1, 2, 1),
+            (2, 2, -4),
+            (3, 4, 5),
+            (5, 0, 1),
+        ];
+        let owned = |edges: Vec<(&u8, &u8, i64)>| {
+            let mut edges = edges
+                .into_iter()
+                .map(|(a, b, w)| (*a, *b, w))
+                .collect::<Vec<_>>();
+            edges.sort_unstable();
+            edges
+        };
+        expected.sort_unstable();
+        assert_eq!(owned(g.edges_weighted().collect()), expected);
+        assert_eq!(owned(g.edges_weighted().rev().collect()), expected);
+
+        // Meeting in the middle still yields each edge exactly once
+        let mut edges = g.edges_weighted();
+        assert_eq!(edges.len(), g.edge_count());
+        let mut mixed = Vec::new();
+        while let (Some(front), back) = (edges.next(), edges.next_back()) {
+            mixed.push(front);
+            mixed.extend(back);
+        }
+        assert_eq!(owned(mixed), expected);
+    }
+
+    #[test]
+    fn iterator_contracts() {
+        let mut g = Graph::init('a'..='f');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'a', &'c'));
+        assert!(g.connect(&'c', &'d'));
+        assert!(g.connect(&'e', &'d'));
+        assert!(g.connect(&'e', &'f'));
+
+        let mut edges = g.edges();
+        assert_eq!(edges.len(), 5);
+        edges.next();
+        edges.next_back();
+        assert_eq!(edges.len(), 3);
+        assert_eq!(edges.by_ref().count(), 3);
+        assert!(edges.next().is_none() && edges.next_back().is_none());
+
+        // Either end gives the same edges, just reversed
+        let forward = g.edges().map(|e| (e.from, e.to)).collect::<Vec<_>>();
+        let mut backward = g.edges().rev().map(|e| (e.from, e.to)).collect::<Vec<_>>();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        // Meeting in the middle of one node's edges
+        let mut edges = g.edges();
+        let mut seen = Vec::new();
+        while let Some(edge) = edges.next() {
+            seen.push((edge.from, edge.to));
+            seen.extend(edges.next_back().map(|e| (e.from, e.to)));
+        }
+        seen.sort();
+        let mut all = forward.clone();
+        all.sort();
+        assert_eq!(seen, all);
+
+        let mut owned = g.edges().into_owned();
+        owned.sort();
+        assert_eq!(owned[0], ('a', 'b', 1));
+
+        let mut walk = g.bfs(&'a');
+        assert_eq!(walk.size_hint(), (1, None));
+        walk.next();
+        assert_eq!(walk.size_hint(), (2, None));
+        assert_eq!(walk.into_labels().len(), 3);
+
+        let mut scratch = TraversalScratch::new();
+        let mut walk = g.dfs_with(&mut scratch, &'a');
+        walk.next();
+        assert_eq!(walk.size_hint(), (2, Some(5)));
+        assert_eq!(walk.count(), 3);
+
+        let mut order = g.ordering();
+        assert_eq!(order.size_hint(), (2, Some(6)));
+        order.next();
+        assert_eq!(order.size_hint().1, Some(5));
+        assert_eq!(order.into_labels().len(), 5);
+        assert_eq!(g.ordering_by_priority(|c| *c).size_hint(), (2, Some(6)));
+
+        let g = Graph::init(0..4usize);
+        assert_eq!(g.bfs_indexed(2).size_hint(), (1, Some(4)));
+    }
 }
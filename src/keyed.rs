@@ -0,0 +1,156 @@
+use crate::{base::GraphBase, graph::*, hash};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Deref;
+
+// Graph whose node identity is a key pulled out of each value, so the rest
+// of the value is free payload that doesn't need Hash or Eq. The wrapped
+// graph is over the keys, with the values held alongside
+pub struct KeyedGraph<T, K, F> {
+    graph: Graph<K>,
+    values: HashMap<u64, T>,
+    key_fn: F,
+}
+
+impl<K: Hash + Eq> Graph<K> {
+    pub fn with_key_fn<T, F: Fn(&T) -> K>(key_fn: F) -> KeyedGraph<T, K, F> {
+        KeyedGraph {
+            graph: Graph::new(),
+            values: HashMap::new(),
+            key_fn,
+        }
+    }
+}
+
+impl<T, K, F> Deref for KeyedGraph<T, K, F> {
+    type Target = Graph<K>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T, K, F> KeyedGraph<T, K, F> {
+    pub fn into_inner(self) -> Graph<K> {
+        self.graph
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_ {
+        self.values.values()
+    }
+}
+
+impl<T, K: Hash + Eq, F: Fn(&T) -> K> KeyedGraph<T, K, F> {
+    pub fn key_of(&self, value: &T) -> K {
+        (self.key_fn)(value)
+    }
+
+    // A value with a key that's already present replaces the old value,
+    // which is given back, and keeps its edges
+    pub fn add(&mut self, value: T) -> Option<T> {
+        let label = self.key_of(&value);
+        let key = hash(&label);
+        if !self.graph.nodes.contains_key(&key) {
+            self.graph.add(label);
+        }
+        self.values.insert(key, value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<T> {
+        self.graph.remove(key)?;
+        self.values.remove(&hash(key))
+    }
+
+    pub fn get(&self, key: &K) -> Option<&T> {
+        self.values.get(&hash(key))
+    }
+
+    // The key is only read when the value is added, so changing the part
+    // it comes from here doesn't move the node
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        self.values.get_mut(&hash(key))
+    }
+
+    pub fn connect(&mut self, from: &K, to: &K) -> bool {
+        self.graph.connect(from, to)
+    }
+
+    pub fn disconnect(&mut self, from: &K, to: &K) -> bool {
+        self.graph.disconnect(from, to)
+    }
+
+    pub fn neighbor_values(&self, key: &K) -> Vec<&T> {
+        self.graph
+            .neighbors(key)
+            .into_iter()
+            .map(|k| &self.values[&hash(k)])
+            .collect()
+    }
+}
+
+impl<T, K: Hash + Eq, F> GraphBase<K> for KeyedGraph<T, K, F> {
+    fn contains(&self, label: &K) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&K> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &K) -> Vec<&K> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &K, to: &K) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // No Hash or Eq, the score is payload
+    #[derive(Debug)]
+    struct Task {
+        id: u32,
+        name: String,
+        score: f64,
+    }
+
+    fn task(id: u32, name: &str) -> Task {
+        Task {
+            id,
+            name: name.to_owned(),
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn keyed() {
+        let mut g = Graph::with_key_fn(|t: &Task| t.id);
+        assert!(g.add(task(1, "fetch")).is_none());
+        assert!(g.add(task(2, "build")).is_none());
+        assert!(g.add(task(3, "test")).is_none());
+        assert!(g.connect(&1, &2));
+        assert!(g.connect(&2, &3));
+        assert!(!g.connect(&2, &4));
+
+        g.get_mut(&2).unwrap().score = 0.5;
+        assert_eq!(g.get(&2).unwrap().score, 0.5);
+        assert_eq!(g.neighbor_values(&1)[0].name, "build");
+        assert_eq!(g.bfs(&1).count(), 3);
+
+        // Same id, so the value is swapped in place and the edges stay
+        let old = g.add(task(2, "compile")).unwrap();
+        assert_eq!(old.name, "build");
+        assert_eq!(g.neighbor_values(&1)[0].name, "compile");
+        assert_eq!(g.outdegree(&2), Some(1));
+        assert_eq!(g.size(), 3);
+
+        assert_eq!(g.remove(&3).unwrap().name, "test");
+        assert!(g.remove(&3).is_none());
+        assert_eq!(g.values().count(), 2);
+        assert_eq!(g.edge_count(), 1);
+    }
+}
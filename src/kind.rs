@@ -0,0 +1,272 @@
+use crate::iter::Mode;
+use crate::{base::GraphBase, graph::*, hash};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::ops::Deref;
+
+// Graph wrapper where each edge carries one or more kinds of relationship.
+// The wrapped graph has an edge wherever there is at least one kind
+#[derive(Debug)]
+pub struct KindedGraph<T, K> {
+    graph: Graph<T>,
+    kinds: HashMap<(u64, u64), HashSet<K>>,
+}
+
+// Only the edges with at least one of the given kinds
+pub struct KindView<'a, T, K> {
+    graph: &'a KindedGraph<T, K>,
+    kinds: HashSet<&'a K>,
+}
+
+impl<T, K> Default for KindedGraph<T, K> {
+    fn default() -> Self {
+        KindedGraph {
+            graph: Graph::new(),
+            kinds: HashMap::new(),
+        }
+    }
+}
+
+impl<T, K> Deref for KindedGraph<T, K> {
+    type Target = Graph<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T, K> KindedGraph<T, K> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn into_inner(self) -> Graph<T> {
+        self.graph
+    }
+}
+
+impl<T: Hash + Eq, K: Hash + Eq> KindedGraph<T, K> {
+    // Replacing a node drops its outgoing edges, so their kinds go too
+    pub fn add(&mut self, label: T) {
+        let key = hash(&label);
+        if let Some(node) = self.graph.nodes.get(&key) {
+            for to in node.edges.keys() {
+                self.kinds.remove(&(key, *to));
+            }
+        }
+        self.graph.add(label);
+    }
+
+    pub fn remove(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        let Some((node, incoming)) = self.graph.remove_node(key) else {
+            return false;
+        };
+        for to in node.edges.keys() {
+            self.kinds.remove(&(key, *to));
+        }
+        for (from, _) in incoming {
+            self.kinds.remove(&(from, key));
+        }
+        true
+    }
+
+    pub fn connect_kind(&mut self, from: &T, to: &T, kind: K) -> bool {
+        if !self.graph.connect(from, to) {
+            return false;
+        }
+        self.kinds
+            .entry((hash(from), hash(to)))
+            .or_default()
+            .insert(kind);
+        true
+    }
+
+    // The edge itself goes once its last kind is removed
+    pub fn disconnect_kind(&mut self, from: &T, to: &T, kind: &K) -> bool {
+        let pair = (hash(from), hash(to));
+        let kinds = match self.kinds.get_mut(&pair) {
+            Some(kinds) => kinds,
+            None => return false,
+        };
+        if !kinds.remove(kind) {
+            return false;
+        }
+        if kinds.is_empty() {
+            self.kinds.remove(&pair);
+            self.graph.disconnect(from, to);
+        }
+        true
+    }
+
+    // Removes the edge along with every kind on it
+    pub fn disconnect(&mut self, from: &T, to: &T) -> bool {
+        self.kinds.remove(&(hash(from), hash(to)));
+        self.graph.disconnect(from, to)
+    }
+
+    pub fn kinds(&self, from: &T, to: &T) -> Vec<&K> {
+        match self.kinds.get(&(hash(from), hash(to))) {
+            Some(kinds) => kinds.iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn is_connected_kind(&self, from: &T, to: &T, kind: &K) -> bool {
+        self.kinds
+            .get(&(hash(from), hash(to)))
+            .is_some_and(|k| k.contains(kind))
+    }
+
+    pub fn neighbors_of_kind(&self, label: &T, kind: &K) -> Vec<&T> {
+        let mut neighbors = self.graph.neighbors(label);
+        neighbors.retain(|n| self.is_connected_kind(label, n, kind));
+        neighbors
+    }
+
+    pub fn of_kinds<'a, I>(&'a self, kinds: I) -> KindView<'a, T, K>
+    where
+        I: IntoIterator<Item = &'a K>,
+    {
+        KindView {
+            graph: self,
+            kinds: kinds.into_iter().collect(),
+        }
+    }
+
+    // Everything reachable from start following only the given kinds, in
+    // the same order as walk on of_kinds would give
+    pub fn walk_filtered<'a, I>(&self, start: &T, kinds: I, mode: Mode) -> Vec<&T>
+    where
+        I: IntoIterator<Item = &'a K>,
+        K: 'a,
+    {
+        let kinds = kinds.into_iter().collect::<HashSet<_>>();
+        let start = hash(start);
+        if !self.graph.nodes.contains_key(&start) {
+            return Vec::new();
+        }
+        let mut buffer = VecDeque::new();
+        buffer.push_front(start);
+        let mut visited = HashSet::new();
+        visited.insert(start);
+
+        let mut walked = Vec::new();
+        while let Some(key) = match mode {
+            Mode::Bredth => buffer.pop_back(),
+            Mode::Depth => buffer.pop_front(),
+        } {
+            let node = &self.graph.nodes[&key];
+            for target in node.edges.keys() {
+                let matches = self.kinds[&(key, *target)]
+                    .iter()
+                    .any(|k| kinds.contains(k));
+                if matches && visited.insert(*target) {
+                    buffer.push_front(*target);
+                }
+            }
+            walked.push(&node.label);
+        }
+        walked
+    }
+}
+
+impl<T: Hash + Eq, K> GraphBase<T> for KindedGraph<T, K> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+impl<T: Hash + Eq, K: Hash + Eq> GraphBase<T> for KindView<'_, T, K> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        let mut neighbors = self.graph.neighbors(label);
+        neighbors.retain(|n| self.weight(label, n).is_some());
+        neighbors
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        let kinds = self.graph.kinds.get(&(hash(from), hash(to)))?;
+        if kinds.iter().any(|k| self.kinds.contains(k)) {
+            self.graph.weight(from, to)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    enum Kind {
+        BuildDep,
+        RunDep,
+        Optional,
+    }
+
+    #[test]
+    fn kinds() {
+        let mut g = KindedGraph::new();
+        for label in 'a'..='d' {
+            g.add(label);
+        }
+        assert!(g.connect_kind(&'a', &'b', Kind::BuildDep));
+        assert!(g.connect_kind(&'a', &'b', Kind::RunDep));
+        assert!(g.connect_kind(&'b', &'c', Kind::RunDep));
+        assert!(g.connect_kind(&'a', &'d', Kind::Optional));
+        assert!(!g.connect_kind(&'a', &'z', Kind::Optional));
+
+        assert_eq!(g.kinds(&'a', &'b').len(), 2);
+        assert_eq!(g.neighbors_of_kind(&'a', &Kind::BuildDep), vec![&'b']);
+        assert_eq!(g.neighbors(&'a').len(), 2);
+
+        let run = g.walk_filtered(&'a', &[Kind::RunDep], Mode::Depth);
+        assert_eq!(run, vec![&'a', &'b', &'c']);
+        let kinds = [Kind::BuildDep, Kind::Optional];
+        let build = g.walk_filtered(&'a', &kinds, Mode::Bredth);
+        assert_eq!(build.len(), 3);
+        assert!(!build.contains(&&'c'));
+        assert_eq!(build, g.of_kinds(&kinds).bfs(&'a').collect::<Vec<_>>());
+
+        // The edge stays until its last kind is gone
+        assert!(g.disconnect_kind(&'a', &'b', &Kind::BuildDep));
+        assert!(!g.disconnect_kind(&'a', &'b', &Kind::BuildDep));
+        assert!(g.is_connected(&'a', &'b'));
+        assert!(g.disconnect_kind(&'a', &'b', &Kind::RunDep));
+        assert!(!g.is_connected(&'a', &'b'));
+
+        assert!(g.remove(&'c'));
+        assert!(g.kinds(&'b', &'c').is_empty());
+        assert!(!g.remove(&'c'));
+
+        // Re-adding drops the node's outgoing kinds but not its incoming
+        assert!(g.connect_kind(&'b', &'d', Kind::RunDep));
+        assert!(g.connect_kind(&'d', &'b', Kind::RunDep));
+        g.add('d');
+        assert!(g.kinds(&'d', &'b').is_empty());
+        assert_eq!(g.kinds(&'b', &'d').len(), 1);
+        assert!(g.disconnect(&'a', &'d'));
+        assert!(g.of_kinds(&[Kind::Optional]).neighbors(&'a').is_empty());
+    }
+}
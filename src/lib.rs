@@ -1,12 +1,131 @@
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "hashbrown")))]
+compile_error!("either the `std` or `hashbrown` feature is required");
+
+extern crate alloc;
+
+pub mod base;
+#[cfg(feature = "binary")]
+pub mod binary;
+#[cfg(feature = "std")]
+pub mod budget;
+#[cfg(feature = "std")]
+pub mod canonical;
+#[cfg(feature = "std")]
+pub mod centrality;
+#[cfg(feature = "std")]
+pub mod cluster;
+#[cfg(feature = "std")]
+pub mod contract;
+#[cfg(feature = "std")]
+pub mod cover;
+#[cfg(feature = "std")]
+pub mod cycle;
+#[cfg(feature = "std")]
 pub mod draw;
+mod edges;
+#[cfg(feature = "std")]
+pub mod edit;
+pub mod entry;
+#[cfg(feature = "std")]
+pub mod evaluate;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod flow;
+#[cfg(feature = "std")]
+pub mod generate;
 pub mod graph;
+#[cfg(feature = "std")]
+pub mod group;
+#[cfg(feature = "std")]
+pub mod intern;
+#[cfg(feature = "std")]
+pub mod invalidate;
+#[cfg(feature = "std")]
+pub mod isomorphism;
 pub mod iter;
+#[cfg(feature = "std")]
+pub mod keyed;
+#[cfg(feature = "std")]
+pub mod kind;
+#[cfg(feature = "std")]
+pub mod load;
+pub mod measure;
+#[cfg(feature = "std")]
+pub mod minimize;
+#[cfg(feature = "std")]
+pub mod oracle;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod partition;
+#[cfg(feature = "std")]
+pub mod path;
+#[cfg(all(feature = "std", feature = "im"))]
+pub mod persistent;
+#[cfg(feature = "std")]
+pub mod provenance;
+#[cfg(feature = "std")]
+pub mod query;
+#[cfg(feature = "std")]
+pub mod reach;
+#[cfg(feature = "std")]
+pub mod relabel;
+#[cfg(feature = "std")]
+pub mod resolve;
+#[cfg(feature = "std")]
+pub mod schedule;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(all(feature = "std", any(test, feature = "proptest")))]
+pub mod strategy;
+#[cfg(feature = "std")]
+pub mod structure;
+#[cfg(feature = "std")]
+pub mod sync;
+#[cfg(feature = "std")]
+pub mod temporal;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "std")]
+pub mod undo;
+#[cfg(feature = "std")]
+pub mod util;
+pub mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use core::hash::{Hash, Hasher};
+
+// Node keys come from std's DefaultHasher, SipHash-1-3 with zero keys as
+// of writing, though std doesn't promise which algorithm it uses
+#[cfg(feature = "std")]
+fn hash<T: Hash>(data: &T) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
 
+// Without std there's no DefaultHasher, so keys come from core's
+// deprecated SipHasher instead, which is SipHash-2-4 with zero keys. The
+// same label gets a different key in std and no_std builds, so keys must
+// never be stored or shared between the two
+#[cfg(not(feature = "std"))]
+#[allow(deprecated)]
 fn hash<T: Hash>(data: &T) -> u64 {
-    let mut hasher = DefaultHasher::new();
+    let mut hasher = core::hash::SipHasher::new();
     data.hash(&mut hasher);
     hasher.finish()
 }
+
+// The core graph, traversal and ordering only need alloc, with hashbrown
+// standing in for the std maps
+mod collections {
+    pub(crate) use alloc::collections::{BinaryHeap, VecDeque};
+    #[cfg(not(feature = "std"))]
+    pub(crate) use hashbrown::{hash_map, HashMap, HashSet};
+    #[cfg(feature = "std")]
+    pub(crate) use std::collections::{hash_map, HashMap, HashSet};
+}
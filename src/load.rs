@@ -0,0 +1,129 @@
+use crate::{edges::Edges, graph::*, hash};
+use std::io::{self, BufRead};
+
+const PROGRESS_INTERVAL: usize = 100_000;
+
+impl Graph<String> {
+    // Builds a graph from lines of `from to [weight]`, creating nodes as
+    // they are first seen. Blank lines and lines starting with # are
+    // skipped. Progress is reported with the number of edges loaded so far,
+    // the last report always being the total
+    pub fn load_edges_streaming<R, F>(reader: R, progress: F) -> io::Result<Self>
+    where
+        R: BufRead,
+        F: FnMut(usize),
+    {
+        Self::load_edges_streaming_with_capacity(reader, 0, progress)
+    }
+
+    // Same again with room for `nodes` nodes up front, so a large load
+    // doesn't keep rehashing as it grows
+    pub fn load_edges_streaming_with_capacity<R, F>(
+        reader: R,
+        nodes: usize,
+        mut progress: F,
+    ) -> io::Result<Self>
+    where
+        R: BufRead,
+        F: FnMut(usize),
+    {
+        let mut graph = Graph::with_capacity(nodes);
+        let mut loaded = 0;
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || {
+                let message = format!("line {}: expected `from to [weight]`", number + 1);
+                io::Error::new(io::ErrorKind::InvalidData, message)
+            };
+            let mut parts = line.split_whitespace();
+            let from = parts.next().ok_or_else(invalid)?;
+            let to = parts.next().ok_or_else(invalid)?;
+            let weight = match parts.next() {
+                Some(w) => w.parse().map_err(|_| invalid())?,
+                None => 1,
+            };
+            if parts.next().is_some() {
+                return Err(invalid());
+            }
+
+            let to = graph.entry_key(to);
+            let from = graph.entry_key(from);
+            graph.set_edge(from, to, Some(weight));
+
+            loaded += 1;
+            if loaded % PROGRESS_INTERVAL == 0 {
+                progress(loaded);
+            }
+        }
+        if loaded == 0 || loaded % PROGRESS_INTERVAL != 0 {
+            progress(loaded);
+        }
+        Ok(graph)
+    }
+
+    fn entry_key(&mut self, label: &str) -> u64 {
+        let key = hash(&label);
+        if !self.nodes.contains_key(&key) {
+            let node = Node {
+                label: label.to_owned(),
+                edges: Edges::new(),
+                incoming: Edges::new(),
+            };
+            self.insert_node(key, node);
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaming() {
+        let input = "# deps\na b\nb c 5\n\na c\nd d\n";
+        let mut reports = Vec::new();
+        let g = Graph::load_edges_streaming(input.as_bytes(), |n| reports.push(n)).unwrap();
+
+        assert_eq!(g.size(), 4);
+        assert_eq!(g.edges().count(), 4);
+        assert!(g.is_connected(&"a".to_owned(), &"c".to_owned()));
+        assert!(g.edges().any(|e| e.from == "b" && e.weight == 5));
+        assert_eq!(reports, vec![4]);
+    }
+
+    #[test]
+    fn progress() {
+        let mut reports = Vec::new();
+        let input = "a b\n".repeat(PROGRESS_INTERVAL);
+        let g = Graph::load_edges_streaming(input.as_bytes(), |n| reports.push(n)).unwrap();
+        assert_eq!(g.edge_count(), 1);
+        assert_eq!(reports, vec![PROGRESS_INTERVAL]);
+
+        reports.clear();
+        let input = "a b\n".repeat(PROGRESS_INTERVAL + 1);
+        Graph::load_edges_streaming(input.as_bytes(), |n| reports.push(n)).unwrap();
+        assert_eq!(reports, vec![PROGRESS_INTERVAL, PROGRESS_INTERVAL + 1]);
+
+        reports.clear();
+        let g = Graph::load_edges_streaming_with_capacity("".as_bytes(), 64, |n| reports.push(n))
+            .unwrap();
+        assert_eq!(g.size(), 0);
+        assert_eq!(reports, vec![0]);
+    }
+
+    #[test]
+    fn malformed() {
+        let err = Graph::load_edges_streaming("a b\nc\n".as_bytes(), |_| {}).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 2"));
+
+        assert!(Graph::load_edges_streaming("a b x".as_bytes(), |_| {}).is_err());
+        assert!(Graph::load_edges_streaming("a b 1 2".as_bytes(), |_| {}).is_err());
+    }
+}
@@ -0,0 +1,27 @@
+use core::ops::Add;
+use core::time::Duration;
+
+// Costs that path searches can add up and compare. Edges store i64
+// weights, searches taking a cost function can map them to any Measure
+pub trait Measure: Copy + PartialOrd + Add<Output = Self> {
+    fn zero() -> Self;
+}
+
+macro_rules! measure {
+    ($($t:ty => $zero:expr),*) => {
+        $(
+            impl Measure for $t {
+                fn zero() -> Self {
+                    $zero
+                }
+            }
+        )*
+    };
+}
+
+measure!(
+    i8 => 0, i16 => 0, i32 => 0, i64 => 0, i128 => 0, isize => 0,
+    u8 => 0, u16 => 0, u32 => 0, u64 => 0, u128 => 0, usize => 0,
+    f32 => 0.0, f64 => 0.0,
+    Duration => Duration::ZERO
+);
@@ -0,0 +1,111 @@
+use crate::graph::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
+
+impl<T: Hash + Eq> Graph<T> {
+    // Merges nodes that are bisimilar: same class key and successors in the
+    // same merged blocks. Returns the quotient graph, labelled by block
+    // number, and the block each node ended up in. Edge weights are ignored
+    pub fn minimize<K, F>(&self, class: F) -> (Graph<usize>, HashMap<&T, usize>)
+    where
+        K: Ord,
+        F: Fn(&T) -> K,
+    {
+        let keys = self.nodes.keys().copied().collect::<Vec<_>>();
+        let classes = keys
+            .iter()
+            .map(|k| class(&self.nodes[k].label))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| (c, i))
+            .collect::<BTreeMap<_, _>>();
+        let mut blocks = keys
+            .iter()
+            .map(|k| (*k, classes[&class(&self.nodes[k].label)]))
+            .collect::<HashMap<_, _>>();
+        let mut count = classes.len();
+
+        // Split blocks by successor blocks until nothing changes
+        loop {
+            let signatures = keys
+                .iter()
+                .map(|k| {
+                    let successors = self.nodes[k]
+                        .edges
+                        .keys()
+                        .map(|t| blocks[t])
+                        .collect::<BTreeSet<_>>();
+                    (*k, (blocks[k], successors))
+                })
+                .collect::<HashMap<_, _>>();
+            let numbered = signatures
+                .values()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .enumerate()
+                .map(|(i, s)| (s, i))
+                .collect::<BTreeMap<_, _>>();
+            let refined = numbered.len();
+            blocks = signatures.iter().map(|(k, s)| (*k, numbered[s])).collect();
+            if refined == count {
+                break;
+            }
+            count = refined;
+        }
+
+        let mut quotient = Graph::init(0..count);
+        for (key, node) in &self.nodes {
+            for target in node.edges.keys() {
+                quotient.connect(&blocks[key], &blocks[target]);
+            }
+        }
+        let mapping = blocks
+            .into_iter()
+            .map(|(k, b)| (&self.nodes[&k].label, b))
+            .collect();
+        (quotient, mapping)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimize() {
+        // Two identical branches a -> b1 -> c and a -> b2 -> c, plus d -> c
+        // which looks like a b but is in a different class
+        let labels = ["a", "b1", "b2", "c", "d"];
+        let mut g = Graph::init(labels.iter().copied());
+        for (from, to) in [
+            ("a", "b1"),
+            ("a", "b2"),
+            ("b1", "c"),
+            ("b2", "c"),
+            ("d", "c"),
+        ] {
+            assert!(g.connect(&from, &to));
+        }
+
+        let (quotient, mapping) = g.minimize(|l| l.starts_with('b'));
+        assert_eq!(quotient.size(), 4);
+        assert_eq!(mapping[&"b1"], mapping[&"b2"]);
+        assert_ne!(mapping[&"b1"], mapping[&"d"]);
+        assert!(quotient.is_connected(&mapping[&"a"], &mapping[&"b1"]));
+        assert_eq!(quotient.edges().count(), 3);
+
+        // Same class everywhere, c and d differ by having no successors
+        // and only then do a and b differ by what they lead to
+        let (quotient, mapping) = g.minimize(|_| ());
+        assert_eq!(quotient.size(), 3);
+        assert_eq!(mapping[&"b1"], mapping[&"d"]);
+        assert_ne!(mapping[&"a"], mapping[&"b1"]);
+
+        // A cycle of identical nodes collapses to a single self-loop
+        let g = Graph::cycle_graph(6);
+        let (quotient, _) = g.minimize(|_| 0);
+        assert_eq!(quotient.size(), 1);
+        assert!(quotient.is_connected(&0, &0));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{graph::*, hash};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Landmark sketches for approximate weighted distances. Every node knows
+// its distance to and from each landmark, so a query is a scan over the
+// landmarks rather than a search of the graph
+#[derive(Debug)]
+pub struct DistanceOracle {
+    to_landmark: HashMap<u64, Sketch>,
+    from_landmark: HashMap<u64, Sketch>,
+}
+
+// Distance per landmark, None where there's no path
+type Sketch = Vec<Option<i64>>;
+
+impl DistanceOracle {
+    // Shortest route through any landmark, never less than the true
+    // distance. None if no landmark links the two
+    pub fn estimate<T: Hash>(&self, from: &T, to: &T) -> Option<i64> {
+        let (outward, inward) = self.sketches(from, to)?;
+        outward
+            .iter()
+            .zip(inward)
+            .filter_map(|(a, b)| Some((*a)? + (*b)?))
+            .min()
+    }
+
+    // Lower and upper bounds on the distance from the triangle inequality,
+    // None under the same conditions as estimate
+    pub fn bounds<T: Hash>(&self, from: &T, to: &T) -> Option<(i64, i64)> {
+        let upper = self.estimate(from, to)?;
+        let (a, b) = (hash(from), hash(to));
+        let from_a = &self.from_landmark[&a];
+        let from_b = &self.from_landmark[&b];
+        let to_a = &self.to_landmark[&a];
+        let to_b = &self.to_landmark[&b];
+        let mut lower = 0;
+        for i in 0..from_a.len() {
+            // d(L, b) <= d(L, a) + d(a, b) and d(a, L) <= d(a, b) + d(b, L)
+            if let (Some(la), Some(lb)) = (from_a[i], from_b[i]) {
+                lower = lower.max(lb - la);
+            }
+            if let (Some(al), Some(bl)) = (to_a[i], to_b[i]) {
+                lower = lower.max(al - bl);
+            }
+        }
+        Some((lower, upper))
+    }
+
+    fn sketches<T: Hash>(&self, from: &T, to: &T) -> Option<(&Sketch, &Sketch)> {
+        Some((
+            self.to_landmark.get(&hash(from))?,
+            self.from_landmark.get(&hash(to))?,
+        ))
+    }
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    // Epsilon only picks the number of landmarks, ceil(1 / epsilon) of the
+    // highest degree nodes, and is not a bound on the error. Estimates
+    // never undershoot and are exact when some shortest path passes
+    // through a landmark, so an epsilon of 1 / size() makes every node a
+    // landmark and every estimate exact. Weights are assumed non-negative
+    pub fn distance_oracle(&self, epsilon: f64) -> DistanceOracle {
+        let count = ((1.0 / epsilon).ceil() as usize).clamp(1, self.size().max(1));
+        let mut keys = self.nodes.keys().copied().collect::<Vec<_>>();
+        keys.sort_by_key(|k| {
+            let node = &self.nodes[k];
+            (Reverse(node.incoming.len() + node.edges.len()), *k)
+        });
+        keys.truncate(count);
+
+        let blank = || {
+            self.nodes
+                .keys()
+                .map(|k| (*k, vec![None; keys.len()]))
+                .collect::<HashMap<_, _>>()
+        };
+        let (mut to_landmark, mut from_landmark) = (blank(), blank());
+        for (i, landmark) in keys.iter().enumerate() {
+            for (key, (distance, _)) in self.dijkstra(*landmark, None) {
+                from_landmark.get_mut(&key).unwrap()[i] = Some(distance);
+            }
+            for (key, (distance, _)) in self.dijkstra_to(*landmark) {
+                to_landmark.get_mut(&key).unwrap()[i] = Some(distance);
+            }
+        }
+        DistanceOracle {
+            to_landmark,
+            from_landmark,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oracle() {
+        let g = Graph::grid_graph(5, 5);
+        let exact = g.distance_oracle(1.0 / 25.0);
+        for a in 0..25usize {
+            for b in 0..25usize {
+                let reachable = a % 5 <= b % 5 && a / 5 <= b / 5;
+                let distance = (b % 5 + b / 5) as i64 - (a % 5 + a / 5) as i64;
+                let expected = if reachable { Some(distance) } else { None };
+                assert_eq!(exact.estimate(&a, &b), expected);
+            }
+        }
+
+        // A single landmark still gives an upper bound
+        let rough = g.distance_oracle(1.0);
+        let (lower, upper) = rough.bounds(&0usize, &24usize).unwrap();
+        assert!(lower <= 8 && 8 <= upper);
+        assert!(rough.estimate(&0usize, &99usize).is_none());
+    }
+}
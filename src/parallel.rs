@@ -0,0 +1,150 @@
+use crate::{graph::*, hash};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Nodes laid out by position so rayon can split the work across slices
+struct Indexed<'a, T> {
+    labels: Vec<&'a T>,
+    index: HashMap<u64, usize>,
+    targets: Vec<Vec<usize>>,
+}
+
+impl<'a, T: Sync> Indexed<'a, T> {
+    fn new(graph: &'a Graph<T>) -> Self {
+        let (keys, nodes): (Vec<&u64>, Vec<&Node<T>>) = graph.nodes.iter().unzip();
+        let index = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (**k, i))
+            .collect::<HashMap<_, _>>();
+        let targets = nodes
+            .par_iter()
+            .map(|n| n.edges.keys().map(|t| index[t]).collect())
+            .collect();
+        Indexed {
+            labels: nodes.iter().map(|n| &n.label).collect(),
+            index,
+            targets,
+        }
+    }
+
+    // Edge direction ignored, self-loops dropped
+    fn undirected(&self) -> Vec<Vec<usize>> {
+        let mut undirected = self.targets.clone();
+        for (from, targets) in self.targets.iter().enumerate() {
+            for to in targets {
+                undirected[*to].push(from);
+            }
+        }
+        undirected.par_iter_mut().enumerate().for_each(|(i, n)| {
+            n.retain(|o| *o != i);
+            n.sort_unstable();
+            n.dedup();
+        });
+        undirected
+    }
+}
+
+impl<T: Hash + Eq + Sync> Graph<T> {
+    // Same components as components(), found by parallel min-label
+    // propagation with pointer jumping between rounds
+    pub fn par_components(&self) -> Vec<HashSet<&T>> {
+        let indexed = Indexed::new(self);
+        let undirected = indexed.undirected();
+        let mut labels = (0..undirected.len()).collect::<Vec<_>>();
+        loop {
+            let next = undirected
+                .par_iter()
+                .enumerate()
+                .map(|(i, n)| n.iter().map(|o| labels[*o]).fold(labels[i], usize::min))
+                .collect::<Vec<_>>();
+            let jumped = next.par_iter().map(|l| next[*l]).collect::<Vec<_>>();
+            if jumped == labels {
+                break;
+            }
+            labels = jumped;
+        }
+
+        let mut components = HashMap::<usize, HashSet<&T>>::new();
+        for (i, label) in labels.into_iter().enumerate() {
+            components
+                .entry(label)
+                .or_default()
+                .insert(indexed.labels[i]);
+        }
+        components.into_values().collect()
+    }
+
+    // Hop counts to everything reachable from start, expanding each
+    // frontier in parallel. None if start isn't in the graph
+    pub fn par_distances(&self, start: &T) -> Option<HashMap<&T, usize>> {
+        let indexed = Indexed::new(self);
+        let start = *indexed.index.get(&hash(start))?;
+        let visited = (0..indexed.labels.len())
+            .map(|i| AtomicBool::new(i == start))
+            .collect::<Vec<_>>();
+
+        let mut distances = HashMap::new();
+        let mut frontier = vec![start];
+        let mut distance = 0;
+        while !frontier.is_empty() {
+            distances.extend(frontier.iter().map(|i| (indexed.labels[*i], distance)));
+            frontier = frontier
+                .par_iter()
+                .flat_map_iter(|i| &indexed.targets[*i])
+                .filter(|t| !visited[**t].swap(true, Ordering::Relaxed))
+                .copied()
+                .collect();
+            distance += 1;
+        }
+        Some(distances)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(components: Vec<HashSet<&usize>>) -> Vec<Vec<&usize>> {
+        let mut components = components
+            .into_iter()
+            .map(|c| {
+                let mut c = c.into_iter().collect::<Vec<_>>();
+                c.sort();
+                c
+            })
+            .collect::<Vec<_>>();
+        components.sort();
+        components
+    }
+
+    #[test]
+    fn components_match_sequential() {
+        let mut g = Graph::init(0..60);
+        for i in 0..60 {
+            if i % 7 != 0 {
+                assert!(g.connect(&i, &((i * 11) % 60)));
+            }
+        }
+        assert!(g.connect(&59, &59));
+        assert_eq!(sorted(g.par_components()), sorted(g.components()));
+    }
+
+    #[test]
+    fn distances_match_sequential() {
+        let g = Graph::grid_graph(8, 6);
+        let distances = g.par_distances(&0).unwrap();
+        assert_eq!(distances.len(), 48);
+        for (label, distance) in distances {
+            assert_eq!(distance, label % 8 + label / 8);
+        }
+
+        let mut g = Graph::cycle_graph(5);
+        g.add(9);
+        assert_eq!(g.par_distances(&3).unwrap().len(), 5);
+        assert_eq!(g.par_distances(&9).unwrap().len(), 1);
+        assert!(g.par_distances(&10).is_none());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::{graph::*, group::quote, hash, iter::Edge};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Write};
+use std::hash::Hash;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crossing {
+    AToB,
+    BToA,
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    // Every edge between the two sets, flagged with its direction. Found
+    // from set a's own adjacency and reverse index, so only its edges are
+    // looked at. Missing labels are ignored and the sets shouldn't overlap
+    pub fn cut_edges<'a, 'b, I, J>(&'a self, a: I, b: J) -> Vec<(Edge<'a, T>, Crossing)>
+    where
+        I: IntoIterator<Item = &'b T>,
+        J: IntoIterator<Item = &'b T>,
+        T: 'b,
+    {
+        let b = b.into_iter().map(hash).collect::<HashSet<_>>();
+        let mut cut = Vec::new();
+        for key in a.into_iter().map(hash).collect::<HashSet<_>>() {
+            let Some(node) = self.nodes.get(&key) else {
+                continue;
+            };
+            for (to, weight) in &node.edges {
+                if b.contains(to) {
+                    let edge = Edge {
+                        from: &node.label,
+                        to: &self.nodes[to].label,
+                        weight: *weight,
+                    };
+                    cut.push((edge, Crossing::AToB));
+                }
+            }
+            for (from, weight) in &node.incoming {
+                if b.contains(from) {
+                    let edge = Edge {
+                        from: &self.nodes[from].label,
+                        to: &node.label,
+                        weight: *weight,
+                    };
+                    cut.push((edge, Crossing::BToA));
+                }
+            }
+        }
+        cut
+    }
+
+    // Merges every node sharing a group key into one node labelled with
+    // that key. Parallel edges between two groups are folded together with
+    // combine, edges inside a group are dropped
+    pub fn quotient<K, F, C>(&self, group: F, combine: C) -> Graph<K>
+    where
+        K: Hash + Eq,
+        F: Fn(&T) -> K,
+        C: Fn(i64, i64) -> i64,
+    {
+        self.quotient_keys(group, combine).0
+    }
+
+    // The quotient along with the group each original node went into
+    pub fn drill_down<K, F, C>(&self, group: F, combine: C) -> DrillDown<'_, T, K>
+    where
+        K: Hash + Eq,
+        F: Fn(&T) -> K,
+        C: Fn(i64, i64) -> i64,
+    {
+        let (quotient, groups) = self.quotient_keys(group, combine);
+        let mut members = HashMap::<u64, Vec<u64>>::new();
+        for (key, group) in &groups {
+            members.entry(*group).or_default().push(*key);
+        }
+        DrillDown {
+            graph: self,
+            quotient,
+            groups,
+            members,
+        }
+    }
+
+    fn quotient_keys<K, F, C>(&self, group: F, combine: C) -> (Graph<K>, HashMap<u64, u64>)
+    where
+        K: Hash + Eq,
+        F: Fn(&T) -> K,
+        C: Fn(i64, i64) -> i64,
+    {
+        let mut quotient = Graph::new();
+        let mut groups = HashMap::new();
+        for (key, node) in &self.nodes {
+            let label = group(&node.label);
+            groups.insert(*key, hash(&label));
+            if !quotient.contains(&label) {
+                quotient.add(label);
+            }
+        }
+        for (key, node) in &self.nodes {
+            for (target, weight) in &node.edges {
+                let (from, to) = (groups[key], groups[target]);
+                if from == to {
+                    continue;
+                }
+                let merged = match quotient.nodes[&from].edges.get(&to) {
+                    Some(existing) => combine(*existing, *weight),
+                    None => *weight,
+                };
+                quotient.set_edge(from, to, Some(merged));
+            }
+        }
+        (quotient, groups)
+    }
+}
+
+// A quotient graph that remembers which original nodes are inside each
+// group, so one group at a time can be opened back up
+pub struct DrillDown<'a, T, K> {
+    graph: &'a Graph<T>,
+    quotient: Graph<K>,
+    groups: HashMap<u64, u64>,
+    members: HashMap<u64, Vec<u64>>,
+}
+
+impl<'a, T: Hash + Eq, K: Hash + Eq> DrillDown<'a, T, K> {
+    pub fn quotient(&self) -> &Graph<K> {
+        &self.quotient
+    }
+
+    pub fn into_quotient(self) -> Graph<K> {
+        self.quotient
+    }
+
+    pub fn members(&self, group: &K) -> Option<Vec<&'a T>> {
+        let graph = self.graph;
+        let members = self.members.get(&hash(group))?;
+        Some(members.iter().map(|k| &graph.nodes[k].label).collect())
+    }
+
+    pub fn group_of(&self, label: &T) -> Option<&K> {
+        let group = self.groups.get(&hash(label))?;
+        Some(&self.quotient.nodes[group].label)
+    }
+}
+
+impl<T: Hash + Eq + Display, K: Hash + Eq + Display> DrillDown<'_, T, K> {
+    // The quotient with one group drawn as a cluster of its members, and
+    // the edges touching them drawn to and from the other groups. Group and
+    // member labels share a namespace so shouldn't clash
+    pub fn to_dot(&self, expand: Option<&K>) -> String {
+        let expanded = expand.map(hash).filter(|k| self.members.contains_key(k));
+        let name = |key: &u64| quote(&self.quotient.nodes[key].label);
+        let member = |key: &u64| quote(&self.graph.nodes[key].label);
+        let mut dot = String::from("digraph {\n");
+
+        if let Some(group) = expanded {
+            writeln!(
+                dot,
+                "    subgraph {} {{",
+                quote(format!("cluster_{}", self.quotient.nodes[&group].label))
+            )
+            .unwrap();
+            writeln!(dot, "        label = {};", name(&group)).unwrap();
+            let mut members = self.members[&group].iter().map(member).collect::<Vec<_>>();
+            members.sort_unstable();
+            for label in members {
+                writeln!(dot, "        {};", label).unwrap();
+            }
+            writeln!(dot, "    }}").unwrap();
+        }
+
+        let mut nodes = self
+            .quotient
+            .nodes
+            .keys()
+            .filter(|k| Some(**k) != expanded)
+            .map(name)
+            .collect::<Vec<_>>();
+        nodes.sort_unstable();
+        for node in nodes {
+            writeln!(dot, "    {};", node).unwrap();
+        }
+
+        let mut edges = Vec::new();
+        for (from, node) in &self.quotient.nodes {
+            for to in node.edges.keys() {
+                if Some(*from) != expanded && Some(*to) != expanded {
+                    edges.push((name(from), name(to)));
+                }
+            }
+        }
+        if let Some(group) = expanded {
+            // Inside the group edges run between members, outside they run
+            // to whichever group the other end is in
+            let end = |key: &u64| match self.groups[key] {
+                g if g == group => member(key),
+                g => name(&g),
+            };
+            for key in &self.members[&group] {
+                let node = &self.graph.nodes[key];
+                edges.extend(node.edges.keys().map(|t| (member(key), end(t))));
+                edges.extend(node.incoming.keys().map(|f| (end(f), member(key))));
+            }
+        }
+        edges.sort_unstable();
+        edges.dedup();
+        for (from, to) in edges {
+            writeln!(dot, "    {} -> {};", from, to).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<T: Hash + Eq + Clone> Graph<T> {
+    // Splits into the nodes matching the predicate and the rest, along with
+    // the edges between the two halves that had to be cut, in either
+    // direction
+    pub fn split_by<F: Fn(&T) -> bool>(&self, predicate: F) -> (Graph<T>, Graph<T>, Vec<(T, T)>) {
+        let (matched, rest): (HashSet<u64>, HashSet<u64>) = self
+            .nodes
+            .keys()
+            .partition(|k| predicate(&self.nodes[*k].label));
+        let left = &matched;
+        let cut = self
+            .nodes
+            .iter()
+            .flat_map(|(k, n)| {
+                let side = left.contains(k);
+                n.edges
+                    .keys()
+                    .filter(move |t| left.contains(*t) != side)
+                    .map(move |t| (n.label.clone(), self.nodes[t].label.clone()))
+            })
+            .collect();
+        (self.subgraph_keys(&matched), self.subgraph_keys(&rest), cut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::GraphBase;
+
+    #[test]
+    fn split_by() {
+        let labels = ["ui", "router", "api", "db"];
+        let mut g = Graph::init(labels.iter().map(|l| l.to_string()));
+        for (a, b) in [
+            ("ui", "router"),
+            ("router", "api"),
+            ("api", "db"),
+            ("db", "ui"),
+        ] {
+            assert!(g.connect(&a.to_owned(), &b.to_owned()));
+        }
+
+        let frontend = |l: &String| l == "ui" || l == "router";
+        let (front, back, mut cut) = g.split_by(frontend);
+        assert_eq!((front.size(), back.size()), (2, 2));
+        assert_eq!((front.edge_count(), back.edge_count()), (1, 1));
+        assert_eq!(back.indegree(&"api".to_owned()), Some(0));
+        cut.sort();
+        assert_eq!(
+            cut,
+            vec![
+                ("db".to_owned(), "ui".to_owned()),
+                ("router".to_owned(), "api".to_owned())
+            ]
+        );
+
+        let (all, none, cut) = g.split_by(|_| true);
+        assert_eq!((all.size(), none.size(), cut.len()), (4, 0, 0));
+    }
+
+    #[test]
+    fn cut_edges() {
+        let mut g = Graph::init(0..6usize);
+        for (a, b) in [(0, 1), (1, 3), (2, 4), (4, 0), (3, 5), (4, 5)] {
+            assert!(g.connect(&a, &b));
+        }
+
+        let mut cut = g
+            .cut_edges(&[0, 1, 2, 9], &[3, 4])
+            .into_iter()
+            .map(|(e, c)| (*e.from, *e.to, c))
+            .collect::<Vec<_>>();
+        cut.sort_by_key(|(from, to, _)| (*from, *to));
+        assert_eq!(
+            cut,
+            vec![
+                (1, 3, Crossing::AToB),
+                (2, 4, Crossing::AToB),
+                (4, 0, Crossing::BToA)
+            ]
+        );
+        assert!(g.cut_edges(&[5], &[0, 1, 2]).is_empty());
+    }
+
+    #[test]
+    fn quotient() {
+        let files = ["app/main", "app/cli", "net/http", "net/tls", "log/sink"];
+        let mut g = Graph::init(files.iter().map(|f| f.to_string()));
+        for (a, b) in [
+            ("app/main", "app/cli"),
+            ("app/main", "net/http"),
+            ("app/cli", "net/http"),
+            ("app/cli", "net/tls"),
+            ("net/http", "net/tls"),
+            ("net/http", "log/sink"),
+        ] {
+            assert!(g.connect(&a.to_owned(), &b.to_owned()));
+        }
+
+        let package = |f: &String| f.split('/').next().unwrap().to_owned();
+        let q = g.quotient(package, |a, b| a + b);
+        assert_eq!(q.size(), 3);
+        assert_eq!(q.edge_count(), 2);
+        assert_eq!(q.weight(&"app".to_owned(), &"net".to_owned()), Some(3));
+        assert_eq!(q.weight(&"net".to_owned(), &"log".to_owned()), Some(1));
+        assert!(q.self_loops().is_empty());
+
+        let q = g.quotient(package, i64::max);
+        assert_eq!(q.weight(&"app".to_owned(), &"net".to_owned()), Some(1));
+    }
+
+    #[test]
+    fn drill_down() {
+        let files = ["app/main", "app/cli", "net/http", "net/tls", "log/sink"];
+        let mut g = Graph::init(files.iter().map(|f| f.to_string()));
+        for (a, b) in [
+            ("app/main", "app/cli"),
+            ("app/main", "net/http"),
+            ("app/cli", "net/tls"),
+            ("net/http", "net/tls"),
+            ("net/http", "log/sink"),
+        ] {
+            assert!(g.connect(&a.to_owned(), &b.to_owned()));
+        }
+        let package = |f: &String| f.split('/').next().unwrap().to_owned();
+        let view = g.drill_down(package, |a, b| a + b);
+
+        let mut members = view.members(&"net".to_owned()).unwrap();
+        members.sort();
+        assert_eq!(members, vec!["net/http", "net/tls"]);
+        assert_eq!(view.group_of(&"log/sink".to_owned()).unwrap(), "log");
+        assert!(view.members(&"missing".to_owned()).is_none());
+        assert_eq!(view.quotient().edge_count(), 2);
+
+        let expected = r#"digraph {
+    subgraph "cluster_net" {
+        label = "net";
+        "net/http";
+        "net/tls";
+    }
+    "app";
+    "log";
+    "app" -> "net/http";
+    "app" -> "net/tls";
+    "net/http" -> "log";
+    "net/http" -> "net/tls";
+}
+"#;
+        assert_eq!(view.to_dot(Some(&"net".to_owned())), expected);
+
+        let collapsed = view.to_dot(None);
+        assert!(collapsed.contains("\"app\" -> \"net\";"));
+        assert_eq!(collapsed.matches("->").count(), 2);
+        assert_eq!(view.into_quotient().size(), 3);
+    }
+}
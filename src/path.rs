@@ -0,0 +1,1133 @@
+use crate::budget::{Budget, Interrupt};
+use crate::{graph::*, hash, measure::Measure};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+
+type WeightedPath<'a, T> = Option<(i64, Vec<&'a T>)>;
+
+// Heap entry ordered by cost then keys. A cost that isn't comparable even
+// with itself, i.e. NaN, orders after every other cost, so Ord stays total
+// and such paths are the last to be settled
+struct Candidate<W>(W, u64, u64);
+
+impl<W: PartialOrd> PartialEq for Candidate<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<W: PartialOrd> Eq for Candidate<W> {}
+
+impl<W: PartialOrd> PartialOrd for Candidate<W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<W: PartialOrd> Ord for Candidate<W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let nan = |w: &W| w.partial_cmp(w).is_none();
+        let cost = match self.0.partial_cmp(&other.0) {
+            Some(cost) => cost,
+            None => nan(&self.0).cmp(&nan(&other.0)),
+        };
+        cost.then((self.1, self.2).cmp(&(other.1, other.2)))
+    }
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    // Dijkstra over edge weights, which callers check aren't negative with
+    // has_negative_weight first. Maps each settled node to its distance
+    // and predecessor, stopping early once the target is settled
+    pub(crate) fn dijkstra(&self, start: u64, target: Option<u64>) -> HashMap<u64, (i64, u64)> {
+        self.dijkstra_by(start, target, |_, _, weight| weight)
+    }
+
+    // Same again with each edge costed by the given function
+    fn dijkstra_by<W, F>(&self, start: u64, target: Option<u64>, cost: F) -> HashMap<u64, (W, u64)>
+    where
+        W: Measure,
+        F: Fn(&T, &T, i64) -> W,
+    {
+        self.dijkstra_from(&[start], |key| Some(key) == target, cost)
+    }
+
+    // Dijkstra along edges in reverse, mapping each node that can reach
+    // target to its distance there and its successor on the way
+    pub(crate) fn dijkstra_to(&self, target: u64) -> HashMap<u64, (i64, u64)> {
+        self.search(&[target], |_| false, |_, _, weight| weight, true)
+    }
+
+    // Searches from all the starts at once, as if from a single node with a
+    // free edge to each. Starts are their own predecessors, and the search
+    // stops once a node passing stop is settled
+    fn dijkstra_from<W, S, F>(&self, starts: &[u64], stop: S, cost: F) -> HashMap<u64, (W, u64)>
+    where
+        W: Measure,
+        S: Fn(u64) -> bool,
+        F: Fn(&T, &T, i64) -> W,
+    {
+        self.search(starts, stop, cost, false)
+    }
+
+    // Walks incoming rather than outgoing edges when reverse is set
+    fn search<W, S, F>(
+        &self,
+        starts: &[u64],
+        stop: S,
+        cost: F,
+        reverse: bool,
+    ) -> HashMap<u64, (W, u64)>
+    where
+        W: Measure,
+        S: Fn(u64) -> bool,
+        F: Fn(&T, &T, i64) -> W,
+    {
+        let mut settled = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        heap.extend(starts.iter().map(|s| Reverse(Candidate(W::zero(), *s, *s))));
+        while let Some(Reverse(Candidate(distance, key, previous))) = heap.pop() {
+            if settled.contains_key(&key) {
+                continue;
+            }
+            settled.insert(key, (distance, previous));
+            if stop(key) {
+                break;
+            }
+            let node = &self.nodes[&key];
+            let edges = if reverse { &node.incoming } else { &node.edges };
+            for (next, weight) in edges {
+                if !settled.contains_key(next) {
+                    let step = cost(&node.label, &self.nodes[next].label, *weight);
+                    heap.push(Reverse(Candidate(distance + step, *next, key)));
+                }
+            }
+        }
+        settled
+    }
+
+    // Dijkstra settles nodes too early past a negative edge, so anything
+    // searching by weight gives up instead of answering wrongly
+    pub(crate) fn has_negative_weight(&self) -> bool {
+        self.nodes
+            .values()
+            .any(|n| n.edges.iter().any(|(_, w)| *w < 0))
+    }
+
+    pub(crate) fn weighted_distances(&self, start: u64) -> HashMap<u64, i64> {
+        self.dijkstra(start, None)
+            .into_iter()
+            .map(|(k, (d, _))| (k, d))
+            .collect()
+    }
+
+    // Cheapest distance to everything reachable from start, None if start
+    // isn't in the graph or any weight is negative. Breadth first when
+    // every weight is 1
+    pub fn distances(&self, start: &T) -> Option<HashMap<&T, i64>> {
+        self.get(start)?;
+        if self.has_negative_weight() {
+            return None;
+        }
+        let start = hash(start);
+        let unweighted = self
+            .nodes
+            .values()
+            .all(|n| n.edges.iter().all(|(_, w)| *w == 1));
+        let distances = if unweighted {
+            self.hop_distances(start)
+                .into_iter()
+                .map(|(k, d)| (k, d as i64))
+                .collect()
+        } else {
+            self.weighted_distances(start)
+        };
+        Some(
+            distances
+                .into_iter()
+                .map(|(k, d)| (&self.nodes[&k].label, d))
+                .collect(),
+        )
+    }
+
+    // The edges the cheapest paths from start take, with their weights, as
+    // a graph over everything reachable from it. None under the same
+    // conditions as distances
+    pub fn shortest_path_tree(&self, start: &T) -> Option<Graph<T>>
+    where
+        T: Clone,
+    {
+        if self.get(start).is_none() || self.has_negative_weight() {
+            return None;
+        }
+        let settled = self.dijkstra(hash(start), None);
+        let mut tree = Graph::with_capacity(settled.len());
+        for key in settled.keys() {
+            tree.add(self.nodes[key].label.clone());
+        }
+        for (key, (_, previous)) in &settled {
+            if key != previous {
+                let weight = self.nodes[previous].edges.get(key).copied();
+                tree.set_edge(*previous, *key, weight);
+            }
+        }
+        Some(tree)
+    }
+
+    // Cheapest path by edge weight along with its total cost, None if
+    // there isn't one or any weight is negative
+    pub fn shortest_path(&self, from: &T, to: &T) -> Option<(i64, Vec<&T>)> {
+        if self.get(from).is_none() || self.has_negative_weight() {
+            return None;
+        }
+        let (start, end) = (hash(from), hash(to));
+        let settled = self.dijkstra(start, Some(end));
+        self.trace(&settled, end)
+    }
+
+    pub fn shortest_path_owned(&self, from: &T, to: &T) -> Option<(i64, Vec<T>)>
+    where
+        T: Clone,
+    {
+        let (distance, path) = self.shortest_path(from, to)?;
+        Some((distance, path.into_iter().cloned().collect()))
+    }
+
+    // Cheapest path where each edge costs cost(from, to, weight), e.g. to
+    // search over floating point or Duration costs. Costs must not be
+    // negative
+    pub fn shortest_path_by<W, F>(&self, from: &T, to: &T, cost: F) -> Option<(W, Vec<&T>)>
+    where
+        W: Measure,
+        F: Fn(&T, &T, i64) -> W,
+    {
+        self.get(from)?;
+        let (start, end) = (hash(from), hash(to));
+        let settled = self.dijkstra_by(start, Some(end), cost);
+        self.trace(&settled, end)
+    }
+
+    // Cheapest path from any of the sources to any of the targets in one
+    // search. Sources and targets not in the graph are ignored, and like
+    // shortest_path there's no answer if any weight is negative
+    pub fn shortest_path_multi<'a, I, J>(&self, sources: I, targets: J) -> Option<(i64, Vec<&T>)>
+    where
+        I: IntoIterator<Item = &'a T>,
+        J: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        let starts = sources
+            .into_iter()
+            .map(hash)
+            .filter(|k| self.nodes.contains_key(k))
+            .collect::<Vec<_>>();
+        let ends = targets.into_iter().map(hash).collect::<HashSet<_>>();
+        if self.has_negative_weight() {
+            return None;
+        }
+        let settled = self.dijkstra_from(&starts, |key| ends.contains(&key), |_, _, w| w);
+        let end = settled.keys().find(|k| ends.contains(k))?;
+        self.trace(&settled, *end)
+    }
+
+    // Cheapest path whose total resource, e.g. latency, stays within limit,
+    // with both costed per edge like shortest_path_by. Neither may be
+    // negative. Every partial path is kept unless one already settled at
+    // the same node is no dearer and uses no more, so unlike shortest_path
+    // this can grow with the number of paths rather than nodes
+    pub fn shortest_path_constrained<W, R, F, G>(
+        &self,
+        from: &T,
+        to: &T,
+        cost: F,
+        resource: G,
+        limit: R,
+    ) -> Option<(W, R, Vec<&T>)>
+    where
+        W: Measure,
+        R: Measure,
+        F: Fn(&T, &T, i64) -> W,
+        G: Fn(&T, &T, i64) -> R,
+    {
+        self.get(from)?;
+        let end = hash(to);
+        // Partial paths as (cost, resource, node, parent), indexed from the heap
+        let mut labels = vec![(W::zero(), R::zero(), hash(from), 0)];
+        let mut settled: HashMap<u64, Vec<(W, R)>> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        heap.push(Reverse(Candidate(W::zero(), hash(from), 0)));
+        let dominated = |settled: &HashMap<u64, Vec<(W, R)>>, key, c, r| {
+            settled
+                .get(&key)
+                .is_some_and(|found| found.iter().any(|(fc, fr)| *fc <= c && *fr <= r))
+        };
+        while let Some(Reverse(Candidate(distance, key, index))) = heap.pop() {
+            let index = index as usize;
+            let used = labels[index].1;
+            if dominated(&settled, key, distance, used) {
+                continue;
+            }
+            settled.entry(key).or_default().push((distance, used));
+            if key == end {
+                let mut path = Vec::new();
+                let mut current = index;
+                loop {
+                    let (_, _, key, parent) = labels[current];
+                    path.push(&self.nodes[&key].label);
+                    if current == 0 {
+                        break;
+                    }
+                    current = parent;
+                }
+                path.reverse();
+                return Some((distance, used, path));
+            }
+            let node = &self.nodes[&key];
+            for (next, weight) in &node.edges {
+                let label = &self.nodes[next].label;
+                let r = used + resource(&node.label, label, *weight);
+                if r > limit {
+                    continue;
+                }
+                let c = distance + cost(&node.label, label, *weight);
+                if !dominated(&settled, *next, c, r) {
+                    labels.push((c, r, *next, index));
+                    heap.push(Reverse(Candidate(c, *next, labels.len() as u64 - 1)));
+                }
+            }
+        }
+        None
+    }
+
+    // Path whose narrowest edge is as wide as possible, along with that
+    // width. Dijkstra with the widest candidate settled first and a path's
+    // width being its smallest weight. A path from a node to itself has no
+    // edges to narrow it, so its width is i64::MAX
+    pub fn widest_path(&self, from: &T, to: &T) -> Option<(i64, Vec<&T>)> {
+        self.get(from)?;
+        let (start, end) = (hash(from), hash(to));
+        let mut settled = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate(i64::MAX, start, start));
+        while let Some(Candidate(width, key, previous)) = heap.pop() {
+            if settled.contains_key(&key) {
+                continue;
+            }
+            settled.insert(key, (width, previous));
+            if key == end {
+                break;
+            }
+            for (next, weight) in &self.nodes[&key].edges {
+                if !settled.contains_key(next) {
+                    heap.push(Candidate(width.min(*weight), *next, key));
+                }
+            }
+        }
+        self.trace(&settled, end)
+    }
+
+    // Follows predecessors back from end to whichever start it came from
+    fn trace<W: Copy>(&self, settled: &HashMap<u64, (W, u64)>, end: u64) -> Option<(W, Vec<&T>)> {
+        let (cost, _) = settled.get(&end)?;
+        let mut path = vec![&self.nodes[&end].label];
+        let mut current = end;
+        while settled[&current].1 != current {
+            current = settled[&current].1;
+            path.push(&self.nodes[&current].label);
+        }
+        path.reverse();
+        Some((*cost, path))
+    }
+
+    // Answers in the order asked, walking once per distinct source rather
+    // than once per pair
+    pub fn batch_has_path<'a, I>(&self, pairs: I) -> Vec<bool>
+    where
+        I: IntoIterator<Item = (&'a T, &'a T)>,
+        T: 'a,
+    {
+        self.batch(
+            pairs,
+            &Budget::unlimited(),
+            &mut |_, _| (),
+            |start| self.hop_distances(start),
+            |reached, _, end| reached.contains_key(&end),
+        )
+        .unwrap()
+    }
+
+    // Same as calling shortest_path on each pair, with one full Dijkstra
+    // per distinct source
+    pub fn batch_shortest_paths<'a, I>(&self, pairs: I) -> Vec<Option<(i64, Vec<&T>)>>
+    where
+        I: IntoIterator<Item = (&'a T, &'a T)>,
+        T: 'a,
+    {
+        self.batch_shortest_paths_within(pairs, &Budget::unlimited())
+            .unwrap()
+    }
+
+    // The budget is checked between sources, reading the clock each time,
+    // so it can run over by up to one Dijkstra
+    pub fn batch_shortest_paths_within<'a, I>(
+        &self,
+        pairs: I,
+        budget: &Budget,
+    ) -> Result<Vec<WeightedPath<'_, T>>, Interrupt>
+    where
+        I: IntoIterator<Item = (&'a T, &'a T)>,
+        T: 'a,
+    {
+        self.batch_dijkstra(pairs, budget, &mut |_, _| ())
+    }
+
+    // Reports the number of distinct sources searched out of the total
+    // after each one
+    pub fn batch_shortest_paths_with_progress<'a, I, P>(
+        &self,
+        pairs: I,
+        mut progress: P,
+    ) -> Vec<WeightedPath<'_, T>>
+    where
+        I: IntoIterator<Item = (&'a T, &'a T)>,
+        T: 'a,
+        P: FnMut(usize, usize),
+    {
+        self.batch_dijkstra(pairs, &Budget::unlimited(), &mut progress)
+            .unwrap()
+    }
+
+    // Every path is None if any weight is negative, without searching
+    fn batch_dijkstra<'a, I>(
+        &self,
+        pairs: I,
+        budget: &Budget,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<Vec<WeightedPath<'_, T>>, Interrupt>
+    where
+        I: IntoIterator<Item = (&'a T, &'a T)>,
+        T: 'a,
+    {
+        let negative = self.has_negative_weight();
+        self.batch(
+            pairs,
+            budget,
+            progress,
+            |start| {
+                if negative {
+                    HashMap::new()
+                } else {
+                    self.dijkstra(start, None)
+                }
+            },
+            |settled, _, end| self.trace(settled, end),
+        )
+    }
+
+    // Groups pairs by source so each source is searched at most once,
+    // pairs from a missing source get the answer for an empty search
+    fn batch<'a, I, S, R, F, A>(
+        &self,
+        pairs: I,
+        budget: &Budget,
+        progress: &mut dyn FnMut(usize, usize),
+        search: F,
+        answer: A,
+    ) -> Result<Vec<R>, Interrupt>
+    where
+        I: IntoIterator<Item = (&'a T, &'a T)>,
+        T: 'a,
+        S: Default,
+        F: Fn(u64) -> S,
+        A: Fn(&S, u64, u64) -> R,
+    {
+        let pairs = pairs
+            .into_iter()
+            .map(|(from, to)| (hash(from), hash(to)))
+            .collect::<Vec<_>>();
+        let mut by_source = HashMap::<u64, Vec<usize>>::new();
+        for (i, (from, _)) in pairs.iter().enumerate() {
+            by_source.entry(*from).or_default().push(i);
+        }
+
+        let mut answers = (0..pairs.len()).map(|_| None).collect::<Vec<_>>();
+        let total = by_source.len();
+        for (done, (start, indices)) in by_source.into_iter().enumerate() {
+            budget.check_now()?;
+            let searched = if self.nodes.contains_key(&start) {
+                search(start)
+            } else {
+                S::default()
+            };
+            for i in indices {
+                answers[i] = Some(answer(&searched, start, pairs[i].1));
+            }
+            progress(done + 1, total);
+        }
+        Ok(answers.into_iter().map(Option::unwrap).collect())
+    }
+
+    // None if the node is missing or can't reach every other node
+    pub fn eccentricity(&self, label: &T) -> Option<usize> {
+        self.get(label)?;
+        let distances = self.hop_distances(hash(label));
+        Self::max_if_complete(distances, self.size())
+    }
+
+    // None if the node is missing, can't reach every other node or any
+    // weight is negative
+    pub fn weighted_eccentricity(&self, label: &T) -> Option<i64> {
+        if self.get(label).is_none() || self.has_negative_weight() {
+            return None;
+        }
+        let distances = self.weighted_distances(hash(label));
+        Self::max_if_complete(distances, self.size())
+    }
+
+    pub fn diameter(&self) -> Option<usize> {
+        self.diameter_within(&Budget::unlimited()).unwrap()
+    }
+
+    pub fn radius(&self) -> Option<usize> {
+        self.radius_within(&Budget::unlimited()).unwrap()
+    }
+
+    pub fn weighted_diameter(&self) -> Option<i64> {
+        self.weighted_diameter_within(&Budget::unlimited()).unwrap()
+    }
+
+    pub fn weighted_radius(&self) -> Option<i64> {
+        self.weighted_radius_within(&Budget::unlimited()).unwrap()
+    }
+
+    // These search from every node, checking the budget, clock included,
+    // before each search
+    pub fn diameter_within(&self, budget: &Budget) -> Result<Option<usize>, Interrupt> {
+        let eccentricities = self.eccentricities(budget, |k| self.hop_distances(k))?;
+        Ok(eccentricities.and_then(|e| e.into_iter().max()))
+    }
+
+    pub fn radius_within(&self, budget: &Budget) -> Result<Option<usize>, Interrupt> {
+        let eccentricities = self.eccentricities(budget, |k| self.hop_distances(k))?;
+        Ok(eccentricities.and_then(|e| e.into_iter().min()))
+    }
+
+    pub fn weighted_diameter_within(&self, budget: &Budget) -> Result<Option<i64>, Interrupt> {
+        let eccentricities = self.weighted_eccentricities(budget)?;
+        Ok(eccentricities.and_then(|e| e.into_iter().max()))
+    }
+
+    pub fn weighted_radius_within(&self, budget: &Budget) -> Result<Option<i64>, Interrupt> {
+        let eccentricities = self.weighted_eccentricities(budget)?;
+        Ok(eccentricities.and_then(|e| e.into_iter().min()))
+    }
+
+    // Stops at the first node that can't reach every other one, as there's
+    // no answer once one is found
+    fn eccentricities<D, F>(
+        &self,
+        budget: &Budget,
+        distances: F,
+    ) -> Result<Option<Vec<D>>, Interrupt>
+    where
+        D: Ord + Copy,
+        F: Fn(u64) -> HashMap<u64, D>,
+    {
+        let mut eccentricities = Vec::with_capacity(self.size());
+        for key in self.nodes.keys() {
+            budget.check_now()?;
+            match Self::max_if_complete(distances(*key), self.size()) {
+                Some(eccentricity) => eccentricities.push(eccentricity),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(eccentricities))
+    }
+
+    fn weighted_eccentricities(&self, budget: &Budget) -> Result<Option<Vec<i64>>, Interrupt> {
+        if self.has_negative_weight() {
+            return Ok(None);
+        }
+        self.eccentricities(budget, |k| self.weighted_distances(k))
+    }
+
+    // Number of distinct paths in a DAG, None if either node is missing,
+    // the graph has a cycle or the count overflows
+    pub fn count_paths(&self, from: &T, to: &T) -> Option<u128> {
+        self.count_paths_with(from, to, 0, 1, |a, b| a.checked_add(*b))
+    }
+
+    #[cfg(feature = "num-bigint")]
+    pub fn count_paths_big(&self, from: &T, to: &T) -> Option<num_bigint::BigUint> {
+        use num_bigint::BigUint;
+        self.count_paths_with(from, to, BigUint::from(0u8), BigUint::from(1u8), |a, b| {
+            Some(a + b)
+        })
+    }
+
+    fn count_paths_with<N, F>(&self, from: &T, to: &T, zero: N, one: N, add: F) -> Option<N>
+    where
+        N: Clone,
+        F: Fn(&N, &N) -> Option<N>,
+    {
+        self.get(from)?;
+        self.get(to)?;
+        if !self.is_dag() {
+            return None;
+        }
+
+        // A None count has overflowed and stays that way downstream
+        let mut counts = HashMap::new();
+        counts.insert(hash(from), Some(one));
+        for label in self.ordering() {
+            let count = match counts.get(&hash(label)) {
+                Some(count) => count.clone(),
+                None => continue,
+            };
+            for target in self.get(label).unwrap().edges.keys() {
+                let total = match (counts.get(target), &count) {
+                    (Some(None), _) | (_, None) => None,
+                    (Some(Some(total)), Some(count)) => add(total, count),
+                    (None, Some(count)) => add(&zero, count),
+                };
+                counts.insert(*target, total);
+            }
+        }
+        counts.remove(&hash(to)).unwrap_or(Some(zero))
+    }
+
+    // Hierholzer's algorithm, the path uses every edge exactly once
+    pub fn eulerian_path(&self) -> Option<Vec<(&T, &T)>> {
+        let mut remaining = self
+            .nodes
+            .iter()
+            .map(|(k, n)| (*k, n.edges.keys().copied().collect::<Vec<_>>()))
+            .collect::<HashMap<_, _>>();
+        let indegrees = self.indegrees();
+
+        let mut start = None;
+        let mut ends = 0;
+        for (key, targets) in &remaining {
+            match targets.len() as i64 - indegrees[key] as i64 {
+                0 => {}
+                1 if start.is_none() => start = Some(*key),
+                -1 if ends == 0 => ends += 1,
+                _ => return None,
+            }
+        }
+        let start = start.or_else(|| {
+            remaining
+                .iter()
+                .find(|(_, t)| !t.is_empty())
+                .map(|(k, _)| *k)
+        });
+
+        let walk = Self::hierholzer(start, |key| remaining.get_mut(&key).unwrap().pop());
+        self.euler_edges(walk, self.edge_count())
+    }
+
+    // Edge direction is ignored and a <-> b counts as a single edge. Self
+    // loops don't change any degree's parity, so they're left out of the
+    // search and spliced in where the walk first reaches their node
+    pub fn undirected_eulerian_path(&self) -> Option<Vec<(&T, &T)>> {
+        let mut remaining = self.undirected();
+        let odd = remaining
+            .iter()
+            .filter(|(_, n)| n.len() % 2 == 1)
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+        if odd.len() > 2 {
+            return None;
+        }
+        let mut loops = self
+            .nodes
+            .iter()
+            .filter(|(k, n)| n.edges.contains_key(k))
+            .map(|(k, _)| *k)
+            .collect::<HashSet<_>>();
+        let total = remaining.values().map(|n| n.len()).sum::<usize>() / 2 + loops.len();
+        let start = odd.first().copied().or_else(|| {
+            remaining
+                .iter()
+                .find(|(_, n)| !n.is_empty())
+                .map(|(k, _)| *k)
+                .or_else(|| loops.iter().next().copied())
+        });
+
+        let walk = Self::hierholzer(start, |key| {
+            let next = *remaining[&key].iter().next()?;
+            remaining.get_mut(&key).unwrap().remove(&next);
+            remaining.get_mut(&next).unwrap().remove(&key);
+            Some(next)
+        });
+        // Loops at nodes the walk never reaches leave it short
+        let mut spliced = Vec::with_capacity(walk.len() + loops.len());
+        for key in walk {
+            spliced.push(key);
+            if loops.remove(&key) {
+                spliced.push(key);
+            }
+        }
+        self.euler_edges(spliced, total)
+    }
+
+    fn hierholzer<F: FnMut(u64) -> Option<u64>>(start: Option<u64>, mut next: F) -> Vec<u64> {
+        let mut walk = Vec::new();
+        let mut stack = start.into_iter().collect::<Vec<_>>();
+        while let Some(key) = stack.last().copied() {
+            match next(key) {
+                Some(target) => stack.push(target),
+                None => walk.push(stack.pop().unwrap()),
+            }
+        }
+        walk.reverse();
+        walk
+    }
+
+    fn euler_edges(&self, walk: Vec<u64>, edges: usize) -> Option<Vec<(&T, &T)>> {
+        if walk.len() != edges + 1 && edges > 0 {
+            return None; // Edges left over in another component
+        }
+        let labels = walk
+            .iter()
+            .map(|k| &self.nodes[k].label)
+            .collect::<Vec<_>>();
+        Some(labels.windows(2).map(|w| (w[0], w[1])).collect())
+    }
+
+    fn max_if_complete<D: Ord + Copy>(distances: HashMap<u64, D>, size: usize) -> Option<D> {
+        if distances.len() == size {
+            distances.values().copied().max()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn shortest_path_by() {
+        // a -> b -> d is two hops but a -> c -> d is cheaper in hours
+        let mut g = Graph::init('a'..='d');
+        for (a, b) in [('a', 'b'), ('b', 'd'), ('a', 'c'), ('c', 'd')] {
+            assert!(g.connect(&a, &b));
+        }
+        let hours = |a: &char, b: &char| match (a, b) {
+            ('a', 'b') | ('b', 'd') => 1.5,
+            _ => 0.25,
+        };
+
+        let (cost, path) = g
+            .shortest_path_by(&'a', &'d', |a, b, _| hours(a, b))
+            .unwrap();
+        assert_eq!(path, vec![&'a', &'c', &'d']);
+        assert_eq!(cost, 0.5);
+
+        let (cost, path) = g
+            .shortest_path_by(&'a', &'d', |a, b, _| {
+                Duration::from_secs_f64(hours(a, b) * 3600.0) * if *b == 'c' { 20 } else { 1 }
+            })
+            .unwrap();
+        assert_eq!(path, vec![&'a', &'b', &'d']);
+        assert_eq!(cost, Duration::from_secs(3 * 3600));
+
+        assert_eq!(
+            g.shortest_path_by(&'a', &'d', |_, _, w| w as u32),
+            g.shortest_path(&'a', &'d').map(|(c, p)| (c as u32, p))
+        );
+        assert!(g.shortest_path_by(&'d', &'a', |_, _, _| 1.0).is_none());
+
+        // A NaN cost never beats a real one, wherever it turns up
+        let (cost, path) = g
+            .shortest_path_by(&'a', &'d', |a, b, _| {
+                if (*a, *b) == ('a', 'c') {
+                    f64::NAN
+                } else {
+                    hours(a, b)
+                }
+            })
+            .unwrap();
+        assert_eq!(path, vec![&'a', &'b', &'d']);
+        assert_eq!(cost, 3.0);
+        let mut candidates = [1.0, f64::NAN, 0.0, f64::NAN, f64::INFINITY]
+            .iter()
+            .enumerate()
+            .map(|(i, w)| Candidate(*w, i as u64, 0))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        let order = candidates.iter().map(|c| c.1).collect::<Vec<_>>();
+        assert_eq!(order, vec![2, 0, 4, 1, 3]);
+    }
+
+    #[test]
+    fn distances() {
+        let mut g = Graph::init('a'..='e');
+        for (a, b) in [('a', 'b'), ('b', 'c'), ('a', 'c'), ('c', 'd')] {
+            assert!(g.connect(&a, &b));
+        }
+        let hops = g.distances(&'a').unwrap();
+        assert_eq!(hops.len(), 4);
+        assert_eq!((hops[&'a'], hops[&'c'], hops[&'d']), (0, 1, 2));
+
+        assert!(g.connect_weighted(&'a', &'c', 5));
+        let weighted = g.distances(&'a').unwrap();
+        assert_eq!((weighted[&'b'], weighted[&'c'], weighted[&'d']), (1, 2, 3));
+        assert!(!weighted.contains_key(&'e'));
+        assert!(g.distances(&'z').is_none());
+
+        let tree = g.shortest_path_tree(&'a').unwrap();
+        assert_eq!(tree.size(), 4);
+        assert_eq!(tree.edge_count(), 3);
+        assert!(tree.contains_edge(&'b', &'c') && !tree.contains_edge(&'a', &'c'));
+        assert!(tree.is_tree());
+        assert_eq!(g.shortest_path_tree(&'e').unwrap().edge_count(), 0);
+    }
+
+    #[test]
+    fn negative_weights() {
+        // Dijkstra would settle b at 2 before finding a -> c -> b at 1
+        let mut g = Graph::init('a'..='c');
+        for (a, b, w) in [('a', 'b', 2), ('a', 'c', 5), ('c', 'b', -4), ('b', 'a', 1)] {
+            assert!(g.connect_weighted(&a, &b, w));
+        }
+        assert!(g.distances(&'a').is_none());
+        assert!(g.shortest_path_tree(&'a').is_none());
+        assert!(g.shortest_path(&'a', &'b').is_none());
+        assert!(g.shortest_path_multi(&['a'], &['b']).is_none());
+        assert_eq!(g.batch_shortest_paths([(&'a', &'b')]), vec![None]);
+        assert!(g.weighted_eccentricity(&'a').is_none());
+        assert!(g.weighted_diameter().is_none() && g.weighted_radius().is_none());
+
+        assert!(g.connect_weighted(&'c', &'b', 4));
+        assert_eq!(g.shortest_path(&'a', &'b'), Some((2, vec![&'a', &'b'])));
+        assert_eq!(g.weighted_eccentricity(&'a'), Some(5));
+    }
+
+    #[test]
+    fn shortest_path_multi() {
+        // Rooms a and b, with exits x and y
+        let mut g = Graph::init(vec!['a', 'b', 'c', 'd', 'x', 'y']);
+        for (a, b, w) in [
+            ('a', 'c', 4),
+            ('b', 'c', 1),
+            ('c', 'x', 5),
+            ('c', 'd', 1),
+            ('d', 'y', 2),
+            ('a', 'y', 9),
+        ] {
+            assert!(g.connect_weighted(&a, &b, w));
+        }
+        assert_eq!(
+            g.shortest_path_multi(&['a', 'b'], &['x', 'y']),
+            Some((4, vec![&'b', &'c', &'d', &'y']))
+        );
+        assert_eq!(
+            g.shortest_path_multi(&['a'], &['x', 'y']),
+            Some((7, vec![&'a', &'c', &'d', &'y']))
+        );
+        assert_eq!(
+            g.shortest_path_multi(&['a', 'z'], &['x']),
+            Some((9, vec![&'a', &'c', &'x']))
+        );
+        assert_eq!(
+            g.shortest_path_multi(&['c', 'd'], &['d']),
+            Some((0, vec![&'d']))
+        );
+        assert_eq!(g.shortest_path_multi(&['x'], &['a', 'y']), None);
+        assert_eq!(g.shortest_path_multi(&[], &['a']), None);
+    }
+
+    #[test]
+    fn shortest_path_constrained() {
+        // Weights are prices, latency is looked up. The cheap route via b
+        // is slow, c is dear but fast and d is in between
+        let mut g = Graph::init('a'..='e');
+        for (a, b, w) in [
+            ('a', 'b', 1),
+            ('b', 'e', 1),
+            ('a', 'c', 5),
+            ('c', 'e', 5),
+            ('a', 'd', 2),
+            ('d', 'e', 3),
+            ('b', 'a', 0),
+        ] {
+            assert!(g.connect_weighted(&a, &b, w));
+        }
+        let latency = |a: &char, b: &char, _| match (a, b) {
+            ('a', 'b') | ('b', 'e') => 50,
+            ('a', 'd') | ('d', 'e') => 20,
+            _ => 5,
+        };
+        let route = |limit| g.shortest_path_constrained(&'a', &'e', |_, _, w| w, latency, limit);
+
+        assert_eq!(route(100), Some((2, 100, vec![&'a', &'b', &'e'])));
+        assert_eq!(route(99), Some((5, 40, vec![&'a', &'d', &'e'])));
+        assert_eq!(route(39), Some((10, 10, vec![&'a', &'c', &'e'])));
+        assert_eq!(route(9), None);
+        assert_eq!(
+            g.shortest_path_constrained(&'a', &'a', |_, _, w| w, latency, 0),
+            Some((0, 0, vec![&'a']))
+        );
+    }
+
+    #[test]
+    fn widest_path() {
+        // a -> b -> d is shorter but a -> c -> d has the better bottleneck
+        let mut g = Graph::init('a'..='e');
+        for (a, b, w) in [
+            ('a', 'b', 10),
+            ('b', 'd', 2),
+            ('a', 'c', 5),
+            ('c', 'd', 4),
+            ('d', 'e', 7),
+        ] {
+            assert!(g.connect_weighted(&a, &b, w));
+        }
+        assert_eq!(
+            g.widest_path(&'a', &'e'),
+            Some((4, vec![&'a', &'c', &'d', &'e']))
+        );
+        assert_eq!(g.widest_path(&'a', &'b'), Some((10, vec![&'a', &'b'])));
+        assert_eq!(g.widest_path(&'a', &'a'), Some((i64::MAX, vec![&'a'])));
+        assert_eq!(g.widest_path(&'e', &'a'), None);
+        assert_eq!(g.widest_path(&'z', &'a'), None);
+    }
+
+    #[test]
+    fn batch_queries() {
+        let mut g = Graph::init('a'..='e');
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'a', &'c'));
+        assert!(g.connect(&'d', &'e'));
+
+        let pairs = vec![
+            (&'a', &'c'),
+            (&'d', &'e'),
+            (&'a', &'a'),
+            (&'c', &'a'),
+            (&'a', &'e'),
+            (&'z', &'a'),
+            (&'a', &'z'),
+        ];
+        assert_eq!(
+            g.batch_has_path(pairs.iter().copied()),
+            vec![true, true, true, false, false, false, false]
+        );
+
+        let paths = g.batch_shortest_paths(pairs.iter().copied());
+        assert_eq!(paths.len(), pairs.len());
+
+        let mut reports = Vec::new();
+        let reported = g
+            .batch_shortest_paths_with_progress(pairs.iter().copied(), |done, total| {
+                reports.push((done, total))
+            });
+        assert_eq!(reported, paths);
+        assert_eq!(reports, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+
+        let token = crate::budget::CancelToken::new();
+        let budget = Budget::unlimited().and_token(token.clone());
+        let within = g.batch_shortest_paths_within(pairs.iter().copied(), &budget);
+        assert_eq!(within.as_ref(), Ok(&paths));
+        token.cancel();
+        let within = g.batch_shortest_paths_within(pairs.iter().copied(), &budget);
+        assert_eq!(within, Err(Interrupt::Cancelled));
+
+        // The deadline passes once the first source is done, whichever it
+        // is, since z is missing and never searched
+        let deadline = Instant::now() + Duration::from_millis(250);
+        let budget = Budget::with_deadline(deadline);
+        let mut searched = 0;
+        let late = g.batch(
+            pairs.iter().copied(),
+            &budget,
+            &mut |done, _| {
+                searched = done;
+                std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            },
+            |start| g.dijkstra(start, None),
+            |settled, _, end| g.trace(settled, end),
+        );
+        assert_eq!(late, Err(Interrupt::Timeout));
+        assert_eq!(searched, 1);
+
+        for ((from, to), path) in pairs.into_iter().zip(paths) {
+            assert_eq!(path, g.shortest_path(from, to));
+        }
+    }
+
+    #[test]
+    fn eccentricity() {
+        // 0 -> 1 -> 2 -> 3 -> 0
+        let mut g = Graph::cycle_graph(4);
+        assert_eq!(g.eccentricity(&0), Some(3));
+        assert_eq!(g.diameter(), Some(3));
+        assert_eq!(g.radius(), Some(3));
+
+        // 0 -> 2 shortcut
+        assert!(g.connect(&0, &2));
+        assert_eq!(g.eccentricity(&0), Some(2));
+        assert_eq!(g.eccentricity(&1), Some(3));
+        assert_eq!(g.eccentricity(&3), Some(2));
+        assert_eq!(g.radius(), Some(2));
+        assert_eq!(g.diameter(), Some(3));
+        assert_eq!(g.eccentricity(&9), None);
+
+        assert_eq!(g.radius_within(&Budget::unlimited()), Ok(Some(2)));
+        assert_eq!(
+            g.weighted_diameter_within(&Budget::unlimited()),
+            Ok(Some(3))
+        );
+        let expired = Budget::with_timeout(Duration::ZERO);
+        assert_eq!(g.diameter_within(&expired), Err(Interrupt::Timeout));
+        assert_eq!(g.weighted_radius_within(&expired), Err(Interrupt::Timeout));
+        let token = crate::budget::CancelToken::new();
+        token.cancel();
+        let cancelled = Budget::unlimited().and_token(token);
+        assert_eq!(g.radius_within(&cancelled), Err(Interrupt::Cancelled));
+
+        // Not strongly connected
+        let g = Graph::path_graph(3);
+        assert_eq!(g.eccentricity(&0), Some(2));
+        assert_eq!(g.eccentricity(&1), None);
+        assert_eq!(g.diameter(), None);
+    }
+
+    #[test]
+    fn eulerian() {
+        let g = Graph::cycle_graph(4);
+        let path = g.eulerian_path().unwrap();
+        assert_eq!(path.len(), 4);
+        assert!(path.windows(2).all(|w| w[0].1 == w[1].0));
+        assert_eq!(path[0].0, path[3].1);
+
+        // 0 -> 1 -> 2 -> 0 -> 3 must start at 0 and end at 3
+        let mut g = Graph::cycle_graph(3);
+        g.add(3);
+        assert!(g.connect(&0, &3));
+        let path = g.eulerian_path().unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path[0].0, &0);
+        assert_eq!(path[3].1, &3);
+
+        assert!(Graph::star_graph(4).eulerian_path().is_none());
+        assert_eq!(Graph::path_graph(1).eulerian_path(), Some(vec![]));
+
+        // Two separate cycles
+        let mut g = Graph::cycle_graph(3);
+        for i in 3..6 {
+            g.add(i);
+        }
+        assert!(g.connect(&3, &4));
+        assert!(g.connect(&4, &5));
+        assert!(g.connect(&5, &3));
+        assert!(g.eulerian_path().is_none());
+    }
+
+    #[test]
+    fn undirected_eulerian() {
+        // Star with three leaves has four odd nodes
+        assert!(Graph::star_graph(4).undirected_eulerian_path().is_none());
+
+        // 1 - 0 - 2 is fine in either direction
+        let g = Graph::star_graph(3);
+        let path = g.undirected_eulerian_path().unwrap();
+        assert_eq!(path.len(), 2);
+        assert_eq!(path[1].0, &0);
+
+        let mut g = Graph::init('a'..='c');
+        assert!(g.biconnect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'a', &'c'));
+        let path = g.undirected_eulerian_path().unwrap();
+        assert_eq!(path.len(), 3);
+        assert_eq!(path[0].0, path[2].1);
+
+        // Self-loops are walked too, but not if nothing else reaches them
+        assert!(g.connect(&'b', &'b'));
+        let path = g.undirected_eulerian_path().unwrap();
+        assert_eq!(path.len(), 4);
+        assert!(path.contains(&(&'b', &'b')));
+        assert!(path.windows(2).all(|w| w[0].1 == w[1].0));
+        g.add('d');
+        assert!(g.connect(&'d', &'d'));
+        assert!(g.undirected_eulerian_path().is_none());
+
+        let mut g = Graph::init('a'..='b');
+        assert!(g.connect(&'a', &'a'));
+        assert_eq!(g.undirected_eulerian_path(), Some(vec![(&'a', &'a')]));
+    }
+
+    // 0 -> {1, 2} -> 3 -> {4, 5} -> 6 ...
+    fn diamonds(n: usize) -> Graph<usize> {
+        let mut g = Graph::init(0..=3 * n);
+        for a in (0..n).map(|i| 3 * i) {
+            assert!(g.connect(&a, &(a + 1)) && g.connect(&a, &(a + 2)));
+            assert!(g.connect(&(a + 1), &(a + 3)) && g.connect(&(a + 2), &(a + 3)));
+        }
+        g
+    }
+
+    #[test]
+    fn path_counts() {
+        // Each row of the grid doubles the choices
+        let g = Graph::grid_graph(3, 3);
+        assert_eq!(g.count_paths(&0, &8), Some(6));
+        assert_eq!(g.count_paths(&0, &0), Some(1));
+        assert_eq!(g.count_paths(&8, &0), Some(0));
+        assert_eq!(g.count_paths(&0, &9), None);
+        assert_eq!(Graph::cycle_graph(3).count_paths(&0, &2), None);
+
+        // Paths double with each diamond, 130 of them overflows u128
+        let g = diamonds(130);
+        assert_eq!(g.count_paths(&0, &300), Some(1 << 100));
+        assert_eq!(g.count_paths(&0, &390), None);
+    }
+
+    #[cfg(feature = "num-bigint")]
+    #[test]
+    fn big_path_counts() {
+        let g = diamonds(130);
+        let expected = num_bigint::BigUint::from(1u8) << 130;
+        assert_eq!(g.count_paths_big(&0, &390), Some(expected));
+    }
+
+    #[test]
+    fn shortest_path() {
+        let g = Graph::grid_graph(3, 3);
+        let (cost, path) = g.shortest_path(&0, &8).unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!(path.len(), 5);
+        assert_eq!((path[0], path[4]), (&0, &8));
+
+        assert_eq!(g.shortest_path(&4, &4), Some((0, vec![&4])));
+        assert_eq!(g.shortest_path(&8, &0), None);
+        assert_eq!(g.shortest_path(&0, &9), None);
+
+        let (cost, path) = g.shortest_path_owned(&0, &8).unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!((path[0], path[4]), (0, 8));
+
+        // Heavier direct edge loses to the longer route
+        let mut g = Graph::path_graph(3);
+        assert!(g.connect_weighted(&0, &2, 5));
+        assert_eq!(g.shortest_path(&0, &2), Some((2, vec![&0, &1, &2])));
+    }
+
+    #[test]
+    fn weighted() {
+        let mut g = Graph::cycle_graph(3);
+        assert!(g.connect_weighted(&0, &1, 5));
+        assert_eq!(g.weighted_eccentricity(&0), Some(6));
+        assert_eq!(g.weighted_eccentricity(&1), Some(2));
+        assert_eq!(g.weighted_diameter(), Some(6));
+        assert_eq!(g.weighted_radius(), Some(2));
+        assert_eq!(g.eccentricity(&0), Some(2));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{base::GraphBase, edges::Edges, graph::*, hash};
+use std::hash::Hash;
+
+// Immutable graph where every edit returns a new graph sharing structure
+// with the old one, so branching off speculative edits is cheap
+#[derive(Debug, Clone)]
+pub struct PersistentGraph<T: Clone> {
+    nodes: im::HashMap<u64, PersistentNode<T>>,
+}
+
+#[derive(Debug, Clone)]
+struct PersistentNode<T> {
+    label: T,
+    edges: im::HashMap<u64, i64>,
+}
+
+impl<T: Clone> Default for PersistentGraph<T> {
+    fn default() -> Self {
+        PersistentGraph {
+            nodes: im::HashMap::new(),
+        }
+    }
+}
+
+impl<T: Clone> PersistentGraph<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn to_graph(&self) -> Graph<T> {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(k, n)| {
+                let node = Node {
+                    label: n.label.clone(),
+                    edges: n.edges.iter().map(|(t, w)| (*t, *w)).collect(),
+                    incoming: Edges::new(),
+                };
+                (*k, node)
+            })
+            .collect();
+        Graph::from_nodes(nodes)
+    }
+}
+
+impl<T: Clone> From<&Graph<T>> for PersistentGraph<T> {
+    fn from(graph: &Graph<T>) -> Self {
+        let nodes = graph
+            .nodes
+            .iter()
+            .map(|(k, n)| {
+                let node = PersistentNode {
+                    label: n.label.clone(),
+                    edges: n.edges.iter().map(|(t, w)| (*t, *w)).collect(),
+                };
+                (*k, node)
+            })
+            .collect();
+        PersistentGraph { nodes }
+    }
+}
+
+impl<T: Hash + Eq + Clone> PersistentGraph<T> {
+    pub fn add(&self, label: T) -> Self {
+        let node = PersistentNode {
+            label,
+            edges: im::HashMap::new(),
+        };
+        PersistentGraph {
+            nodes: self.nodes.update(hash(&node.label), node),
+        }
+    }
+
+    pub fn remove(&self, label: &T) -> Option<Self> {
+        let key = hash(label);
+        let mut nodes = self.nodes.without(&key);
+        if nodes.len() == self.nodes.len() {
+            return None;
+        }
+        // Only copy the nodes that actually pointed at the removed one
+        let sources = nodes
+            .iter()
+            .filter(|(_, n)| n.edges.contains_key(&key))
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+        for source in sources {
+            nodes.get_mut(&source).unwrap().edges.remove(&key);
+        }
+        Some(PersistentGraph { nodes })
+    }
+
+    // An existing edge keeps its weight
+    pub fn connect(&self, from: &T, to: &T) -> Option<Self> {
+        if self.is_connected(from, to) {
+            return Some(self.clone());
+        }
+        self.set_edge(from, to, Some(1))
+    }
+
+    pub fn disconnect(&self, from: &T, to: &T) -> Option<Self> {
+        self.set_edge(from, to, None)
+    }
+
+    fn set_edge(&self, from: &T, to: &T, weight: Option<i64>) -> Option<Self> {
+        let (a, b) = (hash(from), hash(to));
+        if !self.nodes.contains_key(&b) {
+            return None;
+        }
+        let mut node = self.nodes.get(&a)?.clone();
+        match weight {
+            Some(weight) => node.edges.insert(b, weight),
+            None => node.edges.remove(&b),
+        };
+        Some(PersistentGraph {
+            nodes: self.nodes.update(a, node),
+        })
+    }
+
+    pub fn is_connected(&self, from: &T, to: &T) -> bool {
+        match self.nodes.get(&hash(from)) {
+            Some(node) => node.edges.contains_key(&hash(to)),
+            None => false,
+        }
+    }
+
+    pub fn connections(&self, label: &T) -> Option<Vec<&T>> {
+        let node = self.nodes.get(&hash(label))?;
+        Some(node.edges.keys().map(|k| &self.nodes[k].label).collect())
+    }
+}
+
+impl<T: Hash + Eq + Clone> GraphBase<T> for PersistentGraph<T> {
+    fn contains(&self, label: &T) -> bool {
+        self.nodes.contains_key(&hash(label))
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.nodes.values().map(|n| &n.label).collect()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        self.connections(label).unwrap_or_default()
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.nodes.get(&hash(from))?.edges.get(&hash(to)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branching() {
+        let base = PersistentGraph::from(&Graph::path_graph(3));
+        let branch = base.connect(&2, &0).unwrap();
+        assert!(branch.is_connected(&2, &0));
+        assert!(!base.is_connected(&2, &0));
+
+        let smaller = branch.remove(&1).unwrap();
+        assert_eq!(smaller.size(), 2);
+        assert!(smaller.connections(&0).unwrap().is_empty());
+        assert_eq!(branch.size(), 3);
+        assert!(branch.is_connected(&0, &1));
+
+        assert!(base.connect(&0, &7).is_none());
+        assert!(base.remove(&7).is_none());
+
+        let grown = base.add(3).connect(&2, &3).unwrap();
+        assert!(grown.contains(&3) && !base.contains(&3));
+        assert_eq!(grown.to_graph().edges().count(), 3);
+        assert!(grown
+            .disconnect(&0, &1)
+            .unwrap()
+            .to_graph()
+            .is_connected(&1, &2));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{base::GraphBase, graph::*, hash};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Deref;
+
+// When and from where a node or edge was last added
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub created: u64, // value of the mutation counter at the time
+    pub source: Option<String>,
+}
+
+// Graph wrapper recording provenance for every node and edge it adds. The
+// counter goes up on every successful mutation and the source is whatever
+// was last given to set_source
+#[derive(Debug)]
+pub struct TrackedGraph<T> {
+    graph: Graph<T>,
+    counter: u64,
+    source: Option<String>,
+    nodes: HashMap<u64, Provenance>,
+    edges: HashMap<(u64, u64), Provenance>,
+}
+
+impl<T> Default for TrackedGraph<T> {
+    fn default() -> Self {
+        TrackedGraph {
+            graph: Graph::new(),
+            counter: 0,
+            source: None,
+            nodes: HashMap::new(),
+            edges: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Deref for TrackedGraph<T> {
+    type Target = Graph<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T> TrackedGraph<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn into_inner(self) -> Graph<T> {
+        self.graph
+    }
+
+    // Tags everything added from now on, None to stop tagging
+    pub fn set_source(&mut self, source: Option<&str>) {
+        self.source = source.map(str::to_owned);
+    }
+
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    fn stamp(&mut self) -> Provenance {
+        self.counter += 1;
+        Provenance {
+            created: self.counter,
+            source: self.source.clone(),
+        }
+    }
+}
+
+impl<T: Hash + Eq> TrackedGraph<T> {
+    // Re-adding a node replaces it along with its outgoing edges, so
+    // their provenance goes too
+    pub fn add(&mut self, label: T) {
+        let key = hash(&label);
+        if let Some(node) = self.graph.nodes.get(&key) {
+            for to in node.edges.keys() {
+                self.edges.remove(&(key, *to));
+            }
+        }
+        self.graph.add(label);
+        let stamp = self.stamp();
+        self.nodes.insert(key, stamp);
+    }
+
+    pub fn remove(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        let Some((node, incoming)) = self.graph.remove_node(key) else {
+            return false;
+        };
+        self.nodes.remove(&key);
+        for to in node.edges.keys() {
+            self.edges.remove(&(key, *to));
+        }
+        for (from, _) in incoming {
+            self.edges.remove(&(from, key));
+        }
+        self.counter += 1;
+        true
+    }
+
+    // Reconnecting an existing edge keeps its original provenance
+    pub fn connect(&mut self, from: &T, to: &T) -> bool {
+        let existed = self.graph.is_connected(from, to);
+        if !self.graph.connect(from, to) {
+            return false;
+        }
+        if !existed {
+            let stamp = self.stamp();
+            self.edges.insert((hash(from), hash(to)), stamp);
+        }
+        true
+    }
+
+    pub fn disconnect(&mut self, from: &T, to: &T) -> bool {
+        if !self.graph.disconnect(from, to) {
+            return false;
+        }
+        if self.edges.remove(&(hash(from), hash(to))).is_some() {
+            self.counter += 1;
+        }
+        true
+    }
+
+    pub fn provenance(&self, label: &T) -> Option<&Provenance> {
+        self.nodes.get(&hash(label))
+    }
+
+    pub fn edge_provenance(&self, from: &T, to: &T) -> Option<&Provenance> {
+        self.edges.get(&(hash(from), hash(to)))
+    }
+
+    // Every edge added while the given source was set
+    pub fn edges_from_source(&self, source: &str) -> Vec<(&T, &T)> {
+        self.edges
+            .iter()
+            .filter(|(_, p)| p.source.as_deref() == Some(source))
+            .map(|((from, to), _)| (&self.graph.nodes[from].label, &self.graph.nodes[to].label))
+            .collect()
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for TrackedGraph<T> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provenance() {
+        let mut g = TrackedGraph::new();
+        g.set_source(Some("base.txt"));
+        g.add('a');
+        g.add('b');
+        assert!(g.connect(&'a', &'b'));
+
+        g.set_source(Some("extra.txt"));
+        g.add('c');
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'a', &'b'));
+        assert!(!g.connect(&'a', &'z'));
+
+        let a = g.provenance(&'a').unwrap();
+        assert_eq!((a.created, a.source.as_deref()), (1, Some("base.txt")));
+        let ab = g.edge_provenance(&'a', &'b').unwrap();
+        assert_eq!((ab.created, ab.source.as_deref()), (3, Some("base.txt")));
+        assert_eq!(g.edge_provenance(&'b', &'c').unwrap().created, 5);
+        assert_eq!(g.edges_from_source("extra.txt"), vec![(&'b', &'c')]);
+        assert_eq!(g.counter(), 5);
+
+        g.set_source(None);
+        assert!(g.disconnect(&'b', &'c'));
+        assert!(g.edge_provenance(&'b', &'c').is_none());
+        assert!(g.connect(&'b', &'c'));
+        assert_eq!(g.edge_provenance(&'b', &'c').unwrap().source, None);
+
+        // Replacing a drops its outgoing edge, removing c drops b -> c
+        g.add('a');
+        assert!(g.edge_provenance(&'a', &'b').is_none());
+        assert!(g.remove(&'c'));
+        assert!(g.provenance(&'c').is_none());
+        assert!(g.edge_provenance(&'b', &'c').is_none());
+        assert_eq!(g.counter(), 9);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{graph::*, hash};
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::iter::FusedIterator;
+use std::ops::{Bound, RangeBounds};
+
+// Builder for multi-hop path queries, e.g.
+// graph.query().from(&a).via_any(2..=4).through(|n| ok(n)).to(&b)
+// Only simple paths are matched, so no node appears twice in one path
+pub struct Query<'a, T> {
+    graph: &'a Graph<T>,
+    from: Option<u64>,
+    min: usize,
+    max: Option<usize>,
+    empty: bool, // the hop range can't be met, e.g. ..0
+    through: Option<Predicate<'a, T>>,
+}
+
+type Predicate<'a, T> = Box<dyn Fn(&T) -> bool + 'a>;
+
+// Matching paths found depth first, one at a time
+pub struct PathIter<'a, T> {
+    query: Query<'a, T>,
+    to: Option<u64>,
+    starts: Vec<u64>,
+    stack: Vec<(u64, Vec<u64>)>, // node on the path and its neighbors left to try
+    on_path: HashSet<u64>,
+}
+
+impl<T> Graph<T> {
+    pub fn query(&self) -> Query<'_, T> {
+        Query {
+            graph: self,
+            from: None,
+            min: 0,
+            max: None,
+            empty: false,
+            through: None,
+        }
+    }
+}
+
+impl<'a, T: Hash + Eq> Query<'a, T> {
+    // Paths start from every node unless this is set
+    pub fn from(mut self, label: &T) -> Self {
+        self.from = Some(hash(label));
+        self
+    }
+
+    // Number of edges a path may have. An empty range matches nothing
+    pub fn via_any<R: RangeBounds<usize>>(mut self, hops: R) -> Self {
+        let min = match hops.start_bound() {
+            Bound::Included(n) => Some(*n),
+            Bound::Excluded(n) => n.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let max = match hops.end_bound() {
+            Bound::Included(n) => Some(Some(*n)),
+            Bound::Excluded(n) => n.checked_sub(1).map(Some),
+            Bound::Unbounded => Some(None),
+        };
+        match (min, max) {
+            (Some(min), Some(max)) if max.is_none_or(|max| min <= max) => {
+                self.min = min;
+                self.max = max;
+                self.empty = false;
+            }
+            _ => self.empty = true,
+        }
+        self
+    }
+
+    // Every node after the start has to pass the predicate
+    pub fn through<F: Fn(&T) -> bool + 'a>(mut self, predicate: F) -> Self {
+        self.through = Some(Box::new(predicate));
+        self
+    }
+
+    pub fn to(self, label: &T) -> PathIter<'a, T> {
+        let to = hash(label);
+        self.search(Some(to))
+    }
+
+    // Paths ending anywhere
+    pub fn paths(self) -> PathIter<'a, T> {
+        self.search(None)
+    }
+
+    fn search(self, to: Option<u64>) -> PathIter<'a, T> {
+        let starts = match self.from {
+            _ if self.empty => Vec::new(),
+            Some(from) if self.graph.nodes.contains_key(&from) => vec![from],
+            Some(_) => Vec::new(),
+            None => self.graph.nodes.keys().copied().collect(),
+        };
+        PathIter {
+            query: self,
+            to,
+            starts,
+            stack: Vec::new(),
+            on_path: HashSet::new(),
+        }
+    }
+}
+
+impl<'a, T> PathIter<'a, T> {
+    fn push(&mut self, key: u64) {
+        let hops = self.stack.len();
+        let neighbors = if self.query.max.is_some_and(|max| hops >= max) {
+            Vec::new()
+        } else {
+            self.query.graph.nodes[&key].edges.keys().copied().collect()
+        };
+        self.stack.push((key, neighbors));
+        self.on_path.insert(key);
+    }
+
+    // The path on the stack if it's long enough and ends in the right place
+    fn matched(&self) -> Option<Vec<&'a T>> {
+        let hops = self.stack.len() - 1;
+        let (end, _) = self.stack.last()?;
+        if hops < self.query.min || self.to.is_some_and(|to| to != *end) {
+            return None;
+        }
+        let nodes = &self.query.graph.nodes;
+        Some(self.stack.iter().map(|(k, _)| &nodes[k].label).collect())
+    }
+}
+
+impl<'a, T> Iterator for PathIter<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.stack.is_empty() {
+                let start = self.starts.pop()?;
+                self.on_path.clear();
+                self.push(start);
+                if let Some(path) = self.matched() {
+                    return Some(path);
+                }
+                continue;
+            }
+
+            let next = self.stack.last_mut().unwrap().1.pop();
+            match next {
+                Some(key) => {
+                    let label = &self.query.graph.nodes[&key].label;
+                    let allowed = self.query.through.as_ref().is_none_or(|f| f(label));
+                    if self.on_path.contains(&key) || !allowed {
+                        continue;
+                    }
+                    self.push(key);
+                    if let Some(path) = self.matched() {
+                        return Some(path);
+                    }
+                }
+                None => {
+                    let (key, _) = self.stack.pop().unwrap();
+                    self.on_path.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+impl<T> FusedIterator for PathIter<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        let g = Graph::grid_graph(3, 3);
+
+        // Corner to corner always takes exactly 4 steps right or down
+        let mut paths = g.query().from(&0).to(&8).collect::<Vec<_>>();
+        assert_eq!(paths.len(), 6);
+        assert!(paths.iter().all(|p| p.len() == 5));
+        assert_eq!(g.query().from(&0).via_any(..4).to(&8).count(), 0);
+
+        // Avoiding the centre leaves the two paths round the edge
+        paths = g.query().from(&0).through(|n| *n != 4).to(&8).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![vec![&0, &1, &2, &5, &8], vec![&0, &3, &6, &7, &8]]
+        );
+
+        assert_eq!(g.query().from(&0).via_any(1..=1).paths().count(), 2);
+        assert_eq!(g.query().from(&0).via_any(0..=0).paths().count(), 1);
+        assert_eq!(g.query().from(&0).via_any(..0).paths().count(), 0);
+        #[allow(clippy::reversed_empty_ranges)]
+        let backwards = 3..2;
+        assert_eq!(g.query().via_any(backwards).paths().count(), 0);
+        assert_eq!(
+            g.query().via_any(..0).via_any(..).paths().count(),
+            g.query().paths().count()
+        );
+        assert_eq!(g.query().via_any(1..=1).paths().count(), 12);
+        assert_eq!(g.query().from(&9).paths().count(), 0);
+    }
+
+    #[test]
+    fn cycles_are_not_revisited() {
+        let g = Graph::cycle_graph(4);
+        let paths = g.query().from(&0).via_any(1..).paths().collect::<Vec<_>>();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(g.query().from(&0).to(&0).count(), 1);
+        assert_eq!(g.query().from(&0).via_any(2..=3).to(&3).count(), 1);
+    }
+}
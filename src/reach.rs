@@ -0,0 +1,277 @@
+use crate::{base::GraphBase, graph::*, hash};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::Deref;
+
+// Graph wrapper that keeps the transitive closure up to date as edges are
+// added so has_path is a single lookup. Additions extend the closure in
+// place, disconnecting or removing rebuilds it from scratch
+#[derive(Debug)]
+pub struct ReachabilityGraph<T> {
+    graph: Graph<T>,
+    reach: HashMap<u64, HashSet<u64>>, // every node reachable from the key, itself included
+}
+
+impl<T: Hash> Default for ReachabilityGraph<T> {
+    fn default() -> Self {
+        Graph::new().into()
+    }
+}
+
+impl<T: Hash> From<Graph<T>> for ReachabilityGraph<T> {
+    fn from(graph: Graph<T>) -> Self {
+        ReachabilityGraph::from_with_progress(graph, |_, _| ())
+    }
+}
+
+impl<T> Deref for ReachabilityGraph<T> {
+    type Target = Graph<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T: Hash> ReachabilityGraph<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn into_inner(self) -> Graph<T> {
+        self.graph
+    }
+
+    // The closure is built one node at a time, reported as the number done
+    // out of the total
+    pub fn from_with_progress<P>(graph: Graph<T>, progress: P) -> Self
+    where
+        P: FnMut(usize, usize),
+    {
+        let mut g = ReachabilityGraph {
+            graph,
+            reach: HashMap::new(),
+        };
+        g.rebuild_with_progress(progress);
+        g
+    }
+
+    fn rebuild(&mut self) {
+        self.rebuild_with_progress(|_, _| ());
+    }
+
+    fn rebuild_with_progress<P: FnMut(usize, usize)>(&mut self, mut progress: P) {
+        let total = self.graph.size();
+        self.reach = HashMap::with_capacity(total);
+        for key in self.graph.nodes.keys() {
+            let reached = self.graph.hop_distances(*key).into_keys().collect();
+            self.reach.insert(*key, reached);
+            progress(self.reach.len(), total);
+        }
+    }
+}
+
+impl<T: Hash + Eq> ReachabilityGraph<T> {
+    pub fn add(&mut self, label: T) {
+        let key = hash(&label);
+        let replaced = self.graph.nodes.contains_key(&key);
+        self.graph.add(label);
+        if replaced {
+            // Its outgoing edges are gone
+            self.rebuild();
+        } else {
+            self.reach.insert(key, std::iter::once(key).collect());
+        }
+    }
+
+    pub fn remove(&mut self, label: &T) -> bool {
+        if self.graph.remove(label).is_none() {
+            return false;
+        }
+        self.rebuild();
+        true
+    }
+
+    pub fn connect(&mut self, from: &T, to: &T) -> bool {
+        if !self.graph.connect(from, to) {
+            return false;
+        }
+        let (a, b) = (hash(from), hash(to));
+        if !self.reach[&a].contains(&b) {
+            let added = self.reach[&b].clone();
+            for reachable in self.reach.values_mut() {
+                if reachable.contains(&a) {
+                    reachable.extend(&added);
+                }
+            }
+        }
+        true
+    }
+
+    pub fn disconnect(&mut self, from: &T, to: &T) -> bool {
+        let connected = self.graph.is_connected(from, to);
+        if !self.graph.disconnect(from, to) {
+            return false;
+        }
+        if connected {
+            self.rebuild();
+        }
+        true
+    }
+
+    // Whether to can be reached from from, a node always reaches itself
+    pub fn has_path(&self, from: &T, to: &T) -> bool {
+        self.reach
+            .get(&hash(from))
+            .is_some_and(|r| r.contains(&hash(to)))
+    }
+}
+
+// Chain-decomposition labels for a static DAG. Nodes are split into paths
+// and each node stores, for every path, the earliest position on it that
+// it can reach. Memory is nodes x paths rather than nodes squared
+#[derive(Debug)]
+pub struct ReachLabels {
+    positions: HashMap<u64, (usize, usize)>, // path and index along it
+    earliest: HashMap<u64, Vec<usize>>,      // usize::MAX where a path is out of reach
+}
+
+impl ReachLabels {
+    // A node always reaches itself, missing nodes reach nothing
+    pub fn reaches<T: Hash>(&self, from: &T, to: &T) -> bool {
+        match (
+            self.earliest.get(&hash(from)),
+            self.positions.get(&hash(to)),
+        ) {
+            (Some(earliest), Some((path, index))) => earliest[*path] <= *index,
+            _ => false,
+        }
+    }
+
+    pub fn paths(&self) -> usize {
+        self.earliest.values().next().map_or(0, Vec::len)
+    }
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    // None if the graph has a cycle. Labels go stale once the graph changes
+    pub fn build_reach_labels(&self) -> Option<ReachLabels> {
+        let paths = self.minimum_path_cover()?;
+        let positions = paths
+            .iter()
+            .enumerate()
+            .flat_map(|(p, path)| {
+                path.iter()
+                    .enumerate()
+                    .map(move |(i, l)| (hash(*l), (p, i)))
+            })
+            .collect::<HashMap<_, _>>();
+
+        // Sinks first so every successor is done before its predecessors
+        let order = self.ordering().map(hash).collect::<Vec<_>>();
+        let mut earliest = HashMap::<u64, Vec<usize>>::new();
+        for key in order.into_iter().rev() {
+            let mut reach = vec![usize::MAX; paths.len()];
+            let (path, index) = positions[&key];
+            reach[path] = index;
+            for target in self.nodes[&key].edges.keys() {
+                for (r, t) in reach.iter_mut().zip(&earliest[target]) {
+                    *r = (*r).min(*t);
+                }
+            }
+            earliest.insert(key, reach);
+        }
+        Some(ReachLabels {
+            positions,
+            earliest,
+        })
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for ReachabilityGraph<T> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_closure(g: &ReachabilityGraph<char>) {
+        for a in g.labels() {
+            for b in g.labels() {
+                assert_eq!(g.has_path(a, b), g.bfs(a).any(|n| n == b));
+            }
+        }
+    }
+
+    #[test]
+    fn reach_labels() {
+        let g = Graph::grid_graph(4, 3);
+        let labels = g.build_reach_labels().unwrap();
+        assert!(labels.paths() <= 4);
+        for a in 0..12usize {
+            for b in 0..12usize {
+                let expected = a % 4 <= b % 4 && a / 4 <= b / 4;
+                assert_eq!(labels.reaches(&a, &b), expected);
+            }
+        }
+        assert!(!labels.reaches(&0usize, &12usize));
+        assert!(Graph::cycle_graph(3).build_reach_labels().is_none());
+    }
+
+    #[test]
+    fn progress() {
+        let mut reports = Vec::new();
+        let g = ReachabilityGraph::from_with_progress(Graph::path_graph(3), |done, total| {
+            reports.push((done, total))
+        });
+        assert!(g.has_path(&0, &2));
+        assert_eq!(reports, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn maintains_closure() {
+        let mut g = ReachabilityGraph::from(Graph::init('a'..='c'));
+        assert!(g.connect(&'a', &'b'));
+        g.add('d');
+        g.add('e');
+        assert!(g.connect(&'c', &'d'));
+        assert!(!g.has_path(&'a', &'d'));
+        assert_closure(&g);
+
+        // Joins a -> b to c -> d, then closes a cycle through e
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.has_path(&'a', &'d'));
+        assert!(g.connect(&'d', &'e'));
+        assert!(g.connect(&'e', &'b'));
+        assert!(g.has_path(&'d', &'c'));
+        assert_closure(&g);
+
+        assert!(g.disconnect(&'b', &'c'));
+        assert!(!g.has_path(&'a', &'d'));
+        assert_closure(&g);
+
+        assert!(g.remove(&'e'));
+        assert!(!g.has_path(&'d', &'b'));
+        assert!(!g.has_path(&'e', &'e'));
+        assert_closure(&g);
+
+        g.add('a');
+        assert!(!g.has_path(&'a', &'b'));
+        assert!(!g.connect(&'a', &'z'));
+        assert_closure(&g);
+    }
+}
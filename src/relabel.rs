@@ -0,0 +1,91 @@
+use crate::{graph::*, hash};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+impl<T> Graph<T> {
+    // Maps every label into a new graph. Nodes that end up with the same
+    // label are merged, with the weights of edges that now join the same
+    // pair summed. Edges between merged nodes become self-loops
+    pub fn relabel_all<U, F>(self, mut f: F) -> Graph<U>
+    where
+        U: Hash + Eq,
+        F: FnMut(T) -> U,
+    {
+        let mut relabelled = Graph::with_capacity(self.size());
+        let mut keys = HashMap::with_capacity(self.size());
+        let mut edges = Vec::with_capacity(self.edge_count());
+        for (key, node) in self.nodes {
+            edges.extend(node.edges.iter().map(|(t, w)| (key, *t, *w)));
+            let label = f(node.label);
+            keys.insert(key, hash(&label));
+            if !relabelled.contains(&label) {
+                relabelled.add(label);
+            }
+        }
+        for (from, to, weight) in edges {
+            let (from, to) = (keys[&from], keys[&to]);
+            let merged = match relabelled.nodes[&from].edges.get(&to) {
+                Some(existing) => existing + weight,
+                None => weight,
+            };
+            relabelled.set_edge(from, to, Some(merged));
+        }
+        relabelled
+    }
+}
+
+impl<T: Hash + Eq + Clone> Graph<T> {
+    // Renames the nodes in the mapping, leaving the rest alone, and merges
+    // any that collide as relabel_all does. Returns how many nodes were
+    // merged away
+    pub fn apply_mapping(&mut self, mapping: &HashMap<T, T>) -> usize {
+        let before = self.size();
+        let graph = core::mem::take(self);
+        *self = graph.relabel_all(|label| match mapping.get(&label) {
+            Some(renamed) => renamed.clone(),
+            None => label,
+        });
+        before - self.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::GraphBase;
+
+    #[test]
+    fn relabel_all() {
+        let mut g = Graph::init(1..=4);
+        for (a, b) in [(1, 2), (2, 3), (3, 4), (1, 3), (4, 4)] {
+            assert!(g.connect(&a, &b));
+        }
+        let g = g.relabel_all(|n| n.to_string());
+        assert_eq!(g.size(), 4);
+        assert_eq!(g.edge_count(), 5);
+        assert!(g.contains_edge(&"4".to_owned(), &"4".to_owned()));
+
+        // Odd and even nodes fold together
+        let g = g.relabel_all(|n| n.parse::<u32>().unwrap() % 2);
+        assert_eq!(g.size(), 2);
+        assert_eq!(g.weight(&1, &0), Some(2));
+        assert_eq!(g.weight(&0, &1), Some(1));
+        assert_eq!(g.weight(&1, &1), Some(1));
+        assert_eq!(g.weight(&0, &0), Some(1));
+    }
+
+    #[test]
+    fn apply_mapping() {
+        let mut g = Graph::init(vec!["svc-api", "api", "db", "cache"]);
+        for (a, b) in [("svc-api", "db"), ("api", "db"), ("api", "cache")] {
+            assert!(g.connect(&a, &b));
+        }
+        let mapping = HashMap::from([("svc-api", "api"), ("cache", "redis")]);
+        assert_eq!(g.apply_mapping(&mapping), 1);
+        assert_eq!(g.labels().len(), 3);
+        assert_eq!(g.weight(&"api", &"db"), Some(2));
+        assert_eq!(g.weight(&"api", &"redis"), Some(1));
+        assert!(!g.contains(&"svc-api"));
+        assert_eq!(g.indegree(&"db"), Some(1));
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{graph::*, hash};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+#[derive(Debug, PartialEq)]
+pub enum ResolveError<'a, T> {
+    Missing(Vec<&'a T>),
+    Cycles(Vec<Vec<&'a T>>), // each listed from the node the cycle was entered at
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Visiting,
+    Done,
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    // Everything the targets transitively depend on, where an edge a -> b
+    // means a depends on b, ordered so each node comes after everything
+    // it depends on. Unknown targets are reported before any cycles
+    pub fn resolve<'a, I>(&'a self, targets: I) -> Result<Vec<&'a T>, ResolveError<'a, T>>
+    where
+        I: IntoIterator<Item = &'a T>,
+    {
+        let (targets, missing): (Vec<_>, Vec<_>) = targets
+            .into_iter()
+            .partition(|t| self.nodes.contains_key(&hash(*t)));
+        if !missing.is_empty() {
+            return Err(ResolveError::Missing(missing));
+        }
+
+        let targets = targets.into_iter().map(hash).collect();
+        let (order, cycles) = self.post_order(targets, false);
+        if cycles.is_empty() {
+            Ok(self.labels_of(order))
+        } else {
+            let cycles = cycles.into_iter().map(|c| self.labels_of(c)).collect();
+            Err(ResolveError::Cycles(cycles))
+        }
+    }
+
+    // The rebuild plan after the given nodes change: those nodes and
+    // everything that transitively depends on them, ordered so each node
+    // comes after whatever it depends on that's also being rebuilt. Errors
+    // the same way as resolve, with cycles listed along the edges
+    pub fn dirty_set<'a, I>(&'a self, changed: I) -> Result<Vec<&'a T>, ResolveError<'a, T>>
+    where
+        I: IntoIterator<Item = &'a T>,
+    {
+        let (changed, missing): (Vec<_>, Vec<_>) = changed
+            .into_iter()
+            .partition(|t| self.nodes.contains_key(&hash(*t)));
+        if !missing.is_empty() {
+            return Err(ResolveError::Missing(missing));
+        }
+
+        // Walking the reverse index gives dependents before their
+        // dependencies, so both the order and the cycles get turned round
+        let changed = changed.into_iter().map(hash).collect();
+        let (mut order, cycles) = self.post_order(changed, true);
+        if cycles.is_empty() {
+            order.reverse();
+            Ok(self.labels_of(order))
+        } else {
+            let cycles = cycles
+                .into_iter()
+                .map(|mut c| {
+                    c[1..].reverse();
+                    self.labels_of(c)
+                })
+                .collect();
+            Err(ResolveError::Cycles(cycles))
+        }
+    }
+
+    // Every other node with a path to the given one, found from the
+    // reverse index, i.e. everything that depends on it
+    pub fn ancestors(&self, label: &T) -> HashSet<&T> {
+        let key = hash(label);
+        if !self.nodes.contains_key(&key) {
+            return HashSet::new();
+        }
+        let (order, _) = self.post_order(vec![key], true);
+        order
+            .into_iter()
+            .filter(|k| *k != key)
+            .map(|k| &self.nodes[&k].label)
+            .collect()
+    }
+
+    fn labels_of(&self, keys: Vec<u64>) -> Vec<&T> {
+        keys.into_iter().map(|k| &self.nodes[&k].label).collect()
+    }
+
+    // Depth first post-order from the starts along outgoing edges, or along
+    // the reverse index when backwards. Cycles are listed from the node the
+    // cycle was entered at
+    fn post_order(&self, starts: Vec<u64>, backwards: bool) -> (Vec<u64>, Vec<Vec<u64>>) {
+        let next = |key: u64| -> Vec<u64> {
+            let node = &self.nodes[&key];
+            let edges = if backwards {
+                &node.incoming
+            } else {
+                &node.edges
+            };
+            edges.keys().copied().collect()
+        };
+        let mut states = HashMap::new();
+        let mut order = Vec::new();
+        let mut cycles = Vec::new();
+        for start in starts {
+            if states.contains_key(&start) {
+                continue;
+            }
+            states.insert(start, State::Visiting);
+            let mut stack = vec![(start, next(start))];
+            while let Some((key, pending)) = stack.last_mut() {
+                let key = *key;
+                match pending.pop() {
+                    Some(following) => match states.get(&following) {
+                        None => {
+                            states.insert(following, State::Visiting);
+                            stack.push((following, next(following)));
+                        }
+                        Some(State::Visiting) => {
+                            let start = stack.iter().position(|(k, _)| *k == following).unwrap();
+                            cycles.push(stack[start..].iter().map(|(k, _)| *k).collect());
+                        }
+                        Some(State::Done) => (),
+                    },
+                    None => {
+                        stack.pop();
+                        states.insert(key, State::Done);
+                        order.push(key);
+                    }
+                }
+            }
+        }
+        (order, cycles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packages() -> Graph<&'static str> {
+        let labels = ["app", "http", "json", "tls", "log", "unused"];
+        let mut g = Graph::init(labels.iter().copied());
+        for (from, to) in [
+            ("app", "http"),
+            ("app", "json"),
+            ("http", "tls"),
+            ("http", "log"),
+            ("tls", "log"),
+            ("json", "log"),
+        ] {
+            assert!(g.connect(&from, &to));
+        }
+        g
+    }
+
+    #[test]
+    fn install_order() {
+        let g = packages();
+        let order = g.resolve(&["app"]).unwrap();
+        assert_eq!(order.len(), 5);
+        assert!(!order.contains(&&"unused"));
+        assert_eq!(order.last(), Some(&&"app"));
+        for (from, to) in g.edges().map(|e| (e.from, e.to)) {
+            if let (Some(a), Some(b)) = (
+                order.iter().position(|n| *n == from),
+                order.iter().position(|n| *n == to),
+            ) {
+                assert!(b < a);
+            }
+        }
+
+        assert_eq!(g.resolve(&["log", "log"]).unwrap(), vec![&"log"]);
+        assert!(g.resolve(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rebuild_plan() {
+        let g = packages();
+        assert_eq!(g.ancestors(&"tls"), HashSet::from([&"http", &"app"]));
+        assert!(g.ancestors(&"app").is_empty());
+        assert!(g.ancestors(&"nope").is_empty());
+
+        let plan = g.dirty_set(&["tls"]).unwrap();
+        assert_eq!(plan, vec![&"tls", &"http", &"app"]);
+        let plan = g.dirty_set(&["log", "json"]).unwrap();
+        assert_eq!(plan.len(), 5);
+        assert_eq!(plan[0], &"log");
+        assert_eq!(plan.last(), Some(&&"app"));
+        let at = |n| plan.iter().position(|p| *p == n).unwrap();
+        assert!(at(&"tls") < at(&"http"));
+        assert_eq!(g.dirty_set(&["unused"]).unwrap(), vec![&"unused"]);
+    }
+
+    #[test]
+    fn dirty_set_errors() {
+        let mut g = packages();
+        assert_eq!(
+            g.dirty_set(&["tls", "nope"]),
+            Err(ResolveError::Missing(vec![&"nope"]))
+        );
+        assert!(g.connect(&"log", &"app"));
+        match g.dirty_set(&["json"]) {
+            Err(ResolveError::Cycles(cycles)) => {
+                assert!(!cycles.is_empty());
+                for cycle in cycles {
+                    for (i, from) in cycle.iter().enumerate() {
+                        let to = cycle[(i + 1) % cycle.len()];
+                        assert!(g.contains_edge(from, to));
+                    }
+                }
+            }
+            other => panic!("expected cycles, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut g = packages();
+        assert_eq!(
+            g.resolve(&["app", "nope", "gone"]),
+            Err(ResolveError::Missing(vec![&"nope", &"gone"]))
+        );
+
+        assert!(g.connect(&"log", &"http"));
+        assert!(g.connect(&"unused", &"unused"));
+        match g.resolve(&["tls", "unused"]) {
+            Err(ResolveError::Cycles(cycles)) => {
+                assert!(cycles.contains(&vec![&"unused"]));
+                assert!(cycles.iter().any(|c| c.len() == 3 && c[0] == &"tls"));
+            }
+            other => panic!("expected cycles, got {:?}", other),
+        }
+        assert!(g.resolve(&["json"]).is_err());
+    }
+}
@@ -0,0 +1,401 @@
+use crate::{graph::*, hash};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::{Display, Write};
+use std::hash::Hash;
+
+// Pixels per row and for the label column in the SVG chart
+const SVG_ROW: i64 = 24;
+const SVG_LABELS: i64 = 160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    pub earliest_start: i64,
+    pub latest_start: i64,
+    pub slack: i64,
+}
+
+impl Timing {
+    pub fn is_critical(&self) -> bool {
+        self.slack == 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Schedule<'a, T> {
+    pub length: i64,
+    pub timings: HashMap<&'a T, Timing>,
+}
+
+impl<T: Hash + Eq> Schedule<'_, T> {
+    // Nodes that can't start any later without delaying the whole project,
+    // in order of when they start
+    pub fn critical(&self) -> Vec<&T> {
+        let mut critical = self
+            .timings
+            .iter()
+            .filter(|(_, t)| t.is_critical())
+            .map(|(l, t)| (t.earliest_start, *l))
+            .collect::<Vec<_>>();
+        critical.sort_unstable_by_key(|(start, _)| *start);
+        critical.into_iter().map(|(_, l)| l).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot {
+    pub worker: usize,
+    pub start: i64,
+    pub finish: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Simulation<'a, T> {
+    pub makespan: i64,
+    pub slots: HashMap<&'a T, Slot>,
+}
+
+impl<T: Display> Simulation<'_, T> {
+    // Rows in start order, ties by label
+    fn rows(&self) -> Vec<(String, Slot)> {
+        let mut rows = self
+            .slots
+            .iter()
+            .map(|(l, s)| (l.to_string(), *s))
+            .collect::<Vec<_>>();
+        rows.sort_unstable_by(|(a, x), (b, y)| (x.start, a).cmp(&(y.start, b)));
+        rows
+    }
+
+    // Where time lands on a chart `span` units wide
+    fn scale(&self, time: i64, span: usize) -> i64 {
+        if self.makespan == 0 {
+            return 0;
+        }
+        (time as i128 * span as i128 / self.makespan as i128) as i64
+    }
+
+    // Start and end of a task's bar, any task that takes time getting at
+    // least one unit so it doesn't vanish when scaled down
+    fn bar(&self, slot: &Slot, span: usize) -> (i64, i64) {
+        let start = self.scale(slot.start, span);
+        let end = self.scale(slot.finish, span);
+        if slot.finish > slot.start && span > 0 {
+            (
+                start.min(span as i64 - 1),
+                end.max(start + 1).min(span as i64),
+            )
+        } else {
+            (start, start)
+        }
+    }
+
+    // One row per task with its bar scaled to fit `columns` characters,
+    // then the worker and the times it ran
+    pub fn gantt(&self, columns: usize) -> String {
+        let rows = self.rows();
+        let width = rows
+            .iter()
+            .map(|(l, _)| l.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut out = String::new();
+        for (label, slot) in rows {
+            let (start, end) = self.bar(&slot, columns);
+            let bar = " ".repeat(start as usize) + &"#".repeat((end - start) as usize);
+            writeln!(
+                out,
+                "{:<width$} |{:<columns$}| w{} {}-{}",
+                label,
+                bar,
+                slot.worker,
+                slot.start,
+                slot.finish,
+                width = width,
+                columns = columns,
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    // The same chart as a standalone SVG document, with the bars scaled to
+    // fit `width` pixels
+    pub fn gantt_svg(&self, width: usize) -> String {
+        let rows = self.rows();
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+            SVG_LABELS + width as i64,
+            rows.len() as i64 * SVG_ROW,
+        )
+        .unwrap();
+        for (row, (label, slot)) in rows.iter().enumerate() {
+            let y = row as i64 * SVG_ROW;
+            let (start, end) = self.bar(slot, width);
+            writeln!(
+                svg,
+                "  <text x=\"0\" y=\"{}\">{}</text>",
+                y + SVG_ROW * 3 / 4,
+                escape(label),
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><title>w{} {}-{}</title></rect>",
+                SVG_LABELS + start,
+                y + 2,
+                end - start,
+                SVG_ROW - 4,
+                slot.worker,
+                slot.start,
+                slot.finish,
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    // Critical path method over tasks taking the given durations, where an
+    // edge a -> b means a can't start until b has finished. None if the
+    // graph has a cycle
+    pub fn cpm<F: Fn(&T) -> i64>(&self, duration: F) -> Option<Schedule<'_, T>> {
+        let latest = |inputs: &[(&i64, i64)]| inputs.iter().map(|(v, _)| **v).max().unwrap();
+        let finishes = self.dag_dp_backward(&duration, |n, inputs| duration(n) + latest(inputs))?;
+        let length = finishes.values().copied().max().unwrap_or(0);
+
+        // Work back from the end, each task starting as late as the first
+        // thing waiting on it allows
+        let earliest = |inputs: &[(&i64, i64)]| inputs.iter().map(|(v, _)| **v).min().unwrap();
+        let starts = self.dag_dp(
+            |n| length - duration(n),
+            |n, inputs| earliest(inputs) - duration(n),
+        )?;
+
+        let timings = finishes
+            .into_iter()
+            .map(|(label, finish)| {
+                let earliest_start = finish - duration(label);
+                let latest_start = starts[label];
+                let timing = Timing {
+                    earliest_start,
+                    latest_start,
+                    slack: latest_start - earliest_start,
+                };
+                (label, timing)
+            })
+            .collect();
+        Some(Schedule { length, timings })
+    }
+
+    // Runs the tasks on a fixed number of workers, edges meaning the same as
+    // for cpm. Whenever a worker is free it takes the ready task with the
+    // longest chain of work waiting on it, ties going to the lower key.
+    // None if the graph has a cycle, a duration is negative, or there's
+    // work but no workers
+    pub fn simulate_schedule<F>(&self, workers: usize, duration: F) -> Option<Simulation<'_, T>>
+    where
+        F: Fn(&T) -> i64,
+    {
+        if self.nodes.values().any(|n| duration(&n.label) < 0) {
+            return None;
+        }
+        let latest = |inputs: &[(&i64, i64)]| inputs.iter().map(|(v, _)| **v).max().unwrap();
+        let levels = self.dag_dp(&duration, |n, inputs| duration(n) + latest(inputs))?;
+        if workers == 0 && self.size() > 0 {
+            return None;
+        }
+
+        let mut waiting = HashMap::with_capacity(self.size());
+        let mut ready = BinaryHeap::new();
+        for (key, node) in &self.nodes {
+            if node.edges.is_empty() {
+                ready.push((levels[&node.label], Reverse(*key)));
+            } else {
+                waiting.insert(*key, node.edges.len());
+            }
+        }
+        // No more than one worker per task can ever be busy
+        let mut free = (0..workers.min(self.size())).rev().collect::<Vec<_>>();
+        let mut running = BinaryHeap::new();
+        let mut slots = HashMap::with_capacity(self.size());
+        let mut now = 0;
+        loop {
+            while !free.is_empty() {
+                let Some((_, Reverse(key))) = ready.pop() else {
+                    break;
+                };
+                let label = &self.nodes[&key].label;
+                let slot = Slot {
+                    worker: free.pop().unwrap(),
+                    start: now,
+                    finish: now + duration(label),
+                };
+                running.push(Reverse((slot.finish, key)));
+                slots.insert(label, slot);
+            }
+            let Some(Reverse((finish, key))) = running.pop() else {
+                break;
+            };
+            now = finish;
+            let node = &self.nodes[&key];
+            free.push(slots[&node.label].worker);
+            for dependent in node.incoming.keys() {
+                let count = waiting.get_mut(dependent).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    let label = &self.nodes[dependent].label;
+                    ready.push((levels[label], Reverse(hash(label))));
+                }
+            }
+        }
+        let makespan = slots.values().map(|s| s.finish).max().unwrap_or(0);
+        Some(Simulation { makespan, slots })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpm() {
+        let mut g = Graph::init(vec!["design", "build", "docs", "test", "ship"]);
+        for (a, b) in [
+            ("build", "design"),
+            ("docs", "design"),
+            ("test", "build"),
+            ("ship", "test"),
+            ("ship", "docs"),
+        ] {
+            assert!(g.connect(&a, &b));
+        }
+        let days = |task: &&str| match *task {
+            "design" => 3,
+            "build" => 5,
+            "docs" => 2,
+            "test" => 1,
+            _ => 0,
+        };
+        let schedule = g.cpm(days).unwrap();
+        assert_eq!(schedule.length, 9);
+        assert_eq!(
+            schedule.critical(),
+            vec![&"design", &"build", &"test", &"ship"]
+        );
+        assert_eq!(
+            schedule.timings[&"docs"],
+            Timing {
+                earliest_start: 3,
+                latest_start: 7,
+                slack: 4
+            }
+        );
+        assert_eq!(schedule.timings[&"ship"].earliest_start, 9);
+
+        assert!(g.connect(&"design", &"ship"));
+        assert!(g.cpm(days).is_none());
+        assert!(g.simulate_schedule(2, days).is_none());
+        assert_eq!(Graph::<&str>::new().cpm(days).unwrap().length, 0);
+    }
+
+    #[test]
+    fn simulate_schedule() {
+        // Four independent builds of 4, 3, 2 and 2 feeding a link step of 1
+        let mut g = Graph::init(vec!["a", "b", "c", "d", "link"]);
+        for dep in ["a", "b", "c", "d"] {
+            assert!(g.connect(&"link", &dep));
+        }
+        let secs = |task: &&str| match *task {
+            "a" => 4,
+            "b" => 3,
+            "link" => 1,
+            _ => 2,
+        };
+        let serial = g.simulate_schedule(1, secs).unwrap();
+        assert_eq!(serial.makespan, 12);
+        assert_eq!(serial.slots[&"a"].start, 0);
+
+        let two = g.simulate_schedule(2, secs).unwrap();
+        assert_eq!(two.makespan, 7);
+        assert_eq!(two.slots[&"link"].start, 6);
+        let a = Slot {
+            worker: 0,
+            start: 0,
+            finish: 4,
+        };
+        assert_eq!(two.slots[&"a"], a);
+        let (c, d) = (two.slots[&"c"], two.slots[&"d"]);
+        assert_eq!((c.start.min(d.start), c.start.max(d.start)), (3, 4));
+
+        // Never shorter than the critical path however many workers
+        let many = g.simulate_schedule(8, secs).unwrap();
+        assert_eq!(many.makespan, g.cpm(secs).unwrap().length);
+        assert!(many.slots.values().all(|s| s.worker < 4));
+        assert_eq!(g.simulate_schedule(usize::MAX, secs).unwrap().makespan, 5);
+
+        assert!(g.simulate_schedule(0, secs).is_none());
+        assert!(g
+            .simulate_schedule(2, |t| if *t == "b" { -1 } else { 1 })
+            .is_none());
+        assert_eq!(
+            Graph::<&str>::new()
+                .simulate_schedule(0, secs)
+                .unwrap()
+                .makespan,
+            0
+        );
+    }
+
+    #[test]
+    fn gantt() {
+        let mut g = Graph::init(vec!["fetch", "lib", "bin", "link"]);
+        for (a, b) in [
+            ("lib", "fetch"),
+            ("bin", "fetch"),
+            ("link", "lib"),
+            ("link", "bin"),
+        ] {
+            assert!(g.connect(&a, &b));
+        }
+        let secs = |task: &&str| if *task == "lib" { 3 } else { 1 };
+        let sim = g.simulate_schedule(2, secs).unwrap();
+        let chart = sim.gantt(5);
+        let lines = chart.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "fetch |#    | w0 0-1");
+        assert!(lines[1..3].contains(&"lib   | ### | w0 1-4"));
+        assert!(lines[1..3].iter().any(|l| l.starts_with("bin   | #   |")));
+        assert_eq!(lines[3], "link  |    #| w0 4-5");
+
+        // Scaled up and down, short tasks still get a mark
+        assert!(sim.gantt(10).contains("lib   |  ######  | w0 1-4"));
+        assert!(!sim.gantt(0).contains('#'));
+        let narrow = sim.gantt(2);
+        assert!(narrow.lines().all(|l| l.contains("#")));
+        assert!(narrow.contains("link  | #| w0 4-5"));
+
+        // A long schedule doesn't make the chart any wider
+        let hours = g.simulate_schedule(2, |t| secs(t) * 3600).unwrap();
+        assert!(hours.gantt(40).lines().all(|l| l.len() < 70));
+
+        let svg = sim.gantt_svg(100);
+        assert_eq!(svg.matches("<rect").count(), 4);
+        assert!(svg.contains("width=\"260\" height=\"96\""));
+        assert!(svg.contains("<rect x=\"180\" y=\"50\" width=\"60\""));
+        assert!(Graph::init(vec!["a<b"])
+            .simulate_schedule(1, |_| 1)
+            .unwrap()
+            .gantt_svg(10)
+            .contains(">a&lt;b</text>"));
+    }
+}
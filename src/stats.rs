@@ -0,0 +1,313 @@
+use crate::{graph::*, hash};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub nodes: usize,
+    pub edges: usize,
+    pub density: f64,
+    pub indegree: DegreeStats,
+    pub outdegree: DegreeStats,
+    pub sources: usize,
+    pub sinks: usize,
+    pub components: usize,
+    pub longest_path: Option<usize>, // None if the graph has a cycle
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DegreeStats {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+}
+
+impl DegreeStats {
+    fn from<I: IntoIterator<Item = usize>>(degrees: I) -> Self {
+        let degrees = degrees.into_iter().collect::<Vec<_>>();
+        let mean = if degrees.is_empty() {
+            0.0
+        } else {
+            degrees.iter().sum::<usize>() as f64 / degrees.len() as f64
+        };
+        DegreeStats {
+            min: degrees.iter().copied().min().unwrap_or(0),
+            max: degrees.iter().copied().max().unwrap_or(0),
+            mean,
+        }
+    }
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    pub fn stats(&self) -> Stats {
+        Stats {
+            nodes: self.size(),
+            edges: self.edge_count(),
+            density: self.density(),
+            indegree: DegreeStats::from(self.indegrees().into_values()),
+            outdegree: DegreeStats::from(self.nodes.values().map(|n| n.edges.len())),
+            sources: self.num_sources(),
+            sinks: self.num_sinks(),
+            components: self.components().len(),
+            longest_path: self.longest_path_len(),
+        }
+    }
+
+    // Index is total (in + out) degree, value is the number of nodes with it
+    pub fn degree_histogram(&self) -> Vec<usize> {
+        let mut histogram = Vec::new();
+        for (key, indegree) in self.indegrees() {
+            let degree = indegree + self.nodes[&key].edges.len();
+            if histogram.len() <= degree {
+                histogram.resize(degree + 1, 0);
+            }
+            histogram[degree] += 1;
+        }
+        histogram
+    }
+
+    pub fn top_k_by<K: Ord, F: Fn(&T) -> K>(&self, metric: F, k: usize) -> Vec<&T> {
+        let mut scored = self
+            .nodes
+            .values()
+            .map(|n| (metric(&n.label), &n.label))
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+        scored.into_iter().take(k).map(|(_, label)| label).collect()
+    }
+
+    // Which sinks each source feeds, an isolated node feeds itself
+    pub fn source_sink_matrix(&self) -> HashMap<&T, HashSet<&T>> {
+        self.nodes
+            .iter()
+            .filter(|(_, n)| n.incoming.is_empty())
+            .map(|(key, node)| {
+                let sinks = self
+                    .hop_distances(*key)
+                    .into_keys()
+                    .map(|k| &self.nodes[&k])
+                    .filter(|n| n.edges.is_empty())
+                    .map(|n| &n.label)
+                    .collect();
+                (&node.label, sinks)
+            })
+            .collect()
+    }
+
+    // Which sources feed each sink, the inverse of source_sink_matrix
+    pub fn sink_source_matrix(&self) -> HashMap<&T, HashSet<&T>> {
+        let mut matrix = self
+            .sinks()
+            .into_iter()
+            .map(|s| (s, HashSet::new()))
+            .collect::<HashMap<_, _>>();
+        for (source, sinks) in self.source_sink_matrix() {
+            for sink in sinks {
+                matrix.get_mut(sink).unwrap().insert(source);
+            }
+        }
+        matrix
+    }
+
+    // Weakly connected components, edge direction is ignored
+    pub fn components(&self) -> Vec<HashSet<&T>> {
+        let undirected = self.undirected();
+        let mut visited = HashSet::new();
+        let mut components = Vec::new();
+        for key in self.nodes.keys() {
+            if !visited.insert(*key) {
+                continue;
+            }
+            let mut component = HashSet::new();
+            let mut stack = vec![*key];
+            while let Some(next) = stack.pop() {
+                component.insert(&self.nodes[&next].label);
+                for other in &undirected[&next] {
+                    if visited.insert(*other) {
+                        stack.push(*other);
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    // Triangles with edge direction ignored
+    pub fn triangles(&self) -> usize {
+        let undirected = self.undirected();
+        let mut count = 0;
+        for (key, neighbours) in &undirected {
+            for other in neighbours.iter().filter(|o| *o > key) {
+                count += neighbours
+                    .intersection(&undirected[other])
+                    .filter(|third| *third > other)
+                    .count();
+            }
+        }
+        count
+    }
+
+    pub fn clustering_coefficient(&self, label: &T) -> Option<f64> {
+        self.get(label)?;
+        let undirected = self.undirected();
+        Some(Self::local_clustering(&undirected, hash(label)))
+    }
+
+    pub fn average_clustering(&self) -> f64 {
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+        let undirected = self.undirected();
+        let total: f64 = undirected
+            .keys()
+            .map(|k| Self::local_clustering(&undirected, *k))
+            .sum();
+        total / self.size() as f64
+    }
+
+    fn local_clustering(undirected: &HashMap<u64, HashSet<u64>>, key: u64) -> f64 {
+        let neighbours = &undirected[&key];
+        let k = neighbours.len();
+        if k < 2 {
+            return 0.0;
+        }
+        let links = neighbours
+            .iter()
+            .map(|n| neighbours.intersection(&undirected[n]).count())
+            .sum::<usize>()
+            / 2;
+        links as f64 / (k * (k - 1) / 2) as f64
+    }
+
+    fn longest_path_len(&self) -> Option<usize> {
+        let mut lengths: HashMap<&T, usize> = HashMap::new();
+        let mut visited = 0;
+        for label in self.ordering() {
+            visited += 1;
+            let length = lengths.get(label).copied().unwrap_or(0);
+            for next in self.connections(label).unwrap() {
+                let entry = lengths.entry(next).or_insert(0);
+                *entry = (*entry).max(length + 1);
+            }
+        }
+
+        if visited == self.size() {
+            Some(lengths.values().copied().max().unwrap_or(0))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_sink_matrix() {
+        // in1 -> clean -> out1, in2 -> clean, in2 -> out2, lone
+        let labels = ["in1", "in2", "clean", "out1", "out2", "lone"];
+        let mut g = Graph::init(labels.iter().copied());
+        for (from, to) in [
+            ("in1", "clean"),
+            ("in2", "clean"),
+            ("clean", "out1"),
+            ("in2", "out2"),
+        ] {
+            assert!(g.connect(&from, &to));
+        }
+
+        let matrix = g.source_sink_matrix();
+        assert_eq!(matrix.len(), 3);
+        assert_eq!(matrix[&"in1"], [&"out1"].iter().copied().collect());
+        assert_eq!(matrix[&"in2"].len(), 2);
+        assert_eq!(matrix[&"lone"], [&"lone"].iter().copied().collect());
+
+        let inverse = g.sink_source_matrix();
+        assert_eq!(inverse.len(), 3);
+        assert_eq!(inverse[&"out1"].len(), 2);
+        assert_eq!(inverse[&"out2"], [&"in2"].iter().copied().collect());
+
+        // Nothing reaches a sink through a cycle with no way in
+        assert!(Graph::cycle_graph(3).source_sink_matrix().is_empty());
+    }
+
+    #[test]
+    fn stats() {
+        let mut g = Graph::init('a'..='f');
+
+        // a -> b -> c   e -> f
+        // a -> c        d
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.connect(&'a', &'c'));
+        assert!(g.connect(&'e', &'f'));
+
+        let stats = g.stats();
+        assert_eq!(stats.nodes, 6);
+        assert_eq!(stats.edges, 4);
+        assert!((stats.density - 4.0 / 30.0).abs() < 1e-9);
+        assert_eq!(stats.outdegree.max, 2);
+        assert_eq!(stats.indegree.max, 2);
+        assert_eq!(stats.indegree.min, 0);
+        assert!((stats.outdegree.mean - 4.0 / 6.0).abs() < 1e-9);
+        assert_eq!(stats.sources, 3);
+        assert_eq!(stats.sinks, 3);
+        assert_eq!(stats.components, 3);
+        assert_eq!(stats.longest_path, Some(2));
+
+        assert!(g.connect(&'c', &'a'));
+        assert_eq!(g.stats().longest_path, None);
+    }
+
+    #[test]
+    fn hubs() {
+        let mut g = Graph::init('a'..='e');
+
+        // a -> b, c, d, e and b -> c
+        for to in 'b'..='e' {
+            assert!(g.connect(&'a', &to));
+        }
+        assert!(g.connect(&'b', &'c'));
+
+        assert_eq!(g.degree_histogram(), vec![0, 2, 2, 0, 1]);
+
+        let top = g.top_k_by(|n| g.outdegree(n).unwrap(), 2);
+        assert_eq!(top, vec![&'a', &'b']);
+
+        let top = g.top_k_by(|n| g.indegree(n).unwrap(), 1);
+        assert_eq!(top, vec![&'c']);
+        assert_eq!(g.top_k_by(|_| 0, 10).len(), 5);
+    }
+
+    #[test]
+    fn clustering() {
+        let mut g = Graph::init('a'..='e');
+
+        // a - b - c - a triangle with d hanging off a, e isolated
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.biconnect(&'c', &'a'));
+        assert!(g.connect(&'d', &'a'));
+
+        assert_eq!(g.triangles(), 1);
+        assert_eq!(g.clustering_coefficient(&'b'), Some(1.0));
+        assert!((g.clustering_coefficient(&'a').unwrap() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(g.clustering_coefficient(&'d'), Some(0.0));
+        assert_eq!(g.clustering_coefficient(&'z'), None);
+        assert!((g.average_clustering() - (1.0 + 1.0 + 1.0 / 3.0) / 5.0).abs() < 1e-9);
+
+        assert_eq!(Graph::complete_graph(4).triangles(), 4);
+    }
+
+    #[test]
+    fn empty() {
+        let g: Graph<char> = Graph::new();
+        let stats = g.stats();
+        assert_eq!(stats.nodes, 0);
+        assert_eq!(stats.density, 0.0);
+        assert_eq!(stats.components, 0);
+        assert_eq!(stats.longest_path, Some(0));
+    }
+}
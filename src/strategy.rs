@@ -0,0 +1,72 @@
+use crate::graph::*;
+use proptest::prelude::*;
+use std::ops::Range;
+
+// Random directed graph over labels 0..n where each ordered pair is
+// connected with probability `density`
+pub fn digraph(nodes: Range<usize>, density: f64) -> impl Strategy<Value = Graph<usize>> {
+    nodes
+        .prop_flat_map(move |n| {
+            let edges = proptest::collection::vec(proptest::bool::weighted(density), n * n);
+            (Just(n), edges)
+        })
+        .prop_map(|(n, edges)| {
+            let mut graph = Graph::init(0..n);
+            for (i, _) in edges.iter().enumerate().filter(|(_, e)| **e) {
+                graph.connect(&(i / n), &(i % n));
+            }
+            graph
+        })
+}
+
+// Random DAG over labels 0..n, edges only run forward in a shuffled
+// order so algorithms can't rely on the labels being sorted
+pub fn dag(nodes: Range<usize>, density: f64) -> impl Strategy<Value = Graph<usize>> {
+    nodes
+        .prop_flat_map(move |n| {
+            let order = Just((0..n).collect::<Vec<_>>()).prop_shuffle();
+            let edges = proptest::collection::vec(proptest::bool::weighted(density), n * n);
+            (order, edges)
+        })
+        .prop_map(|(order, edges)| {
+            let n = order.len();
+            let mut graph = Graph::init(0..n);
+            for (i, _) in edges.iter().enumerate().filter(|(_, e)| **e) {
+                let (a, b) = (i / n, i % n);
+                if a < b {
+                    graph.connect(&order[a], &order[b]);
+                }
+            }
+            graph
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    proptest! {
+        #[test]
+        fn bfs_and_dfs_visit_same_nodes(g in digraph(0..12, 0.3)) {
+            for start in 0..g.nodes.len() {
+                let bredth = g.bfs(&start).collect::<HashSet<_>>();
+                let depth = g.dfs(&start).collect::<HashSet<_>>();
+                prop_assert_eq!(bredth, depth);
+            }
+        }
+
+        #[test]
+        fn edges_match_connections(g in digraph(0..12, 0.3)) {
+            let total: usize = (0..g.nodes.len()).map(|n| g.connections(&n).unwrap().len()).sum();
+            prop_assert_eq!(g.edges().count(), total);
+        }
+
+        #[test]
+        fn dag_has_no_cycles(g in dag(0..12, 0.4)) {
+            for edge in g.edges() {
+                prop_assert!(!g.bfs(edge.to).any(|n| n == edge.from));
+            }
+        }
+    }
+}
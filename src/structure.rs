@@ -0,0 +1,401 @@
+use crate::{graph::*, hash};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::Hash;
+
+impl<T: Hash + Eq> Graph<T> {
+    // Merkle-style hash of every node, covering its label and everything
+    // below it, computed sinks first. None if the graph has a cycle
+    pub fn structural_hashes(&self) -> Option<HashMap<&T, u64>> {
+        let hashes = self
+            .structural_keys()?
+            .into_iter()
+            .map(|(k, h)| (&self.nodes[&k].label, h))
+            .collect();
+        Some(hashes)
+    }
+
+    pub(crate) fn structural_keys(&self) -> Option<HashMap<u64, u64>> {
+        let order = self.ordering().collect::<Vec<_>>();
+        if order.len() != self.size() {
+            return None;
+        }
+        let mut hashes = HashMap::<u64, u64>::new();
+        for label in order.into_iter().rev() {
+            let key = hash(label);
+            let mut children = self.nodes[&key]
+                .edges
+                .iter()
+                .map(|(t, w)| (hashes[t], *w))
+                .collect::<Vec<_>>();
+            children.sort_unstable();
+            hashes.insert(key, hash(&(key, children)));
+        }
+        Some(hashes)
+    }
+
+    // Hash of the whole graph's labels, edges and weights, independent of
+    // insertion order. None if the graph has a cycle
+    pub fn structural_hash(&self) -> Option<u64> {
+        let mut hashes = self.structural_hashes()?.into_values().collect::<Vec<_>>();
+        hashes.sort_unstable();
+        Some(hash(&hashes))
+    }
+
+    // Same labels joined by the same edges with the same weights, however
+    // the two graphs were built
+    pub fn same_structure(&self, other: &Graph<T>) -> bool {
+        self.size() == other.size()
+            && self
+                .nodes
+                .iter()
+                .all(|(key, node)| match other.nodes.get(key) {
+                    Some(theirs) => {
+                        theirs.label == node.label
+                            && theirs.edges.len() == node.edges.len()
+                            && node
+                                .edges
+                                .iter()
+                                .all(|(t, w)| theirs.edges.get(t) == Some(w))
+                    }
+                    None => false,
+                })
+    }
+
+    // Some relabelling turns one into the other. Unlike is_isomorphic,
+    // edge weights have to match as well
+    pub fn isomorphic_to<U: Hash + Eq>(&self, other: &Graph<U>) -> bool {
+        self.is_isomorphic_matching(other, |_, _| true, |a, b| a == b)
+    }
+
+    // Best-effort correspondence between the nodes of two graphs, e.g. two
+    // versions of the same pipeline. Pairs are matched greedily, most
+    // similar first, with closer in and out degrees breaking ties. Nodes
+    // are matched at most once and never with a similarity of 0 or less
+    pub fn align<'a, U, F>(&'a self, other: &'a Graph<U>, similarity: F) -> HashMap<&'a T, &'a U>
+    where
+        U: Hash + Eq,
+        F: Fn(&T, &U) -> f64,
+    {
+        let mut pairs = Vec::new();
+        for (a, ours) in &self.nodes {
+            for (b, theirs) in &other.nodes {
+                let score = similarity(&ours.label, &theirs.label);
+                if score > 0.0 {
+                    let apart = ours.edges.len().abs_diff(theirs.edges.len())
+                        + ours.incoming.len().abs_diff(theirs.incoming.len());
+                    pairs.push((score, apart, *a, *b));
+                }
+            }
+        }
+        pairs.sort_unstable_by(|x, y| {
+            let score = y.0.partial_cmp(&x.0).unwrap_or(Ordering::Equal);
+            score.then((x.1, x.2, x.3).cmp(&(y.1, y.2, y.3)))
+        });
+
+        let mut taken = HashSet::new();
+        let mut aligned = HashMap::new();
+        for (_, _, a, b) in pairs {
+            let label = &self.nodes[&a].label;
+            if !aligned.contains_key(label) && taken.insert(b) {
+                aligned.insert(label, &other.nodes[&b].label);
+            }
+        }
+        aligned
+    }
+}
+
+// Which of the two compared graphs something was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Difference<'a, T> {
+    Node {
+        label: &'a T,
+        only_in: Side,
+    },
+    Edge {
+        from: &'a T,
+        to: &'a T,
+        weight: i64,
+        only_in: Side,
+    },
+    Weight {
+        from: &'a T,
+        to: &'a T,
+        left: i64,
+        right: i64,
+    },
+}
+
+// Everything that stops two graphs being equal, nodes before edges
+#[derive(Debug, PartialEq)]
+pub struct DifferenceReport<'a, T> {
+    pub differences: Vec<Difference<'a, T>>,
+}
+
+impl<T: Debug> Display for Difference<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let side = |s: &Side| match s {
+            Side::Left => "left",
+            Side::Right => "right",
+        };
+        match self {
+            Difference::Node { label, only_in } => {
+                write!(f, "node {:?} only in {}", label, side(only_in))
+            }
+            Difference::Edge {
+                from,
+                to,
+                weight,
+                only_in,
+            } => write!(
+                f,
+                "edge {:?} -> {:?} (weight {}) only in {}",
+                from,
+                to,
+                weight,
+                side(only_in)
+            ),
+            Difference::Weight {
+                from,
+                to,
+                left,
+                right,
+            } => write!(
+                f,
+                "edge {:?} -> {:?} weighs {} in left but {} in right",
+                from, to, left, right
+            ),
+        }
+    }
+}
+
+impl<T: Debug> Display for DifferenceReport<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for difference in &self.differences {
+            writeln!(f, "{}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Hash + Eq + Debug> Graph<T> {
+    // None if the graphs are equal, otherwise what differs. Edges touching
+    // a node only one side has aren't listed on top of the node. The order
+    // is fixed, by kind and then by the Debug output of the labels
+    pub fn explain_difference<'a>(
+        &'a self,
+        other: &'a Graph<T>,
+    ) -> Option<DifferenceReport<'a, T>> {
+        let mut differences = Vec::new();
+        for (left, right, side) in [(self, other, Side::Left), (other, self, Side::Right)] {
+            for (key, node) in &left.nodes {
+                let Some(theirs) = right.nodes.get(key) else {
+                    differences.push(Difference::Node {
+                        label: &node.label,
+                        only_in: side,
+                    });
+                    continue;
+                };
+                for (to, weight) in &node.edges {
+                    if !right.nodes.contains_key(to) {
+                        continue;
+                    }
+                    let (from, to) = (&node.label, &left.nodes[to].label);
+                    match theirs.edges.get(&hash(to)) {
+                        None => differences.push(Difference::Edge {
+                            from,
+                            to,
+                            weight: *weight,
+                            only_in: side,
+                        }),
+                        Some(theirs) if side == Side::Left && theirs != weight => {
+                            differences.push(Difference::Weight {
+                                from,
+                                to,
+                                left: *weight,
+                                right: *theirs,
+                            })
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+        if differences.is_empty() {
+            return None;
+        }
+        differences.sort_by_cached_key(|d| {
+            let rank = match d {
+                Difference::Node { .. } => 0,
+                _ => 1,
+            };
+            (rank, d.to_string())
+        });
+        Some(DifferenceReport { differences })
+    }
+}
+
+// Panics with the differences listed, rather than both graphs' Debug output
+#[macro_export]
+macro_rules! assert_graph_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        if let Some(report) = $left.explain_difference(&$right) {
+            panic!("graphs differ:\n{}", report);
+        }
+    };
+}
+
+// Equal when they have the same structure, see same_structure
+impl<T: Hash + Eq> PartialEq for Graph<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_structure(other)
+    }
+}
+
+impl<T: Hash + Eq> Eq for Graph<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(order: &[(&'static str, &'static str)]) -> Graph<&'static str> {
+        let mut g = Graph::new();
+        for (from, to) in order {
+            g.add(*from);
+            g.add(*to);
+        }
+        for (from, to) in order {
+            assert!(g.connect(from, to));
+        }
+        g
+    }
+
+    #[test]
+    fn structural_hash() {
+        let edges = [
+            ("app", "lib"),
+            ("app", "util"),
+            ("lib", "util"),
+            ("test", "lib"),
+        ];
+        let g = plan(&edges);
+        let mut reversed = edges;
+        reversed.reverse();
+        assert_eq!(g.structural_hash(), plan(&reversed).structural_hash());
+
+        // Nodes above the removed edge change, util below it doesn't
+        let mut h = plan(&edges);
+        assert!(h.disconnect(&"lib", &"util"));
+        assert_ne!(g.structural_hash(), h.structural_hash());
+        let (before, after) = (
+            g.structural_hashes().unwrap(),
+            h.structural_hashes().unwrap(),
+        );
+        assert_eq!(before[&"util"], after[&"util"]);
+        assert_ne!(before[&"lib"], after[&"lib"]);
+        assert_ne!(before[&"test"], after[&"test"]);
+
+        h = plan(&edges);
+        assert_eq!(g.structural_hash(), h.structural_hash());
+        h.add("extra");
+        assert_ne!(g.structural_hash(), h.structural_hash());
+
+        assert!(h.connect(&"util", &"app"));
+        assert!(h.structural_hash().is_none());
+    }
+
+    #[test]
+    fn equality() {
+        let edges = [("a", "b"), ("b", "c"), ("a", "c")];
+        let g = plan(&edges);
+        let mut reversed = edges;
+        reversed.reverse();
+        let mut h = plan(&reversed);
+        assert!(g.same_structure(&h));
+        assert_eq!(g, h);
+
+        // A self-loop on either side breaks both kinds of equality
+        assert!(h.connect(&"c", &"c"));
+        assert!(!g.same_structure(&h) && !g.isomorphic_to(&h));
+        assert!(h.disconnect(&"c", &"c"));
+
+        // Same shape under other labels
+        let mut other = Graph::init(0..3);
+        for (from, to) in [(2, 1), (1, 0), (2, 0)] {
+            assert!(other.connect(&from, &to));
+        }
+        assert!(g.isomorphic_to(&other));
+        let relabelled = plan(&[("x", "b"), ("b", "c"), ("x", "c")]);
+        assert!(g.isomorphic_to(&relabelled));
+        assert_ne!(g, relabelled);
+
+        // Weights count for both
+        h.set_edge(hash(&"a"), hash(&"b"), Some(3));
+        assert_ne!(g, h);
+        assert!(!g.isomorphic_to(&h));
+        assert!(g.is_isomorphic(&h));
+    }
+
+    #[test]
+    fn align() {
+        let old = plan(&[
+            ("load_csv", "clean"),
+            ("clean", "train_model"),
+            ("train_model", "report"),
+        ]);
+        let mut new = plan(&[
+            ("load_csv_v2", "clean_a"),
+            ("clean_a", "train"),
+            ("train", "report"),
+        ]);
+        new.add("clean_b");
+        new.add("notify");
+
+        // Shared prefix as a share of the longer label
+        let prefix = |a: &&str, b: &&str| {
+            let shared = a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+            shared as f64 / a.len().max(b.len()) as f64
+        };
+        let aligned = old.align(&new, prefix);
+        assert_eq!(aligned.len(), 4);
+        assert_eq!(aligned[&"load_csv"], &"load_csv_v2");
+        assert_eq!(aligned[&"train_model"], &"train");
+        assert_eq!(aligned[&"report"], &"report");
+        // Equally similar, but only clean_a sits in the same place
+        assert_eq!(aligned[&"clean"], &"clean_a");
+
+        assert!(old.align(&new, |_, _| 0.0).is_empty());
+    }
+
+    #[test]
+    fn explain_difference() {
+        let edges = [("a", "b"), ("b", "c"), ("a", "c")];
+        let g = plan(&edges);
+        let mut h = plan(&edges);
+        assert!(g.explain_difference(&h).is_none());
+        crate::assert_graph_eq!(g, h);
+
+        h.set_edge(hash(&"a"), hash(&"b"), Some(3));
+        assert!(h.disconnect(&"a", &"c"));
+        assert!(h.connect(&"c", &"a"));
+        h.add("d");
+        assert!(h.connect(&"d", &"a"));
+        let report = g.explain_difference(&h).unwrap();
+        assert_eq!(report.differences.len(), 4);
+        assert_eq!(
+            report.to_string(),
+            "node \"d\" only in right\n\
+             edge \"a\" -> \"b\" weighs 1 in left but 3 in right\n\
+             edge \"a\" -> \"c\" (weight 1) only in left\n\
+             edge \"c\" -> \"a\" (weight 1) only in right\n"
+        );
+        assert!(std::panic::catch_unwind(|| crate::assert_graph_eq!(g, h)).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use crate::graph::*;
+use std::hash::Hash;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+// Graph shared between threads, any number of readers or a single writer.
+// Hold the guard from read() to use the full traversal API
+#[derive(Debug, Default)]
+pub struct SyncGraph<T> {
+    inner: RwLock<Graph<T>>,
+}
+
+impl<T> From<Graph<T>> for SyncGraph<T> {
+    fn from(graph: Graph<T>) -> Self {
+        SyncGraph {
+            inner: RwLock::new(graph),
+        }
+    }
+}
+
+impl<T> SyncGraph<T> {
+    pub fn new() -> Self {
+        Graph::new().into()
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, Graph<T>> {
+        self.inner.read().unwrap()
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, Graph<T>> {
+        self.inner.write().unwrap()
+    }
+
+    pub fn size(&self) -> usize {
+        self.read().size()
+    }
+
+    pub fn into_inner(self) -> Graph<T> {
+        self.inner.into_inner().unwrap()
+    }
+}
+
+impl<T: Hash + Eq> SyncGraph<T> {
+    pub fn add(&self, label: T) {
+        self.write().add(label);
+    }
+
+    pub fn remove(&self, label: &T) -> bool {
+        self.write().remove(label).is_some()
+    }
+
+    pub fn connect(&self, from: &T, to: &T) -> bool {
+        self.write().connect(from, to)
+    }
+
+    pub fn disconnect(&self, from: &T, to: &T) -> bool {
+        self.write().disconnect(from, to)
+    }
+
+    pub fn is_connected(&self, from: &T, to: &T) -> bool {
+        self.read().is_connected(from, to)
+    }
+
+    pub fn reaches(&self, from: &T, to: &T) -> bool {
+        self.read().bfs(from).any(|n| n == to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn concurrent_reads_and_writes() {
+        let g = SyncGraph::from(Graph::init(0..100));
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 1..100 {
+                    assert!(g.connect(&(i - 1), &i));
+                }
+            });
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 0..100 {
+                        // Whatever has been written so far is a prefix of the chain
+                        let reached = g.read().bfs(&0).count();
+                        assert!((1..=100).contains(&reached));
+                        assert!(!g.reaches(&i, &0) || i == 0);
+                    }
+                });
+            }
+        });
+
+        assert!(g.reaches(&0, &99));
+        assert!(g.remove(&50));
+        assert!(!g.reaches(&0, &99));
+        assert_eq!(g.into_inner().size(), 99);
+    }
+}
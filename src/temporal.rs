@@ -0,0 +1,272 @@
+use crate::{base::GraphBase, graph::*, hash};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::ops::Deref;
+
+// Time range an edge exists for, from inclusive and to exclusive. An
+// open-ended span never expires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub from: i64,
+    pub to: Option<i64>,
+}
+
+impl Span {
+    pub fn always() -> Self {
+        Span {
+            from: i64::MIN,
+            to: None,
+        }
+    }
+
+    pub fn contains(&self, time: i64) -> bool {
+        self.from <= time && self.to.is_none_or(|to| time < to)
+    }
+}
+
+// Graph wrapper where every edge is only valid for a span of time. The
+// wrapped graph holds every edge regardless of when it's valid
+#[derive(Debug)]
+pub struct TemporalGraph<T> {
+    graph: Graph<T>,
+    spans: HashMap<(u64, u64), Span>,
+}
+
+// The temporal graph as it was at a single point in time
+pub struct SnapshotView<'a, T> {
+    graph: &'a TemporalGraph<T>,
+    time: i64,
+}
+
+impl<T> Default for TemporalGraph<T> {
+    fn default() -> Self {
+        Graph::new().into()
+    }
+}
+
+// Existing edges are taken to have always existed
+impl<T> From<Graph<T>> for TemporalGraph<T> {
+    fn from(graph: Graph<T>) -> Self {
+        let spans = graph
+            .nodes
+            .iter()
+            .flat_map(|(k, n)| n.edges.keys().map(move |t| ((*k, *t), Span::always())))
+            .collect();
+        TemporalGraph { graph, spans }
+    }
+}
+
+impl<T> Deref for TemporalGraph<T> {
+    type Target = Graph<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T> TemporalGraph<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn into_inner(self) -> Graph<T> {
+        self.graph
+    }
+
+    pub fn snapshot_at(&self, time: i64) -> SnapshotView<'_, T> {
+        SnapshotView { graph: self, time }
+    }
+}
+
+impl<T: Hash + Eq> TemporalGraph<T> {
+    // Replacing a node drops its outgoing edges, so their spans go too
+    pub fn add(&mut self, label: T) {
+        let key = hash(&label);
+        if let Some(node) = self.graph.nodes.get(&key) {
+            for to in node.edges.keys() {
+                self.spans.remove(&(key, *to));
+            }
+        }
+        self.graph.add(label);
+    }
+
+    pub fn remove(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        let Some((node, incoming)) = self.graph.remove_node(key) else {
+            return false;
+        };
+        for to in node.edges.keys() {
+            self.spans.remove(&(key, *to));
+        }
+        for (from, _) in incoming {
+            self.spans.remove(&(from, key));
+        }
+        true
+    }
+
+    pub fn connect(&mut self, from: &T, to: &T) -> bool {
+        self.connect_during(from, to, Span::always())
+    }
+
+    // Replaces the span if the edge already exists
+    pub fn connect_during(&mut self, from: &T, to: &T, span: Span) -> bool {
+        if !self.graph.connect(from, to) {
+            return false;
+        }
+        self.spans.insert((hash(from), hash(to)), span);
+        true
+    }
+
+    pub fn disconnect(&mut self, from: &T, to: &T) -> bool {
+        self.spans.remove(&(hash(from), hash(to)));
+        self.graph.disconnect(from, to)
+    }
+
+    pub fn span(&self, from: &T, to: &T) -> Option<Span> {
+        self.spans.get(&(hash(from), hash(to))).copied()
+    }
+
+    // Earliest time `to` can be reached setting off from `from` at start,
+    // along with the path taken. Edges are crossed instantly but only
+    // while they exist, so waiting at a node for an edge to appear is fine
+    pub fn earliest_arrival(&self, from: &T, to: &T, start: i64) -> Option<(i64, Vec<&T>)> {
+        self.graph.get(from)?;
+        let (source, target) = (hash(from), hash(to));
+        let mut settled = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        heap.push(Reverse((start, source, source)));
+        while let Some(Reverse((time, key, previous))) = heap.pop() {
+            if settled.contains_key(&key) {
+                continue;
+            }
+            settled.insert(key, previous);
+            if key == target {
+                let mut path = vec![&self.graph.nodes[&key].label];
+                let mut current = key;
+                while current != source {
+                    current = settled[&current];
+                    path.push(&self.graph.nodes[&current].label);
+                }
+                path.reverse();
+                return Some((time, path));
+            }
+            for next in self.graph.nodes[&key].edges.keys() {
+                let span = self.spans[&(key, *next)];
+                let depart = time.max(span.from);
+                if span.contains(depart) && !settled.contains_key(next) {
+                    heap.push(Reverse((depart, *next, key)));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for TemporalGraph<T> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for SnapshotView<'_, T> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        let mut neighbors = self.graph.neighbors(label);
+        neighbors.retain(|n| self.weight(label, n).is_some());
+        neighbors
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        let span = self.graph.span(from, to)?;
+        if span.contains(self.time) {
+            self.graph.weight(from, to)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(from: i64, to: Option<i64>) -> Span {
+        Span { from, to }
+    }
+
+    #[test]
+    fn snapshots() {
+        let mut g = TemporalGraph::from(Graph::init('a'..='d'));
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect_during(&'b', &'c', span(10, Some(20))));
+        assert!(g.connect_during(&'c', &'d', span(15, None)));
+        assert!(!g.connect_during(&'c', &'z', span(0, None)));
+
+        assert_eq!(g.snapshot_at(5).bfs(&'a').count(), 2);
+        assert_eq!(g.snapshot_at(12).bfs(&'a').count(), 3);
+        assert_eq!(g.snapshot_at(17).bfs(&'a').count(), 4);
+        assert_eq!(g.snapshot_at(20).bfs(&'a').count(), 2);
+        assert_eq!(g.snapshot_at(20).weight(&'c', &'d'), Some(1));
+        assert_eq!(g.bfs(&'a').count(), 4);
+
+        assert!(g.disconnect(&'a', &'b'));
+        assert!(g.span(&'a', &'b').is_none());
+        assert!(g.remove(&'c'));
+        assert!(g.span(&'b', &'c').is_none());
+
+        // Re-adding keeps incoming edges but not outgoing ones
+        assert!(g.connect(&'a', &'b'));
+        assert!(g.connect_during(&'b', &'d', span(1, None)));
+        g.add('b');
+        assert!(g.span(&'b', &'d').is_none());
+        assert_eq!(g.span(&'a', &'b'), Some(Span::always()));
+    }
+
+    #[test]
+    fn earliest_arrival() {
+        let mut g = TemporalGraph::from(Graph::init('a'..='e'));
+        assert!(g.connect_during(&'a', &'b', span(0, Some(5))));
+        assert!(g.connect_during(&'b', &'c', span(8, Some(10))));
+        assert!(g.connect_during(&'a', &'d', span(0, None)));
+        assert!(g.connect_during(&'d', &'c', span(12, None)));
+        assert!(g.connect_during(&'c', &'e', span(0, Some(9))));
+
+        // Waits at b for b -> c to open
+        assert_eq!(
+            g.earliest_arrival(&'a', &'c', 0),
+            Some((8, vec![&'a', &'b', &'c']))
+        );
+        assert_eq!(
+            g.earliest_arrival(&'a', &'e', 0),
+            Some((8, vec![&'a', &'b', &'c', &'e']))
+        );
+
+        // Too late for a -> b, so it has to go round through d
+        assert_eq!(
+            g.earliest_arrival(&'a', &'c', 6),
+            Some((12, vec![&'a', &'d', &'c']))
+        );
+        assert_eq!(g.earliest_arrival(&'a', &'e', 6), None);
+        assert_eq!(g.earliest_arrival(&'a', &'a', 3), Some((3, vec![&'a'])));
+    }
+}
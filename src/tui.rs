@@ -0,0 +1,417 @@
+use crate::graph::*;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::io;
+
+const PAGE: usize = 10;
+
+// Opens a full screen browser over the graph until q is pressed
+pub fn browse<T: Hash + Eq + Display>(graph: &Graph<T>) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = run(&mut terminal, Browser::new(graph));
+    ratatui::restore();
+    result
+}
+
+fn run<T: Hash + Eq + Display>(
+    terminal: &mut DefaultTerminal,
+    mut browser: Browser<'_, T>,
+) -> io::Result<()> {
+    while !browser.is_done() {
+        terminal.draw(|frame| browser.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                browser.handle(key.code);
+            }
+        }
+    }
+    Ok(())
+}
+
+// One visible line of the tree, identified by the path of keys from its
+// top level node so the same node can be expanded under different parents
+struct Row {
+    path: Vec<u64>,
+}
+
+impl Row {
+    fn key(&self) -> u64 {
+        *self.path.last().unwrap()
+    }
+
+    fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+// Browser state kept apart from the terminal, so it can be driven by keys
+// and drawn to any backend
+pub struct Browser<'a, T> {
+    graph: &'a Graph<T>,
+    order: Vec<(String, u64)>,
+    query: String,
+    searching: bool,
+    expanded: HashSet<Vec<u64>>,
+    rows: Vec<Row>,
+    list: ListState,
+    done: bool,
+}
+
+impl<'a, T: Hash + Eq + Display> Browser<'a, T> {
+    pub fn new(graph: &'a Graph<T>) -> Self {
+        let mut order = graph
+            .nodes
+            .iter()
+            .map(|(k, n)| (n.label.to_string(), *k))
+            .collect::<Vec<_>>();
+        order.sort_unstable();
+        let mut browser = Browser {
+            graph,
+            order,
+            query: String::new(),
+            searching: false,
+            expanded: HashSet::new(),
+            rows: Vec::new(),
+            list: ListState::default(),
+            done: false,
+        };
+        browser.refresh();
+        browser
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn selected(&self) -> Option<&'a T> {
+        let row = &self.rows[self.list.selected()?];
+        Some(&self.graph.nodes[&row.key()].label)
+    }
+
+    pub fn handle(&mut self, key: KeyCode) {
+        if self.searching {
+            match key {
+                KeyCode::Char(c) => self.query.push(c),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.query.clear();
+                    self.searching = false;
+                }
+                _ => return,
+            }
+            self.refresh();
+            return;
+        }
+
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => self.done = true,
+            KeyCode::Up | KeyCode::Char('k') => self.step(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.step(1),
+            KeyCode::PageUp => self.step(-(PAGE as isize)),
+            KeyCode::PageDown => self.step(PAGE as isize),
+            KeyCode::Home => self.select(0),
+            KeyCode::End => self.select(usize::MAX),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char(' ') => self.expand(),
+            KeyCode::Left | KeyCode::Char('h') => self.collapse(),
+            KeyCode::Enter => self.follow(),
+            KeyCode::Char('/') => {
+                self.searching = true;
+                self.query.clear();
+                self.refresh();
+            }
+            _ => (),
+        }
+    }
+
+    fn step(&mut self, by: isize) {
+        let current = self.list.selected().unwrap_or(0) as isize;
+        self.select((current + by).max(0) as usize);
+    }
+
+    fn select(&mut self, index: usize) {
+        let last = self.rows.len().checked_sub(1);
+        self.list.select(last.map(|last| index.min(last)));
+    }
+
+    fn expand(&mut self) {
+        if let Some(index) = self.list.selected() {
+            let row = &self.rows[index];
+            if !self.graph.nodes[&row.key()].edges.is_empty() {
+                self.expanded.insert(row.path.clone());
+                self.refresh();
+            }
+        }
+    }
+
+    // Closes the selected row, or moves up to its parent if it's closed
+    fn collapse(&mut self) {
+        let Some(index) = self.list.selected() else {
+            return;
+        };
+        let path = self.rows[index].path.clone();
+        if self.expanded.remove(&path) {
+            self.refresh();
+        } else if path.len() > 1 {
+            let parent = &path[..path.len() - 1];
+            if let Some(index) = self.rows.iter().position(|r| r.path == parent) {
+                self.select(index);
+            }
+        }
+    }
+
+    // Jumps from a neighbour in the tree to that node's own top level row
+    fn follow(&mut self) {
+        let Some(index) = self.list.selected() else {
+            return;
+        };
+        let key = self.rows[index].key();
+        if !self.rows.iter().any(|r| r.path == [key]) {
+            self.query.clear();
+            self.refresh();
+        }
+        if let Some(index) = self.rows.iter().position(|r| r.path == [key]) {
+            self.select(index);
+        }
+    }
+
+    // Rebuilds the visible rows, keeping the selection on the same row if
+    // it's still there
+    fn refresh(&mut self) {
+        let selected = self
+            .list
+            .selected()
+            .and_then(|i| self.rows.get(i))
+            .map(|r| r.path.clone());
+        let roots = self
+            .order
+            .iter()
+            .filter(|(label, _)| label.contains(&self.query))
+            .map(|(_, k)| *k)
+            .collect::<Vec<_>>();
+        let mut rows = Vec::new();
+        for root in roots {
+            self.push_rows(&mut rows, vec![root]);
+        }
+        self.rows = rows;
+
+        let index = selected.and_then(|p| self.rows.iter().position(|r| r.path == p));
+        self.select(index.or(self.list.selected()).unwrap_or(0));
+    }
+
+    // Nodes already on the path aren't opened again, so cycles stay finite
+    fn push_rows(&self, rows: &mut Vec<Row>, path: Vec<u64>) {
+        let key = *path.last().unwrap();
+        let open = self.expanded.contains(&path);
+        rows.push(Row { path: path.clone() });
+        if !open {
+            return;
+        }
+        let mut children = self.graph.nodes[&key]
+            .edges
+            .keys()
+            .map(|k| (self.graph.nodes[k].label.to_string(), *k))
+            .collect::<Vec<_>>();
+        children.sort_unstable();
+        for (_, child) in children {
+            let mut child_path = path.clone();
+            child_path.push(child);
+            if path.contains(&child) {
+                rows.push(Row { path: child_path });
+            } else {
+                self.push_rows(rows, child_path);
+            }
+        }
+    }
+
+    fn line(&self, row: &Row) -> String {
+        let node = &self.graph.nodes[&row.key()];
+        let looped = row.path[..row.depth()].contains(&row.key());
+        let marker = if looped {
+            "↻ "
+        } else if node.edges.is_empty() {
+            "  "
+        } else if self.expanded.contains(&row.path) {
+            "▾ "
+        } else {
+            "▸ "
+        };
+        format!("{}{}{}", "  ".repeat(row.depth()), marker, node.label)
+    }
+
+    fn details(&self) -> Vec<Line<'static>> {
+        let Some(index) = self.list.selected() else {
+            return Vec::new();
+        };
+        let node = &self.graph.nodes[&self.rows[index].key()];
+        let labels = |keys: Vec<&u64>| {
+            let mut labels = keys
+                .into_iter()
+                .map(|k| self.graph.nodes[k].label.to_string())
+                .collect::<Vec<_>>();
+            labels.sort_unstable();
+            labels
+        };
+        let mut lines = vec![
+            Line::from(node.label.to_string()),
+            Line::from(format!(
+                "in {}  out {}",
+                node.incoming.len(),
+                node.edges.len()
+            )),
+        ];
+        for label in labels(node.edges.keys().collect()) {
+            lines.push(Line::from(format!("→ {}", label)));
+        }
+        for label in labels(node.incoming.keys().collect()) {
+            lines.push(Line::from(format!("← {}", label)));
+        }
+        lines
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [tree, side] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(main);
+
+        let items = self
+            .rows
+            .iter()
+            .map(|r| ListItem::new(self.line(r)))
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("nodes"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, tree, &mut self.list);
+
+        let details = Paragraph::new(self.details())
+            .block(Block::default().borders(Borders::ALL).title("edges"));
+        frame.render_widget(details, side);
+
+        let status = if self.searching || !self.query.is_empty() {
+            format!("/{}", self.query)
+        } else {
+            "↑↓ move  → open  ← close  enter follow  / search  q quit".to_owned()
+        };
+        frame.render_widget(Paragraph::new(status), footer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn graph() -> Graph<String> {
+        let labels = ["api", "db", "cache", "web"];
+        let mut g = Graph::init(labels.iter().map(|l| l.to_string()));
+        for (a, b) in [
+            ("web", "api"),
+            ("api", "db"),
+            ("api", "cache"),
+            ("db", "api"),
+        ] {
+            assert!(g.connect(&a.to_owned(), &b.to_owned()));
+        }
+        g
+    }
+
+    fn visible<T: Hash + Eq + Display>(browser: &Browser<'_, T>) -> Vec<String> {
+        browser.rows.iter().map(|r| browser.line(r)).collect()
+    }
+
+    #[test]
+    fn navigation() {
+        let g = graph();
+        let mut b = Browser::new(&g);
+        assert_eq!(visible(&b), ["▸ api", "  cache", "▸ db", "▸ web"]);
+
+        b.handle(KeyCode::End);
+        assert_eq!(b.selected().unwrap(), "web");
+        b.handle(KeyCode::Right);
+        b.handle(KeyCode::Down);
+        b.handle(KeyCode::Right);
+        b.handle(KeyCode::Down);
+        b.handle(KeyCode::Down);
+        b.handle(KeyCode::Right);
+        assert_eq!(
+            visible(&b)[3..],
+            ["▾ web", "  ▾ api", "      cache", "    ▾ db", "      ↻ api"]
+        );
+
+        // Left closes, then moves up to the parent
+        b.handle(KeyCode::Left);
+        assert_eq!(visible(&b).len(), 7);
+        b.handle(KeyCode::Left);
+        assert_eq!(b.selected().unwrap(), "api");
+        b.handle(KeyCode::Enter);
+        assert_eq!(b.list.selected(), Some(0));
+
+        b.handle(KeyCode::PageDown);
+        assert_eq!(b.selected().unwrap(), "db");
+        b.handle(KeyCode::Char('q'));
+        assert!(b.is_done());
+    }
+
+    #[test]
+    fn search() {
+        let g = graph();
+        let mut b = Browser::new(&g);
+        b.handle(KeyCode::Char('/'));
+        b.handle(KeyCode::Char('a'));
+        assert_eq!(visible(&b), vec!["▸ api", "  cache"]);
+        b.handle(KeyCode::Char('p'));
+        b.handle(KeyCode::Enter);
+        assert_eq!(visible(&b), vec!["▸ api"]);
+
+        // Following an edge out of the results clears the search
+        b.handle(KeyCode::Right);
+        b.handle(KeyCode::Down);
+        b.handle(KeyCode::Down);
+        assert_eq!(b.selected().unwrap(), "db");
+        b.handle(KeyCode::Enter);
+        assert_eq!(b.query, "");
+        assert_eq!(b.selected().unwrap(), "db");
+        assert_eq!(b.rows[b.list.selected().unwrap()].depth(), 0);
+
+        b.handle(KeyCode::Char('/'));
+        b.handle(KeyCode::Char('z'));
+        assert!(b.selected().is_none());
+        b.handle(KeyCode::Esc);
+        assert_eq!(visible(&b).len(), 6);
+    }
+
+    #[test]
+    fn draws() {
+        let g = graph();
+        let mut b = Browser::new(&g);
+        b.handle(KeyCode::Char('j'));
+        b.handle(KeyCode::Char('j'));
+        let mut terminal = Terminal::new(TestBackend::new(60, 8)).unwrap();
+        terminal.draw(|frame| b.draw(frame)).unwrap();
+
+        let screen = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect::<String>();
+        assert!(screen.contains("▸ web"));
+        assert!(screen.contains("in 1  out 1"));
+        assert!(screen.contains("→ api"));
+        assert!(screen.contains("← api"));
+    }
+}
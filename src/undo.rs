@@ -0,0 +1,302 @@
+use crate::{base::GraphBase, edges::Edges, graph::*, hash};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Deref;
+
+// Graph wrapper that journals every mutation so it can be rolled back
+#[derive(Debug)]
+pub struct UndoableGraph<T> {
+    graph: Graph<T>,
+    undo: Vec<Vec<Change<T>>>,
+    redo: Vec<Vec<Change<T>>>,
+    checkpoints: HashMap<String, usize>,
+}
+
+// Primitive edits, applying one returns the edit that reverses it
+#[derive(Debug)]
+enum Change<T> {
+    Insert {
+        key: u64,
+        node: Node<T>,
+        incoming: Incoming,
+    },
+    Remove {
+        key: u64,
+    },
+    SetEdge {
+        from: u64,
+        to: u64,
+        weight: Option<i64>,
+    },
+}
+
+impl<T> Default for UndoableGraph<T> {
+    fn default() -> Self {
+        Graph::new().into()
+    }
+}
+
+impl<T> From<Graph<T>> for UndoableGraph<T> {
+    fn from(graph: Graph<T>) -> Self {
+        UndoableGraph {
+            graph,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            checkpoints: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Deref for UndoableGraph<T> {
+    type Target = Graph<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T> UndoableGraph<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn into_inner(self) -> Graph<T> {
+        self.graph
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(changes) => {
+                let inverse = self.apply_all(changes);
+                self.redo.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(changes) => {
+                let inverse = self.apply_all(changes);
+                self.undo.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn checkpoint(&mut self, name: &str) {
+        self.checkpoints.insert(name.to_owned(), self.undo.len());
+    }
+
+    // Undoes back to the named checkpoint, false if it's unknown or has
+    // already been undone past
+    pub fn rollback(&mut self, name: &str) -> bool {
+        match self.checkpoints.get(name).copied() {
+            Some(mark) if mark <= self.undo.len() => {
+                while self.undo.len() > mark {
+                    self.undo();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn record(&mut self, changes: Vec<Change<T>>) {
+        let inverse = self.apply_all(changes);
+        self.undo.push(inverse);
+        self.redo.clear();
+        let len = self.undo.len();
+        self.checkpoints.retain(|_, mark| *mark < len);
+    }
+
+    fn apply_all(&mut self, changes: Vec<Change<T>>) -> Vec<Change<T>> {
+        let mut inverse = changes
+            .into_iter()
+            .map(|c| self.apply(c))
+            .collect::<Vec<_>>();
+        inverse.reverse();
+        inverse
+    }
+
+    fn apply(&mut self, change: Change<T>) -> Change<T> {
+        let graph = &mut self.graph;
+        match change {
+            Change::Insert {
+                key,
+                node,
+                incoming,
+            } => {
+                let previous = graph.insert_node(key, node);
+                for (from, weight) in incoming {
+                    graph.set_edge(from, key, Some(weight));
+                }
+                match previous {
+                    Some(node) => Change::Insert {
+                        key,
+                        node,
+                        incoming: Vec::new(),
+                    },
+                    None => Change::Remove { key },
+                }
+            }
+            Change::Remove { key } => {
+                let (node, incoming) = graph.remove_node(key).unwrap();
+                Change::Insert {
+                    key,
+                    node,
+                    incoming,
+                }
+            }
+            Change::SetEdge { from, to, weight } => {
+                let previous = graph.set_edge(from, to, weight);
+                Change::SetEdge {
+                    from,
+                    to,
+                    weight: previous,
+                }
+            }
+        }
+    }
+}
+
+impl<T: Hash + Eq> UndoableGraph<T> {
+    pub fn add(&mut self, label: T) {
+        let key = hash(&label);
+        let node = Node {
+            label,
+            edges: Edges::new(),
+            incoming: Edges::new(),
+        };
+        let incoming = Vec::new();
+        self.record(vec![Change::Insert {
+            key,
+            node,
+            incoming,
+        }]);
+    }
+
+    pub fn remove(&mut self, label: &T) -> bool {
+        let key = hash(label);
+        if !self.graph.nodes.contains_key(&key) {
+            return false;
+        }
+        self.record(vec![Change::Remove { key }]);
+        true
+    }
+
+    // An existing edge keeps its weight and nothing is recorded
+    pub fn connect(&mut self, from: &T, to: &T) -> bool {
+        self.graph.contains_edge(from, to) || self.set_edge(from, to, Some(1))
+    }
+
+    pub fn disconnect(&mut self, from: &T, to: &T) -> bool {
+        self.set_edge(from, to, None)
+    }
+
+    fn set_edge(&mut self, from: &T, to: &T, weight: Option<i64>) -> bool {
+        let (from, to) = (hash(from), hash(to));
+        if !self.graph.nodes.contains_key(&from) || !self.graph.nodes.contains_key(&to) {
+            return false;
+        }
+        self.record(vec![Change::SetEdge { from, to, weight }]);
+        true
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for UndoableGraph<T> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        self.graph.neighbors(label)
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        self.graph.weight(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_redo() {
+        let mut g = UndoableGraph::new();
+        g.add('a');
+        g.add('b');
+        assert!(g.connect(&'a', &'b'));
+        assert!(!g.connect(&'a', &'c'));
+
+        assert!(g.remove(&'b'));
+        assert_eq!(g.size(), 1);
+        assert!(g.connections(&'a').unwrap().is_empty());
+
+        // Removal brings back the incoming edge too
+        assert!(g.undo());
+        assert!(g.is_connected(&'a', &'b'));
+        assert_eq!(g.indegree(&'b'), Some(1));
+
+        assert!(g.undo());
+        assert!(!g.is_connected(&'a', &'b'));
+
+        assert!(g.redo());
+        assert!(g.is_connected(&'a', &'b'));
+
+        assert!(g.undo());
+        assert!(g.undo());
+        assert!(g.undo());
+        assert!(!g.undo());
+        assert_eq!(g.size(), 0);
+
+        assert!(g.redo());
+        assert_eq!(g.size(), 1);
+
+        // A fresh edit discards the redo history
+        g.add('c');
+        assert!(!g.redo());
+    }
+
+    #[test]
+    fn re_adding_restores_old_node() {
+        let mut g = UndoableGraph::from(Graph::init('a'..='b'));
+        assert!(g.connect(&'a', &'b'));
+        g.add('a');
+        assert!(!g.is_connected(&'a', &'b'));
+        assert_eq!(g.indegree(&'b'), Some(0));
+        assert!(g.undo());
+        assert!(g.is_connected(&'a', &'b'));
+        assert_eq!(g.indegree(&'b'), Some(1));
+    }
+
+    #[test]
+    fn checkpoints() {
+        let mut g = UndoableGraph::from(Graph::init('a'..='c'));
+        assert!(g.connect(&'a', &'b'));
+        g.checkpoint("one");
+        assert!(g.connect(&'b', &'c'));
+        assert!(g.disconnect(&'a', &'b'));
+        g.checkpoint("two");
+        assert!(g.remove(&'c'));
+
+        assert!(g.rollback("one"));
+        assert!(g.is_connected(&'a', &'b'));
+        assert!(!g.is_connected(&'b', &'c'));
+        assert_eq!(g.size(), 3);
+
+        // Undone past "two" so it can't be returned to
+        assert!(!g.rollback("two"));
+        assert!(!g.rollback("three"));
+
+        let g = g.into_inner();
+        assert_eq!(g.edges().count(), 1);
+    }
+}
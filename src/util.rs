@@ -0,0 +1,98 @@
+use crate::graph::*;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+#[derive(Debug, PartialEq)]
+pub enum RootError {
+    NoSources,
+    MultipleSources(usize),
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    pub fn is_dag(&self) -> bool {
+        self.ordering().count() == self.size()
+    }
+
+    pub fn is_weakly_connected(&self) -> bool {
+        self.components().len() == 1
+    }
+
+    // Acyclic with at most one parent per node
+    pub fn is_forest(&self) -> bool {
+        self.indegrees().values().all(|d| *d <= 1) && self.is_dag()
+    }
+
+    // A forest with a single root
+    pub fn is_tree(&self) -> bool {
+        self.is_forest() && self.is_weakly_connected()
+    }
+
+    // The only source, doesn't check the rest is actually a tree
+    pub fn root(&self) -> Result<&T, RootError> {
+        match self.num_sources() {
+            0 => Err(RootError::NoSources),
+            1 => Ok(self.iter_sources().next().unwrap()),
+            n => Err(RootError::MultipleSources(n)),
+        }
+    }
+
+    pub fn leaves(&self) -> HashSet<&T> {
+        self.sinks()
+    }
+
+    // None for a missing node or one with no or several predecessors
+    pub fn parent(&self, label: &T) -> Option<&T> {
+        let incoming = &self.get(label)?.incoming;
+        if incoming.len() != 1 {
+            return None;
+        }
+        let parent = incoming.keys().next()?;
+        Some(&self.nodes[parent].label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predicates() {
+        let g = Graph::star_graph(4);
+        assert!(g.is_dag() && g.is_forest() && g.is_tree() && g.is_weakly_connected());
+
+        let g = Graph::grid_graph(2, 2);
+        assert!(g.is_dag() && g.is_weakly_connected());
+        assert!(!g.is_forest() && !g.is_tree());
+
+        let g = Graph::cycle_graph(3);
+        assert!(!g.is_dag() && !g.is_forest() && !g.is_tree());
+        assert!(g.is_weakly_connected());
+
+        let mut g = Graph::path_graph(2);
+        g.add(2);
+        assert!(g.is_forest() && !g.is_tree() && !g.is_weakly_connected());
+
+        let g: Graph<usize> = Graph::new();
+        assert!(g.is_dag() && g.is_forest() && !g.is_tree());
+    }
+
+    #[test]
+    fn roots_and_leaves() {
+        let mut g = Graph::init('a'..='e');
+        for (a, b) in [('a', 'b'), ('a', 'c'), ('c', 'd'), ('c', 'e')] {
+            assert!(g.connect(&a, &b));
+        }
+        assert_eq!(g.root(), Ok(&'a'));
+        assert_eq!(g.leaves(), HashSet::from([&'b', &'d', &'e']));
+        assert_eq!(g.parent(&'d'), Some(&'c'));
+        assert_eq!(g.parent(&'a'), None);
+        assert_eq!(g.parent(&'z'), None);
+
+        assert!(g.connect(&'b', &'d'));
+        assert_eq!(g.parent(&'d'), None);
+
+        g.add('f');
+        assert_eq!(g.root(), Err(RootError::MultipleSources(2)));
+        assert_eq!(Graph::cycle_graph(3).root(), Err(RootError::NoSources));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::collections::HashSet;
+use crate::{base::GraphBase, graph::*, hash};
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+// Every edge flipped, without copying any nodes
+pub struct ReversedView<'a, T> {
+    graph: &'a Graph<T>,
+}
+
+// Only the nodes and edges passing the predicates
+pub struct FilteredView<'a, T, N, E> {
+    graph: &'a Graph<T>,
+    node: N,
+    edge: E,
+}
+
+// Only the given nodes and the edges between them
+pub struct SubgraphView<'a, T> {
+    graph: &'a Graph<T>,
+    keys: HashSet<u64>,
+}
+
+impl<T> Graph<T> {
+    pub fn reversed(&self) -> ReversedView<'_, T> {
+        ReversedView { graph: self }
+    }
+
+    pub fn filtered<N, E>(&self, node: N, edge: E) -> FilteredView<'_, T, N, E>
+    where
+        N: Fn(&T) -> bool,
+        E: Fn(&T, &T) -> bool,
+    {
+        FilteredView {
+            graph: self,
+            node,
+            edge,
+        }
+    }
+}
+
+impl<T: Hash + Eq> Graph<T> {
+    pub fn subgraph_view<'a, I>(&self, labels: I) -> SubgraphView<'_, T>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        let keys = labels
+            .into_iter()
+            .map(hash)
+            .filter(|k| self.nodes.contains_key(k))
+            .collect();
+        SubgraphView { graph: self, keys }
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for ReversedView<'_, T> {
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.graph.labels()
+    }
+
+    // Read straight off the reverse index each node keeps
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        match self.graph.nodes.get(&hash(label)) {
+            Some(node) => node
+                .incoming
+                .keys()
+                .map(|k| &self.graph.nodes[k].label)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        let node = self.graph.nodes.get(&hash(from))?;
+        node.incoming.get(&hash(to)).copied()
+    }
+}
+
+impl<T, N, E> GraphBase<T> for FilteredView<'_, T, N, E>
+where
+    T: Hash + Eq,
+    N: Fn(&T) -> bool,
+    E: Fn(&T, &T) -> bool,
+{
+    fn contains(&self, label: &T) -> bool {
+        self.graph.contains(label) && (self.node)(label)
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        let mut labels = self.graph.labels();
+        labels.retain(|l| (self.node)(l));
+        labels
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        if !self.contains(label) {
+            return Vec::new();
+        }
+        let mut neighbors = self.graph.neighbors(label);
+        neighbors.retain(|n| (self.node)(n) && (self.edge)(label, n));
+        neighbors
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        if self.contains(from) && self.contains(to) && (self.edge)(from, to) {
+            self.graph.weight(from, to)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Hash + Eq> GraphBase<T> for SubgraphView<'_, T> {
+    fn contains(&self, label: &T) -> bool {
+        self.keys.contains(&hash(label))
+    }
+
+    fn labels(&self) -> Vec<&T> {
+        self.keys
+            .iter()
+            .map(|k| &self.graph.nodes[k].label)
+            .collect()
+    }
+
+    fn neighbors(&self, label: &T) -> Vec<&T> {
+        if !self.contains(label) {
+            return Vec::new();
+        }
+        let mut neighbors = self.graph.neighbors(label);
+        neighbors.retain(|n| self.contains(n));
+        neighbors
+    }
+
+    fn weight(&self, from: &T, to: &T) -> Option<i64> {
+        if self.contains(from) && self.contains(to) {
+            self.graph.weight(from, to)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reversed() {
+        let g = Graph::path_graph(4);
+        let r = g.reversed();
+        assert_eq!(r.neighbors(&2), vec![&1]);
+        assert!(r.neighbors(&0).is_empty());
+        assert_eq!(r.bfs(&3).collect::<Vec<_>>(), vec![&3, &2, &1, &0]);
+        assert_eq!(r.labels().len(), 4);
+    }
+
+    #[test]
+    fn filtered() {
+        let g = Graph::grid_graph(3, 3);
+
+        // Drop the centre and every downward edge
+        let f = g.filtered(|n| *n != 4, |a, b| b - a == 1);
+        assert!(!f.contains(&4) && f.contains(&5));
+        assert_eq!(f.labels().len(), 8);
+        assert_eq!(f.neighbors(&3), Vec::<&usize>::new());
+        assert_eq!(f.bfs(&0).count(), 3);
+        assert!(f.neighbors(&4).is_empty());
+    }
+
+    #[test]
+    fn subgraph() {
+        let g = Graph::cycle_graph(4);
+        let s = g.subgraph_view(&[0, 1, 2, 9]);
+        assert!(s.contains(&1) && !s.contains(&3) && !s.contains(&9));
+        assert_eq!(s.dfs(&0).count(), 3);
+        assert!(s.neighbors(&2).is_empty());
+        assert!(s.neighbors(&3).is_empty());
+    }
+}
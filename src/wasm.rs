@@ -0,0 +1,79 @@
+use crate::graph::*;
+use wasm_bindgen::prelude::*;
+
+// String-labelled graph exported to JavaScript
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmGraph {
+    graph: Graph<String>,
+}
+
+#[wasm_bindgen]
+impl WasmGraph {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn size(&self) -> usize {
+        self.graph.size()
+    }
+
+    pub fn add(&mut self, label: String) {
+        self.graph.add(label);
+    }
+
+    pub fn remove(&mut self, label: &str) -> bool {
+        self.graph.remove(&label.to_owned()).is_some()
+    }
+
+    pub fn connect(&mut self, from: &str, to: &str) -> bool {
+        self.graph.connect(&from.to_owned(), &to.to_owned())
+    }
+
+    pub fn disconnect(&mut self, from: &str, to: &str) -> bool {
+        self.graph.disconnect(&from.to_owned(), &to.to_owned())
+    }
+
+    #[wasm_bindgen(js_name = isConnected)]
+    pub fn is_connected(&self, from: &str, to: &str) -> bool {
+        self.graph.is_connected(&from.to_owned(), &to.to_owned())
+    }
+
+    // Nodes on or behind a cycle are left out, as with Graph::ordering
+    pub fn ordering(&self) -> Vec<String> {
+        self.graph.ordering().cloned().collect()
+    }
+
+    // Labels along the cheapest path, undefined if there isn't one
+    #[wasm_bindgen(js_name = shortestPath)]
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let (_, path) = self.graph.shortest_path(&from.to_owned(), &to.to_owned())?;
+        Some(path.into_iter().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapper() {
+        let mut g = WasmGraph::new();
+        for label in ["a", "b", "c"] {
+            g.add(label.to_owned());
+        }
+        assert!(g.connect("a", "b"));
+        assert!(g.connect("b", "c"));
+        assert!(!g.connect("a", "z"));
+        assert!(g.is_connected("a", "b"));
+
+        assert_eq!(g.ordering(), vec!["a", "b", "c"]);
+        assert_eq!(g.shortest_path("a", "c").unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(g.shortest_path("c", "a"), None);
+
+        assert!(g.disconnect("a", "b"));
+        assert!(g.remove("c"));
+        assert_eq!(g.size(), 2);
+    }
+}